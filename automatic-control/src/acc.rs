@@ -0,0 +1,133 @@
+//! Adaptive cruise control using a front-facing radar.
+
+use crate::{
+    counter::{FrameCount, FrameCounter},
+    ego::EgoState,
+};
+use anyhow::Result;
+use carla::{
+    client::{Sensor, Vehicle, World},
+    prelude::*,
+    sensor::data::RadarMeasurement,
+};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use std::sync::{Arc, Mutex};
+
+/// The mounting height of the radar above the vehicle origin in
+/// meters.
+const RADAR_HEIGHT: f32 = 1.0;
+
+/// Detections below this height above the ground are treated as road
+/// surface returns.
+const MIN_TARGET_HEIGHT: f32 = 0.3;
+
+/// Detections farther than this lateral offset in meters are treated
+/// as out of the ego lane.
+const HALF_LANE_WIDTH: f32 = 1.75;
+
+/// The gain from the gap error in meters to the speed correction in
+/// m/s.
+const GAP_GAIN: f32 = 0.3;
+
+/// The nearest object detected in the ego lane.
+#[derive(Debug, Clone, Copy)]
+pub struct LeadVehicle {
+    /// The distance to the object in meters.
+    pub distance: f32,
+    /// The radial velocity in m/s of the object relative to the
+    /// radar. It is negative when approaching.
+    pub relative_velocity: f32,
+}
+
+/// Keeps a time gap to the lead vehicle by lowering the target speed.
+pub struct AdaptiveCruise {
+    /// The desired time gap in seconds.
+    pub time_gap: f32,
+    /// The minimum distance in meters kept from the lead vehicle.
+    pub min_distance: f32,
+    radar: Sensor,
+    frames: FrameCounter,
+    lead: Arc<Mutex<Option<LeadVehicle>>>,
+}
+
+impl AdaptiveCruise {
+    /// Attach a radar to the front of the vehicle.
+    pub fn new(
+        world: &mut World,
+        vehicle: &Vehicle,
+        time_gap: f32,
+        min_distance: f32,
+    ) -> Result<Self> {
+        let pose = Isometry3::from_parts(
+            Translation3::new(2.5, 0.0, RADAR_HEIGHT),
+            UnitQuaternion::identity(),
+        );
+        let radar: Sensor = world
+            .actor_builder("sensor.radar.ranging")?
+            .set_attribute("horizontal_fov", "30")?
+            .set_attribute("vertical_fov", "10")?
+            .set_attribute("range", "100")?
+            .spawn_sensor_opt(&pose, Some(vehicle), None)?;
+
+        let frames = FrameCounter::default();
+        let lead = Arc::new(Mutex::new(None));
+        {
+            let frames = frames.clone();
+            let lead = lead.clone();
+            radar.listen(move |data| {
+                frames.record(data.frame());
+                let measure: RadarMeasurement = data.try_into().unwrap();
+
+                let nearest = measure
+                    .as_slice()
+                    .iter()
+                    .filter(|det| {
+                        let lateral = det.depth * det.azimuth.sin();
+                        let height = RADAR_HEIGHT + det.depth * det.altitude.sin();
+                        lateral.abs() <= HALF_LANE_WIDTH && height >= MIN_TARGET_HEIGHT
+                    })
+                    .min_by(|lhs, rhs| lhs.depth.total_cmp(&rhs.depth))
+                    .map(|det| LeadVehicle {
+                        distance: det.depth,
+                        relative_velocity: det.velocity,
+                    });
+
+                *lead.lock().unwrap() = nearest;
+            });
+        }
+
+        Ok(Self {
+            time_gap,
+            min_distance,
+            radar,
+            frames,
+            lead,
+        })
+    }
+
+    /// The measurements received by the sensor.
+    pub fn frames(&self) -> FrameCount {
+        self.frames.get()
+    }
+
+    /// The lead vehicle from the latest radar measurement.
+    pub fn lead(&self) -> Option<LeadVehicle> {
+        *self.lead.lock().unwrap()
+    }
+
+    /// Compute the maximum speed in m/s to keep the time gap. It
+    /// returns `None` if no lead vehicle is detected.
+    pub fn max_speed(&self, ego: &EgoState) -> Option<f32> {
+        let lead = self.lead()?;
+        let lead_speed = (ego.speed + lead.relative_velocity).max(0.0);
+        let desired_gap = self.min_distance + self.time_gap * ego.speed;
+        let speed = lead_speed + GAP_GAIN * (lead.distance - desired_gap);
+        Some(speed.max(0.0))
+    }
+}
+
+impl Drop for AdaptiveCruise {
+    fn drop(&mut self) {
+        self.radar.stop();
+    }
+}
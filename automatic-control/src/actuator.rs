@@ -0,0 +1,227 @@
+//! Control backends that turn the planned command into vehicle
+//! controls.
+
+use crate::calibration::PedalMap;
+use carla::{
+    client::{ActorBase, Vehicle},
+    rpc::{VehicleAckermannControl, VehicleControl},
+};
+use clap::ValueEnum;
+use std::collections::VecDeque;
+
+/// The command produced by the planner at one tick.
+#[derive(Debug, Clone, Copy)]
+pub struct Command {
+    /// The front wheel angle in radians, positive to the right.
+    pub steer: f32,
+    /// The reference speed in m/s.
+    pub speed: f32,
+    /// The acceleration in m/s². It is negative when braking.
+    pub acceleration: f32,
+    /// The magnitude of the reference jerk in m/s³.
+    pub jerk: f32,
+    /// The simulation time in seconds when the command is issued.
+    pub timestamp: f64,
+    /// Whether to drive in reverse gear.
+    pub reverse: bool,
+    /// Whether to hold the vehicle by the handbrake.
+    pub hand_brake: bool,
+}
+
+/// Applies planner commands to a vehicle.
+pub trait Actuator {
+    fn apply(&mut self, vehicle: &Vehicle, command: &Command);
+
+    /// Drop the pending commands.
+    fn reset(&mut self) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ControlMode {
+    /// Drive by `VehicleAckermannControl`.
+    Ackermann,
+    /// Drive by the throttle, brake and steer of `VehicleControl`.
+    Throttle,
+}
+
+/// Drives the vehicle by the Ackermann control and brakes by
+/// `VehicleControl` since the Ackermann control can only coast down
+/// towards the target speed.
+#[derive(Debug, Clone)]
+pub struct AckermannActuator {
+    /// The steering speed in rad/s.
+    pub steer_speed: f32,
+    /// The maximum steering angle in radians.
+    pub max_steer_angle: f32,
+    /// The deceleration in m/s² mapped to the full brake.
+    pub max_deceleration: f32,
+    /// The deceleration in m/s² above which the brake is applied.
+    pub brake_deadband: f32,
+}
+
+impl Actuator for AckermannActuator {
+    fn apply(&mut self, vehicle: &Vehicle, command: &Command) {
+        if command.hand_brake {
+            vehicle.apply_control(&hold_control(
+                command.steer / self.max_steer_angle,
+                command.reverse,
+            ));
+        } else if command.acceleration < -self.brake_deadband {
+            vehicle.apply_control(&brake_control(
+                command.steer / self.max_steer_angle,
+                -command.acceleration / self.max_deceleration,
+                command.reverse,
+            ));
+        } else {
+            // The Ackermann control reverses on negative speeds.
+            let speed = if command.reverse {
+                -command.speed
+            } else {
+                command.speed
+            };
+            let control = VehicleAckermannControl {
+                steer: command.steer,
+                steer_speed: self.steer_speed,
+                speed,
+                acceleration: command.acceleration.max(0.0),
+                jerk: command.jerk,
+            };
+            vehicle.apply_ackermann_control(&control);
+        }
+    }
+}
+
+/// Drives the vehicle by the classic throttle, brake and steer
+/// control.
+#[derive(Debug, Clone)]
+pub struct ThrottleActuator {
+    /// The maximum steering angle in radians.
+    pub max_steer_angle: f32,
+    /// The acceleration in m/s² mapped to the full throttle.
+    pub max_acceleration: f32,
+    /// The deceleration in m/s² mapped to the full brake.
+    pub max_deceleration: f32,
+    /// The deceleration in m/s² above which the brake is applied.
+    pub brake_deadband: f32,
+    /// The calibrated pedal map used instead of the linear mapping.
+    pub pedal_map: Option<PedalMap>,
+}
+
+impl Actuator for ThrottleActuator {
+    fn apply(&mut self, vehicle: &Vehicle, command: &Command) {
+        let steer = command.steer / self.max_steer_angle;
+
+        if command.hand_brake {
+            vehicle.apply_control(&hold_control(steer, command.reverse));
+            return;
+        }
+
+        if let Some(map) = &self.pedal_map {
+            let pedal = map.pedal(vehicle.velocity().norm(), command.acceleration);
+            let control = if pedal < 0.0 {
+                brake_control(steer, -pedal, command.reverse)
+            } else {
+                throttle_control(steer, pedal, command.reverse)
+            };
+            vehicle.apply_control(&control);
+            return;
+        }
+
+        let control = if command.acceleration < -self.brake_deadband {
+            brake_control(
+                steer,
+                -command.acceleration / self.max_deceleration,
+                command.reverse,
+            )
+        } else {
+            throttle_control(
+                steer,
+                command.acceleration / self.max_acceleration,
+                command.reverse,
+            )
+        };
+        vehicle.apply_control(&control);
+    }
+}
+
+/// Emulates the actuator latency by holding the commands for a fixed
+/// number of ticks before passing them to the inner actuator.
+pub struct DelayedActuator {
+    /// The number of ticks each command is delayed.
+    pub delay_ticks: usize,
+    /// Print the issued and applied timestamps of every command.
+    pub log: bool,
+    inner: Box<dyn Actuator>,
+    queue: VecDeque<Command>,
+}
+
+impl DelayedActuator {
+    pub fn new(inner: Box<dyn Actuator>, delay_ticks: usize, log: bool) -> Self {
+        Self {
+            delay_ticks,
+            log,
+            inner,
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Actuator for DelayedActuator {
+    fn apply(&mut self, vehicle: &Vehicle, command: &Command) {
+        self.queue.push_back(*command);
+
+        while self.queue.len() > self.delay_ticks {
+            let delayed = self.queue.pop_front().unwrap();
+            if self.log {
+                println!(
+                    "actuator commanded={:.3} applied={:.3} delay={:.3}",
+                    delayed.timestamp,
+                    command.timestamp,
+                    command.timestamp - delayed.timestamp
+                );
+            }
+            self.inner.apply(vehicle, &delayed);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.queue.clear();
+        self.inner.reset();
+    }
+}
+
+/// The control with the normalized throttle and steer.
+fn throttle_control(steer: f32, throttle: f32, reverse: bool) -> VehicleControl {
+    VehicleControl {
+        throttle: throttle.clamp(0.0, 1.0),
+        steer: steer.clamp(-1.0, 1.0),
+        brake: 0.0,
+        hand_brake: false,
+        reverse,
+        manual_gear_shift: false,
+        gear: 0,
+    }
+}
+
+/// The control that brakes with the normalized strength while
+/// keeping the normalized steer.
+fn brake_control(steer: f32, brake: f32, reverse: bool) -> VehicleControl {
+    VehicleControl {
+        throttle: 0.0,
+        steer: steer.clamp(-1.0, 1.0),
+        brake: brake.clamp(0.0, 1.0),
+        hand_brake: false,
+        reverse,
+        manual_gear_shift: false,
+        gear: 0,
+    }
+}
+
+/// The control that holds the vehicle still by the brake and the
+/// handbrake.
+fn hold_control(steer: f32, reverse: bool) -> VehicleControl {
+    VehicleControl {
+        hand_brake: true,
+        ..brake_control(steer, 1.0, reverse)
+    }
+}
@@ -0,0 +1,143 @@
+//! Automatic emergency braking using the obstacle detection sensor.
+
+use crate::{
+    counter::{FrameCount, FrameCounter},
+    ego::EgoState,
+};
+use anyhow::Result;
+use carla::{
+    client::{ActorBase, Sensor, Vehicle, World},
+    prelude::*,
+    rpc::{ActorId, VehicleControl},
+    sensor::data::ObstacleDetectionEvent,
+};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use std::sync::{Arc, Mutex};
+
+/// The number of frames without detections after which the path is
+/// considered clear.
+const CLEAR_FRAMES: usize = 20;
+
+/// The obstacle reported by the latest detection event.
+#[derive(Debug, Clone)]
+struct Obstacle {
+    frame: usize,
+    id: ActorId,
+    type_id: String,
+    distance: f32,
+}
+
+/// Overrides the controller with full braking when a collision is
+/// imminent.
+pub struct EmergencyBrake {
+    /// The time-to-collision threshold in seconds.
+    pub ttc: f32,
+    /// The distance in meters below which the brake is always
+    /// engaged.
+    pub min_distance: f32,
+    sensor: Sensor,
+    frames: FrameCounter,
+    obstacle: Arc<Mutex<Option<Obstacle>>>,
+    engaged: bool,
+}
+
+impl EmergencyBrake {
+    /// Attach an obstacle detector that looks `distance` meters ahead
+    /// of the vehicle.
+    pub fn new(
+        world: &mut World,
+        vehicle: &Vehicle,
+        distance: f32,
+        ttc: f32,
+        min_distance: f32,
+    ) -> Result<Self> {
+        let pose =
+            Isometry3::from_parts(Translation3::new(2.5, 0.0, 0.7), UnitQuaternion::identity());
+        let sensor: Sensor = world
+            .actor_builder("sensor.other.obstacle")?
+            .set_attribute("distance", &distance.to_string())?
+            .set_attribute("hit_radius", "0.5")?
+            .set_attribute("only_dynamics", "false")?
+            .spawn_sensor_opt(&pose, Some(vehicle), None)?;
+
+        let frames = FrameCounter::default();
+        let obstacle = Arc::new(Mutex::new(None));
+        {
+            let frames = frames.clone();
+            let obstacle = obstacle.clone();
+            sensor.listen(move |data| {
+                let frame = data.frame();
+                frames.record(frame);
+                let event: ObstacleDetectionEvent = data.try_into().unwrap();
+                let other = event.other_actor();
+
+                *obstacle.lock().unwrap() = Some(Obstacle {
+                    frame,
+                    id: other.id(),
+                    type_id: other.type_id(),
+                    distance: event.distance(),
+                });
+            });
+        }
+
+        Ok(Self {
+            ttc,
+            min_distance,
+            sensor,
+            frames,
+            obstacle,
+            engaged: false,
+        })
+    }
+
+    /// The measurements received by the sensor.
+    pub fn frames(&self) -> FrameCount {
+        self.frames.get()
+    }
+
+    /// Update the brake state with the latest detection and return
+    /// whether the brake is engaged.
+    pub fn update(&mut self, ego: &EgoState, frame: usize) -> bool {
+        let obstacle = self.obstacle.lock().unwrap().clone();
+        let obstacle = obstacle.filter(|obstacle| frame <= obstacle.frame + CLEAR_FRAMES);
+
+        match (self.engaged, obstacle) {
+            (false, Some(obstacle)) => {
+                let ttc = obstacle.distance / ego.speed.max(f32::EPSILON);
+                if ttc < self.ttc || obstacle.distance < self.min_distance {
+                    eprintln!(
+                        "AEB engaged at frame {frame}: {} ({}) at {:.1} m, TTC {ttc:.2} s",
+                        obstacle.type_id, obstacle.id, obstacle.distance
+                    );
+                    self.engaged = true;
+                }
+            }
+            (true, None) => {
+                eprintln!("AEB released at frame {frame}: the path is clear");
+                self.engaged = false;
+            }
+            _ => {}
+        }
+
+        self.engaged
+    }
+
+    /// The control that brings the vehicle to a full stop.
+    pub fn control() -> VehicleControl {
+        VehicleControl {
+            throttle: 0.0,
+            steer: 0.0,
+            brake: 1.0,
+            hand_brake: true,
+            reverse: false,
+            manual_gear_shift: false,
+            gear: 0,
+        }
+    }
+}
+
+impl Drop for EmergencyBrake {
+    fn drop(&mut self) {
+        self.sensor.stop();
+    }
+}
@@ -0,0 +1,334 @@
+//! Behaviors that limit the target speed of the vehicle.
+
+use crate::{
+    ego::EgoState,
+    events::Event,
+    landmarks::{LandmarkAhead, STOP_SIGN_TYPE},
+    route,
+};
+use carla::{
+    client::{ActorBase, TrafficLight, Vehicle, Waypoint, World},
+    rpc::{ActorId, TrafficLightState},
+};
+use nalgebra::Point2;
+use noisy_float::prelude::*;
+
+/// Stops the vehicle in front of red and yellow traffic lights on the
+/// lane ahead and resumes on green.
+#[derive(Debug, Clone)]
+pub struct TrafficLightBehavior {
+    /// The distance in meters along the lane to look for traffic
+    /// lights.
+    pub lookahead: f64,
+    /// The distance in meters kept before the stop line.
+    pub stop_margin: f32,
+    /// The deceleration in m/s² used to plan the stop.
+    pub deceleration: f32,
+    stopped_at: Option<ActorId>,
+    event: Option<Event>,
+}
+
+impl TrafficLightBehavior {
+    pub fn new(lookahead: f64, stop_margin: f32, deceleration: f32) -> Self {
+        Self {
+            lookahead,
+            stop_margin,
+            deceleration,
+            stopped_at: None,
+            event: None,
+        }
+    }
+
+    /// Take the stop or the resume decided by the last call of
+    /// [`max_speed`](Self::max_speed).
+    pub fn take_event(&mut self) -> Option<Event> {
+        self.event.take()
+    }
+
+    /// Compute the maximum speed in m/s allowed to stop in front of
+    /// the traffic light ahead. It returns `None` if the vehicle is
+    /// not required to stop.
+    pub fn max_speed(&mut self, world: &World, ego: &EgoState, waypoint: &Waypoint) -> Option<f32> {
+        let lights = world.traffic_lights_from_waypoint(waypoint, self.lookahead);
+
+        let stop = (0..lights.len())
+            .filter_map(|index| lights.get(index))
+            .filter_map(|actor| TrafficLight::try_from(actor).ok())
+            .filter_map(|light| {
+                let state = light.state();
+                if !matches!(state, TrafficLightState::Red | TrafficLightState::Yellow) {
+                    return None;
+                }
+                let distance = distance_to_stop_line(&light, ego)?;
+                let distance = (distance - self.stop_margin).max(0.0);
+
+                // Pass the yellow light if it is too late to stop
+                // comfortably.
+                let required = ego.speed.powi(2) / (2.0 * distance.max(f32::EPSILON));
+                if state == TrafficLightState::Yellow
+                    && self.stopped_at != Some(light.id())
+                    && required > self.deceleration
+                {
+                    return None;
+                }
+
+                Some((light, state, distance))
+            })
+            .min_by_key(|(_, _, distance)| r32(*distance));
+
+        match stop {
+            Some((light, state, distance)) => {
+                if self.stopped_at != Some(light.id()) {
+                    let state = light_state_name(state);
+                    eprintln!(
+                        "Stop for the {state} traffic light {} in {distance:.1} m",
+                        light.id()
+                    );
+                    self.stopped_at = Some(light.id());
+                    self.event = Some(Event::TrafficLightStop {
+                        light_id: light.id(),
+                        state,
+                        distance,
+                    });
+                }
+                Some((2.0 * self.deceleration * distance).sqrt())
+            }
+            None => {
+                if let Some(id) = self.stopped_at.take() {
+                    eprintln!("Resume after the traffic light {id}");
+                    self.event = Some(Event::TrafficLightResume { light_id: id });
+                }
+                None
+            }
+        }
+    }
+}
+
+/// The OpenDRIVE signal type of maximum speed signs.
+const SPEED_LIMIT_SIGNAL_TYPE: &str = "274";
+
+/// Caps the target speed by the posted speed limit of the current
+/// road and slows down ahead of lower limits on the lane.
+#[derive(Debug, Clone)]
+pub struct SpeedLimitBehavior {
+    /// The distance in meters along the lane to look for speed limit
+    /// signs.
+    pub lookahead: f64,
+    /// The percentage added to the posted speed limit.
+    pub offset_percent: f32,
+    /// The deceleration in m/s² used to slow down for lower limits.
+    pub deceleration: f32,
+    current_limit: Option<f32>,
+}
+
+impl SpeedLimitBehavior {
+    pub fn new(lookahead: f64, offset_percent: f32, deceleration: f32) -> Self {
+        Self {
+            lookahead,
+            offset_percent,
+            deceleration,
+            current_limit: None,
+        }
+    }
+
+    /// Compute the maximum speed in m/s allowed by the speed limits.
+    pub fn max_speed(&mut self, vehicle: &Vehicle, waypoint: &Waypoint) -> Option<f32> {
+        let scale = 1.0 + self.offset_percent / 100.0;
+
+        // The limit reported by the vehicle is in km/h.
+        let current_limit = vehicle.cxx_actor().to_vehicle().GetSpeedLimit() / 3.6;
+        if self.current_limit != Some(current_limit) {
+            eprintln!("Speed limit changed to {:.0} km/h", current_limit * 3.6);
+            self.current_limit = Some(current_limit);
+        }
+
+        // Plan the deceleration to arrive at the upcoming limits.
+        let upcoming = waypoint
+            .landmarks_of_type_in_distance(self.lookahead, SPEED_LIMIT_SIGNAL_TYPE, false)
+            .iter()
+            .filter_map(|landmark| {
+                let limit = match landmark.unit().as_str() {
+                    "mph" => landmark.value() * 1.609_344 / 3.6,
+                    _ => landmark.value() / 3.6,
+                } as f32;
+                if limit <= 0.0 {
+                    return None;
+                }
+                let distance = landmark.distance() as f32;
+                Some(((limit * scale).powi(2) + 2.0 * self.deceleration * distance).sqrt())
+            })
+            .min_by_key(|&speed| r32(speed));
+
+        let current = (current_limit > 0.0).then_some(current_limit * scale);
+        match (current, upcoming) {
+            (Some(lhs), Some(rhs)) => Some(lhs.min(rhs)),
+            (lhs, rhs) => lhs.or(rhs),
+        }
+    }
+}
+
+/// The speed in m/s below which the vehicle stands at a stop sign.
+const STANDSTILL_SPEED: f32 = 0.1;
+
+/// The distance in meters from the stop point within which the vehicle
+/// counts as stopped at the stop sign.
+const STOP_TOLERANCE: f32 = 2.0;
+
+/// Stops the vehicle at the stop signs ahead on the lane for a while
+/// before driving on.
+#[derive(Debug, Clone)]
+pub struct StopSignBehavior {
+    /// The distance in meters kept before the sign.
+    pub stop_margin: f32,
+    /// The deceleration in m/s² used to plan the stop.
+    pub deceleration: f32,
+    /// The time in seconds to stand at the sign.
+    pub wait: f32,
+    /// The sign to stop at and the time stood there.
+    stopping: Option<(String, f32)>,
+    /// The last sign stopped at, which is ignored while it is still
+    /// ahead.
+    passed: Option<String>,
+    event: Option<Event>,
+}
+
+impl StopSignBehavior {
+    pub fn new(stop_margin: f32, deceleration: f32, wait: f32) -> Self {
+        Self {
+            stop_margin,
+            deceleration,
+            wait,
+            stopping: None,
+            passed: None,
+            event: None,
+        }
+    }
+
+    /// Take the stop or the resume decided by the last call of
+    /// [`max_speed`](Self::max_speed).
+    pub fn take_event(&mut self) -> Option<Event> {
+        self.event.take()
+    }
+
+    /// Compute the maximum speed in m/s allowed to stop at the stop
+    /// sign among the landmarks ahead. It returns `None` if the
+    /// vehicle is not required to stop.
+    pub fn max_speed(
+        &mut self,
+        landmarks: &[LandmarkAhead],
+        ego: &EgoState,
+        dt: f32,
+    ) -> Option<f32> {
+        let sign = landmarks.iter().find(|landmark| {
+            landmark.type_code == STOP_SIGN_TYPE && self.passed.as_ref() != Some(&landmark.id)
+        });
+        let Some(sign) = sign else {
+            self.stopping = None;
+            return None;
+        };
+        let distance = (sign.distance - self.stop_margin).max(0.0);
+
+        let waited = match &mut self.stopping {
+            Some((id, waited)) if *id == sign.id => waited,
+            _ => {
+                eprintln!("Stop at the stop sign {} in {distance:.1} m", sign.id);
+                self.event = Some(Event::StopSignStop {
+                    landmark_id: sign.id.clone(),
+                    distance,
+                });
+                &mut self.stopping.insert((sign.id.clone(), 0.0)).1
+            }
+        };
+        if ego.speed < STANDSTILL_SPEED && distance < STOP_TOLERANCE {
+            *waited += dt;
+            if *waited >= self.wait {
+                eprintln!("Resume after the stop sign {}", sign.id);
+                self.event = Some(Event::StopSignResume {
+                    landmark_id: sign.id.clone(),
+                    duration: *waited,
+                });
+                self.passed = Some(sign.id.clone());
+                self.stopping = None;
+                return None;
+            }
+        }
+        Some((2.0 * self.deceleration * distance).sqrt())
+    }
+}
+
+/// The spacing in meters of the waypoints used to estimate the lane
+/// curvature.
+const CURVATURE_STEP: f64 = 2.0;
+
+/// Slows down ahead of sharp turns so that the lateral acceleration
+/// stays within a limit.
+#[derive(Debug, Clone)]
+pub struct CurvatureBehavior {
+    /// The distance in meters along the lane to inspect.
+    pub lookahead: f64,
+    /// The maximum lateral acceleration in m/s².
+    pub max_lateral_acceleration: f32,
+    /// The deceleration in m/s² used to slow down before turns.
+    pub deceleration: f32,
+}
+
+impl CurvatureBehavior {
+    pub fn new(lookahead: f64, max_lateral_acceleration: f32, deceleration: f32) -> Self {
+        Self {
+            lookahead,
+            max_lateral_acceleration,
+            deceleration,
+        }
+    }
+
+    /// Compute the maximum speed in m/s that allows the vehicle to
+    /// decelerate to the curve speed of every turn ahead.
+    pub fn max_speed(&self, waypoint: &Waypoint) -> Option<f32> {
+        let waypoints = route::lane_ahead(waypoint, CURVATURE_STEP, self.lookahead);
+        let curvatures = route::curvatures(&waypoints, CURVATURE_STEP);
+
+        curvatures
+            .iter()
+            .enumerate()
+            .filter(|(_, curvature)| curvature.abs() > f32::EPSILON)
+            .map(|(index, curvature)| {
+                let curve_speed_sq = self.max_lateral_acceleration / curvature.abs();
+                let distance = index as f32 * CURVATURE_STEP as f32;
+                (curve_speed_sq + 2.0 * self.deceleration * distance).sqrt()
+            })
+            .min_by_key(|&speed| r32(speed))
+    }
+}
+
+/// Compute the distance to the nearest stop line of the traffic light
+/// that lies ahead of the vehicle in the same direction.
+fn distance_to_stop_line(light: &TrafficLight, ego: &EgoState) -> Option<f32> {
+    let position = ego.position();
+    let heading = ego.heading();
+
+    light
+        .stop_waypoints()
+        .iter()
+        .filter_map(|waypoint| {
+            let transform = waypoint.transform();
+            let (_, _, yaw) = transform.rotation.euler_angles();
+            let point = Point2::new(transform.translation.x, transform.translation.y);
+            let dir = point - position;
+
+            let same_direction = (yaw - ego.yaw).cos() > 0.0;
+            let ahead = dir.dot(&heading) > 0.0;
+            (same_direction && ahead).then(|| dir.norm())
+        })
+        .min_by_key(|&distance| r32(distance))
+}
+
+/// The human-readable name of a traffic light state.
+pub fn light_state_name(state: TrafficLightState) -> &'static str {
+    match state {
+        TrafficLightState::Red => "red",
+        TrafficLightState::Yellow => "yellow",
+        TrafficLightState::Green => "green",
+        TrafficLightState::Off => "off",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,246 @@
+//! Sweep the throttle and brake pedals and record the resulting
+//! accelerations into a pedal map.
+//!
+//! The map is a CSV file with the header `speed,pedal,acceleration`.
+//! The speed is the center of the speed bin in m/s, the pedal is the
+//! throttle in `[0, 1]` or the negated brake in `[-1, 0)`, and the
+//! acceleration is the mean longitudinal acceleration in m/s².
+
+use anyhow::{ensure, Context, Result};
+use carla::{
+    client::{ActorBase, Client, Map, Vehicle, World},
+    rpc::{EpisodeSettings, VehicleControl},
+};
+use clap::Parser;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The simulation time step in seconds.
+const FIXED_DELTA_SECONDS: f64 = 0.05;
+
+/// The number of ticks to let the vehicle settle after a reset.
+const SETTLE_TICKS: usize = 20;
+
+/// The speed in m/s below which the vehicle is considered stopped.
+const STOP_SPEED: f32 = 0.5;
+
+/// The gain from the heading error to the normalized steer.
+const STEER_GAIN: f32 = 1.5;
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(long, default_value = "localhost")]
+    pub addr: String,
+
+    #[clap(long, default_value = "2000")]
+    pub port: u16,
+
+    #[clap(long)]
+    pub world: Option<String>,
+
+    /// The blueprint of the calibrated vehicle.
+    #[clap(long, default_value = "vehicle.tesla.model3")]
+    pub blueprint: String,
+
+    /// The output pedal map.
+    #[clap(long, default_value = "pedal_map.csv")]
+    pub output: PathBuf,
+
+    /// The increment of the pedal values in the sweep.
+    #[clap(long, default_value = "0.1")]
+    pub pedal_step: f32,
+
+    /// The width in m/s of the speed bins.
+    #[clap(long, default_value = "2.0")]
+    pub speed_bin: f32,
+
+    /// The duration in seconds of each throttle run.
+    #[clap(long, default_value = "10.0")]
+    pub duration: f32,
+
+    /// The speed in m/s reached by full throttle before each brake
+    /// run.
+    #[clap(long, default_value = "15.0")]
+    pub brake_speed: f32,
+}
+
+/// The accumulated accelerations of a speed bin and pedal value.
+#[derive(Debug, Default)]
+struct Bin {
+    sum: f32,
+    count: usize,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    ensure!(opts.pedal_step > 0.0, "--pedal-step must be positive");
+    ensure!(opts.speed_bin > 0.0, "--speed-bin must be positive");
+
+    let client = Client::connect(&opts.addr, opts.port, None);
+    let mut world = match &opts.world {
+        Some(world) => client.load_world(world),
+        None => client.world(),
+    };
+
+    world.apply_settings(
+        &EpisodeSettings {
+            synchronous_mode: true,
+            fixed_delta_seconds: Some(FIXED_DELTA_SECONDS),
+            ..world.settings()
+        },
+        Duration::ZERO,
+    );
+
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let stop = stop.clone();
+        ctrlc::set_handler(move || {
+            stop.store(true, Ordering::SeqCst);
+        })
+        .with_context(|| "Error setting Ctrl-C handler")?;
+    }
+
+    // Use the same start point as the controller, on a long straight
+    // lane of the default town.
+    let start_point = Isometry3 {
+        translation: Translation3::new(83.075226, 13.414804, 0.600000),
+        rotation: UnitQuaternion::from_euler_angles(0.0, 0.0, -179.840_79_f32.to_radians()),
+    };
+    let map = world.map();
+    let vehicle: Vehicle = world
+        .actor_builder(&opts.blueprint)?
+        .spawn_vehicle(&start_point)?;
+
+    let steps = (1.0 / opts.pedal_step).round() as i32;
+    let mut bins: BTreeMap<(i32, i32), Bin> = BTreeMap::new();
+
+    for index in -steps..=steps {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let pedal = index as f32 / steps as f32;
+        eprintln!("Calibrate the pedal {pedal:.2}");
+
+        reset(&mut world, &vehicle, &start_point);
+        let mut ticks = (opts.duration as f64 / FIXED_DELTA_SECONDS) as usize;
+
+        // Speed up before braking.
+        if pedal < 0.0 {
+            while ticks > 0 && speed(&vehicle) < opts.brake_speed && !stop.load(Ordering::SeqCst) {
+                if !drive(&mut world, &map, &vehicle, 1.0) {
+                    break;
+                }
+                ticks -= 1;
+            }
+            ticks = (opts.duration as f64 / FIXED_DELTA_SECONDS) as usize;
+        }
+
+        let mut prev_speed = speed(&vehicle);
+        for _ in 0..ticks {
+            if stop.load(Ordering::SeqCst) || !drive(&mut world, &map, &vehicle, pedal) {
+                break;
+            }
+
+            let speed = speed(&vehicle);
+            let mean_speed = (speed + prev_speed) / 2.0;
+            let acceleration = (speed - prev_speed) / FIXED_DELTA_SECONDS as f32;
+            prev_speed = speed;
+
+            let bin = bins
+                .entry(((mean_speed / opts.speed_bin) as i32, index))
+                .or_default();
+            bin.sum += acceleration;
+            bin.count += 1;
+
+            if pedal < 0.0 && speed < STOP_SPEED {
+                break;
+            }
+        }
+    }
+
+    // Write the pedal map
+    let mut writer = BufWriter::new(
+        File::create(&opts.output)
+            .with_context(|| format!("unable to create {}", opts.output.display()))?,
+    );
+    writeln!(writer, "speed,pedal,acceleration")?;
+    for (&(speed_index, index), bin) in &bins {
+        let speed = (speed_index as f32 + 0.5) * opts.speed_bin;
+        let pedal = index as f32 / steps as f32;
+        let acceleration = bin.sum / bin.count as f32;
+        writeln!(writer, "{speed:.2},{pedal:.3},{acceleration:.4}")?;
+    }
+    writer.flush()?;
+    eprintln!("Wrote {} entries to {}", bins.len(), opts.output.display());
+
+    world.apply_settings(
+        &EpisodeSettings {
+            synchronous_mode: false,
+            fixed_delta_seconds: None,
+            ..world.settings()
+        },
+        Duration::ZERO,
+    );
+
+    Ok(())
+}
+
+/// Place the vehicle at rest on the start point.
+fn reset(world: &mut World, vehicle: &Vehicle, start_point: &Isometry3<f32>) {
+    vehicle.set_transform(start_point);
+    vehicle.set_target_velocity(&Vector3::zeros());
+    vehicle.apply_control(&pedal_control(0.0, -1.0));
+    for _ in 0..SETTLE_TICKS {
+        world.tick();
+    }
+}
+
+/// Apply the pedal while keeping the vehicle in the lane and advance
+/// the simulation by one tick. It returns false if the vehicle has
+/// left the road.
+fn drive(world: &mut World, map: &Map, vehicle: &Vehicle, pedal: f32) -> bool {
+    let transform = vehicle.transform();
+    let Some(target) = map
+        .waypoint(&transform.translation)
+        .and_then(|waypoint| waypoint.next(5.0).get(0))
+    else {
+        return false;
+    };
+
+    let (_, _, yaw) = transform.rotation.euler_angles();
+    let target = target.transform().translation.vector - transform.translation.vector;
+    let error = target.y.atan2(target.x) - yaw;
+    let error = error.sin().atan2(error.cos());
+
+    vehicle.apply_control(&pedal_control(STEER_GAIN * error, pedal));
+    world.tick();
+    true
+}
+
+/// The control with the normalized steer and the pedal value.
+fn pedal_control(steer: f32, pedal: f32) -> VehicleControl {
+    VehicleControl {
+        throttle: pedal.max(0.0),
+        steer: steer.clamp(-1.0, 1.0),
+        brake: (-pedal).max(0.0),
+        hand_brake: false,
+        reverse: false,
+        manual_gear_shift: false,
+        gear: 0,
+    }
+}
+
+/// The speed of the vehicle in m/s.
+fn speed(vehicle: &Vehicle) -> f32 {
+    vehicle.velocity().norm()
+}
@@ -0,0 +1,189 @@
+//! Show the live status of a running `automatic-control --dashboard`.
+//!
+//! The dashboard connects to the address of the example, and
+//! reconnects whenever the example restarts.
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use eframe::egui::{self, Color32, ProgressBar, Sense, Stroke};
+use serde::Deserialize;
+use std::{
+    io::{BufRead, BufReader},
+    net::{SocketAddr, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// The time to wait before connecting again.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Parser)]
+struct Opts {
+    /// The address given to `automatic-control --dashboard`.
+    #[clap(long, default_value = "127.0.0.1:7878")]
+    pub addr: SocketAddr,
+
+    /// The speed in km/h at the full scale of the speed gauge.
+    #[clap(long, default_value = "100.0")]
+    pub max_speed: f32,
+
+    /// The tick latency in milliseconds above which the latency gauge
+    /// turns red.
+    #[clap(long, default_value = "50.0")]
+    pub latency_budget: f32,
+}
+
+/// The status line sent by `automatic-control`.
+#[derive(Debug, Clone, Deserialize)]
+struct Status {
+    frame: usize,
+    speed: f32,
+    target_speed: f32,
+    steer: f32,
+    throttle: f32,
+    brake: f32,
+    nearby_actors: usize,
+    tick_latency: f32,
+}
+
+struct Dashboard {
+    opts: Opts,
+    status: Arc<Mutex<Option<Status>>>,
+}
+
+impl eframe::App for Dashboard {
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        let status = self.status.lock().unwrap().clone();
+        egui::CentralPanel::default_margins().show(ui, |ui| {
+            let Some(status) = status else {
+                ui.label(format!(
+                    "Waiting for automatic-control at {}",
+                    self.opts.addr
+                ));
+                return;
+            };
+            let opts = &self.opts;
+
+            ui.heading(format!("Frame {}", status.frame));
+            ui.separator();
+
+            ui.label("Speed");
+            let speed = status.speed * 3.6;
+            ui.add(ProgressBar::new(speed / opts.max_speed).text(format!(
+                "{speed:.1} km/h, target {:.1} km/h",
+                status.target_speed * 3.6
+            )));
+
+            ui.label("Steering");
+            centered_bar(ui, status.steer, format!("{:+.2}", status.steer));
+
+            ui.label("Throttle");
+            ui.add(
+                ProgressBar::new(status.throttle)
+                    .fill(Color32::DARK_GREEN)
+                    .show_percentage(),
+            );
+
+            ui.label("Brake");
+            ui.add(
+                ProgressBar::new(status.brake)
+                    .fill(Color32::DARK_RED)
+                    .show_percentage(),
+            );
+
+            ui.separator();
+            ui.label(format!("Nearby actors: {}", status.nearby_actors));
+
+            ui.label("Tick latency");
+            let fill = if status.tick_latency > opts.latency_budget {
+                Color32::DARK_RED
+            } else {
+                Color32::DARK_GREEN
+            };
+            ui.add(
+                ProgressBar::new(status.tick_latency / (2.0 * opts.latency_budget))
+                    .fill(fill)
+                    .text(format!("{:.1} ms", status.tick_latency)),
+            );
+        });
+    }
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let addr = opts.addr;
+    let status = Arc::new(Mutex::new(None));
+
+    let native_options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([360.0, 400.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "dashboard",
+        native_options,
+        Box::new(move |cc| {
+            let ctx = cc.egui_ctx.clone();
+            {
+                let status = status.clone();
+                thread::spawn(move || receive(addr, &status, &ctx));
+            }
+            Ok(Box::new(Dashboard { opts, status }))
+        }),
+    )
+    .map_err(|err| anyhow!("unable to run the dashboard: {err}"))?;
+
+    Ok(())
+}
+
+/// Keep the latest status from the example, connecting again after
+/// it disconnects.
+fn receive(addr: SocketAddr, status: &Mutex<Option<Status>>, ctx: &egui::Context) {
+    loop {
+        if let Ok(stream) = TcpStream::connect(addr) {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match serde_json::from_str(&line) {
+                    Ok(latest) => {
+                        *status.lock().unwrap() = Some(latest);
+                        ctx.request_repaint();
+                    }
+                    Err(err) => eprintln!("Ignore the malformed status: {err}"),
+                }
+            }
+        }
+
+        *status.lock().unwrap() = None;
+        ctx.request_repaint();
+        thread::sleep(RECONNECT_INTERVAL);
+    }
+}
+
+/// Draw a bar filled from the center to the left for negative values
+/// and to the right for positive values.
+fn centered_bar(ui: &mut egui::Ui, value: f32, text: String) {
+    let size = egui::vec2(ui.available_width(), ui.spacing().interact_size.y);
+    let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+    let painter = ui.painter();
+    let visuals = ui.visuals();
+
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    let center = rect.center().x;
+    let end = center + value.clamp(-1.0, 1.0) * rect.width() / 2.0;
+    let fill = egui::Rect::from_x_y_ranges(center.min(end)..=center.max(end), rect.y_range());
+    painter.rect_filled(fill, 0.0, visuals.selection.bg_fill);
+    painter.vline(
+        center,
+        rect.y_range(),
+        Stroke::new(1.0, visuals.text_color()),
+    );
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        text,
+        egui::TextStyle::Button.resolve(ui.style()),
+        visuals.text_color(),
+    );
+}
@@ -0,0 +1,154 @@
+//! Pedal maps recorded by the `calibrate` binary.
+
+use anyhow::{bail, Context, Result};
+use noisy_float::prelude::*;
+use std::{fs, path::Path};
+
+/// The pedal values that produce the accelerations at one speed.
+#[derive(Debug, Clone)]
+struct SpeedBin {
+    speed: f32,
+    /// The pairs of the acceleration and the pedal sorted by the
+    /// acceleration.
+    entries: Vec<(f32, f32)>,
+}
+
+/// The lookup table from the speed and the desired acceleration to
+/// the pedal value. Positive pedal values are throttle and negative
+/// ones are brake.
+#[derive(Debug, Clone)]
+pub struct PedalMap {
+    bins: Vec<SpeedBin>,
+}
+
+impl PedalMap {
+    /// Load the CSV pedal map with the `speed,pedal,acceleration`
+    /// columns.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+
+        let mut bins: Vec<SpeedBin> = vec![];
+        for (lineno, line) in text.lines().enumerate().skip(1) {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let values: Vec<f32> = line
+                .split(',')
+                .map(|value| value.trim().parse())
+                .collect::<Result<_, _>>()
+                .with_context(|| format!("invalid number at {}:{}", path.display(), lineno + 1))?;
+            let &[speed, pedal, acceleration] = values.as_slice() else {
+                bail!("expect 3 columns at {}:{}", path.display(), lineno + 1);
+            };
+
+            match bins.iter_mut().find(|bin| bin.speed == speed) {
+                Some(bin) => bin.entries.push((acceleration, pedal)),
+                None => bins.push(SpeedBin {
+                    speed,
+                    entries: vec![(acceleration, pedal)],
+                }),
+            }
+        }
+
+        if bins.is_empty() {
+            bail!("{} has no entries", path.display());
+        }
+        for bin in &mut bins {
+            bin.entries
+                .sort_by_key(|&(acceleration, _)| r32(acceleration));
+        }
+
+        Ok(Self { bins })
+    }
+
+    /// Look up the pedal value that produces the acceleration in m/s²
+    /// at the speed in m/s.
+    pub fn pedal(&self, speed: f32, acceleration: f32) -> f32 {
+        let bin = self
+            .bins
+            .iter()
+            .min_by_key(|bin| r32((bin.speed - speed).abs()))
+            .unwrap();
+
+        let index = bin
+            .entries
+            .partition_point(|&(entry, _)| entry < acceleration);
+        match (index.checked_sub(1), bin.entries.get(index)) {
+            (Some(lower), Some(&(acc1, pedal1))) => {
+                let (acc0, pedal0) = bin.entries[lower];
+                let ratio = (acceleration - acc0) / (acc1 - acc0).max(f32::EPSILON);
+                pedal0 + ratio * (pedal1 - pedal0)
+            }
+            (None, Some(&(_, pedal))) => pedal,
+            (_, None) => bin.entries.last().unwrap().1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Write the pedal map into a file of the temporary directory.
+    fn write_map(name: &str, text: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("pedal-map-{}-{name}.csv", std::process::id()));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn load(name: &str, text: &str) -> Result<PedalMap> {
+        let path = write_map(name, text);
+        let map = PedalMap::load(&path);
+        fs::remove_file(&path).unwrap();
+        map
+    }
+
+    const MAP: &str = "speed,pedal,acceleration
+0,0.5,2.0
+0,0.0,0.0
+0,-0.5,-4.0
+
+10,0.5,1.0
+10,0.0,-0.5
+10,-0.5,-5.0
+";
+
+    #[test]
+    fn interpolates_between_the_entries() {
+        let map = load("interpolate", MAP).unwrap();
+        assert!((map.pedal(0.0, 1.0) - 0.25).abs() < 1e-6);
+        assert!((map.pedal(0.0, -2.0) + 0.25).abs() < 1e-6);
+        assert_eq!(map.pedal(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn holds_the_extreme_pedals() {
+        let map = load("extreme", MAP).unwrap();
+        assert_eq!(map.pedal(0.0, 5.0), 0.5);
+        assert_eq!(map.pedal(0.0, -9.0), -0.5);
+    }
+
+    #[test]
+    fn uses_the_nearest_speed() {
+        let map = load("nearest", MAP).unwrap();
+        assert_eq!(map.pedal(3.0, -0.5), map.pedal(0.0, -0.5));
+        assert_eq!(map.pedal(7.0, -0.5), 0.0);
+        assert_eq!(map.pedal(30.0, 1.0), 0.5);
+    }
+
+    #[test]
+    fn rejects_a_map_without_entries() {
+        assert!(load("empty", "speed,pedal,acceleration\n\n").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_rows() {
+        assert!(load("columns", "speed,pedal,acceleration\n0,0.5\n").is_err());
+        assert!(load("number", "speed,pedal,acceleration\n0,half,2.0\n").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//! Collision detection to restart the run after a crash.
+
+use crate::counter::{FrameCount, FrameCounter};
+use anyhow::Result;
+use carla::{
+    client::{ActorBase, Sensor, Vehicle, World},
+    geom::Vector3DExt,
+    prelude::*,
+    sensor::data::CollisionEvent,
+};
+use clap::ValueEnum;
+use nalgebra::{Isometry3, Point3};
+use std::sync::{Arc, Mutex};
+
+/// Where the vehicle is placed after a collision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CollisionReset {
+    /// The start point of the run.
+    Start,
+    /// A random recommended spawn point of the map.
+    SpawnPoint,
+}
+
+/// A collision reported by the sensor.
+#[derive(Debug, Clone)]
+pub struct Collision {
+    pub frame: usize,
+    /// The simulation time in seconds.
+    pub timestamp: f64,
+    /// The type of the other actor, or `None` for the static scene.
+    pub other: Option<String>,
+    /// The magnitude of the normal impulse in N·s.
+    pub impulse: f32,
+    /// The location of the vehicle in the world frame.
+    pub location: Point3<f32>,
+}
+
+/// Collects the collisions of the vehicle.
+pub struct CollisionMonitor {
+    sensor: Sensor,
+    frames: FrameCounter,
+    collisions: Arc<Mutex<Vec<Collision>>>,
+}
+
+impl CollisionMonitor {
+    pub fn new(world: &mut World, vehicle: &Vehicle) -> Result<Self> {
+        let sensor: Sensor = world
+            .actor_builder("sensor.other.collision")?
+            .spawn_sensor_opt(&Isometry3::identity(), Some(vehicle), None)?;
+
+        let frames = FrameCounter::default();
+        let collisions = Arc::new(Mutex::new(vec![]));
+        {
+            let frames = frames.clone();
+            let collisions = collisions.clone();
+            sensor.listen(move |data| {
+                let frame = data.frame();
+                let timestamp = data.timestamp();
+                frames.record(frame);
+                let location = data.sensor_transform().translation.vector.into();
+                let event: CollisionEvent = data.try_into().unwrap();
+                let other = event
+                    .other_actor()
+                    .map(|actor| format!("{} ({})", actor.type_id(), actor.id()));
+
+                collisions.lock().unwrap().push(Collision {
+                    frame,
+                    timestamp,
+                    other,
+                    impulse: event.normal_impulse().to_na().norm(),
+                    location,
+                });
+            });
+        }
+
+        Ok(Self {
+            sensor,
+            frames,
+            collisions,
+        })
+    }
+
+    /// The measurements received by the sensor.
+    pub fn frames(&self) -> FrameCount {
+        self.frames.get()
+    }
+
+    /// Take the strongest of the collisions since the last call. A
+    /// single contact usually reports several events.
+    pub fn take(&self) -> Option<Collision> {
+        self.collisions
+            .lock()
+            .unwrap()
+            .drain(..)
+            .max_by(|lhs, rhs| lhs.impulse.total_cmp(&rhs.impulse))
+    }
+}
+
+impl Drop for CollisionMonitor {
+    fn drop(&mut self) {
+        self.sensor.stop();
+    }
+}
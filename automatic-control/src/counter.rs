@@ -0,0 +1,30 @@
+//! Counting of the measurements received by the sensors.
+
+use std::sync::{Arc, Mutex};
+
+/// The number of measurements of a sensor so far.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameCount {
+    pub count: usize,
+    /// The frame of the latest measurement.
+    pub last_frame: Option<usize>,
+}
+
+/// Counts the measurements in the sensor thread. The clones share the
+/// same count.
+#[derive(Debug, Clone, Default)]
+pub struct FrameCounter {
+    inner: Arc<Mutex<FrameCount>>,
+}
+
+impl FrameCounter {
+    pub fn record(&self, frame: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.count += 1;
+        inner.last_frame = Some(frame);
+    }
+
+    pub fn get(&self) -> FrameCount {
+        *self.inner.lock().unwrap()
+    }
+}
@@ -0,0 +1,93 @@
+//! Log the controller state of every tick to a CSV file.
+
+use crate::{actuator::Command, ego::EgoState, lateral::Reference};
+use anyhow::{Context, Result};
+use carla::client::{ActorBase, Vehicle, World};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+const HEADER: &str = "frame,timestamp,x,y,z,yaw,pitch,speed,vx,vy,vz,\
+                      target_speed,command_speed,command_acceleration,command_steer,\
+                      hand_brake,reverse,lateral_error,heading_error,\
+                      road_id,section_id,lane_id,waypoint_s,waypoint_x,waypoint_y,\
+                      is_junction,lane_offset";
+
+/// Writes a row per tick. The angles are in degrees and the other
+/// values in SI units.
+pub struct CsvLogger {
+    writer: BufWriter<File>,
+    rows: usize,
+}
+
+impl CsvLogger {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("unable to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{HEADER}")?;
+        eprintln!("Log the ticks to {}", path.display());
+        Ok(Self { writer, rows: 0 })
+    }
+
+    pub fn log(
+        &mut self,
+        world: &World,
+        vehicle: &Vehicle,
+        ego: &EgoState,
+        reference: &Reference,
+        command: &Command,
+        target_speed: f32,
+    ) -> Result<()> {
+        let snapshot = world.snapshot();
+        let frame = snapshot.frame();
+        let timestamp = snapshot.timestamp().elapsed_seconds;
+        let location = ego.transform.translation;
+        let velocity = vehicle.velocity();
+        let error = reference.error(&ego.position(), ego.yaw);
+        let waypoint = &reference.nearest;
+        let waypoint_location = waypoint.transform().translation;
+
+        writeln!(
+            self.writer,
+            "{frame},{timestamp:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},\
+             {target_speed:.3},{:.3},{:.3},{:.3},{},{},{:.3},{:.3},\
+             {},{},{},{:.3},{:.3},{:.3},{},{:.3}",
+            location.x,
+            location.y,
+            location.z,
+            ego.yaw.to_degrees(),
+            ego.pitch.to_degrees(),
+            ego.speed,
+            velocity.x,
+            velocity.y,
+            velocity.z,
+            command.speed,
+            command.acceleration,
+            command.steer.to_degrees(),
+            command.hand_brake as u8,
+            command.reverse as u8,
+            error.lateral,
+            error.heading.to_degrees(),
+            waypoint.road_id(),
+            waypoint.section_id(),
+            waypoint.lane_id(),
+            waypoint.distance(),
+            waypoint_location.x,
+            waypoint_location.y,
+            waypoint.is_junction() as u8,
+            reference.offset,
+        )?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        eprintln!("Logged {} ticks", self.rows);
+        Ok(())
+    }
+}
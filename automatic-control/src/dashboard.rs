@@ -0,0 +1,128 @@
+//! Publish the vehicle status to the `dashboard` binary.
+//!
+//! The status is sent to every connected client as a JSON object per
+//! line over TCP.
+
+use anyhow::{Context, Result};
+use carla::client::{ActorBase, Vehicle, World};
+use serde::Serialize;
+use std::{
+    io::{ErrorKind, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+/// The distance in meters within which the other actors are counted.
+const NEARBY_RADIUS: f32 = 50.0;
+
+/// The time to wait for a slow client before dropping it.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The status of the vehicle in a tick.
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub frame: usize,
+    /// The forward speed in m/s.
+    pub speed: f32,
+    /// The target speed in m/s after the behaviors.
+    pub target_speed: f32,
+    /// The normalized steer applied by the simulator in `[-1, 1]`.
+    pub steer: f32,
+    /// The throttle applied by the simulator in `[0, 1]`.
+    pub throttle: f32,
+    /// The brake applied by the simulator in `[0, 1]`.
+    pub brake: f32,
+    /// The number of other vehicles and walkers nearby.
+    pub nearby_actors: usize,
+    /// The wall time in milliseconds since the previous tick.
+    pub tick_latency: f32,
+}
+
+/// Accepts the dashboard clients and sends the status to them.
+pub struct DashboardServer {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+    last_tick: Option<Instant>,
+}
+
+impl DashboardServer {
+    pub fn new(addr: SocketAddr) -> Result<Self> {
+        let listener =
+            TcpListener::bind(addr).with_context(|| format!("unable to listen on {addr}"))?;
+        listener.set_nonblocking(true)?;
+        eprintln!("Serving the dashboard on {addr}");
+
+        Ok(Self {
+            listener,
+            clients: vec![],
+            last_tick: None,
+        })
+    }
+
+    /// Send the status of the vehicle in this tick. The clients that
+    /// fail to receive it are dropped.
+    pub fn publish(
+        &mut self,
+        world: &World,
+        vehicle: &Vehicle,
+        speed: f32,
+        target_speed: f32,
+    ) -> Result<()> {
+        let now = Instant::now();
+        let tick_latency = self
+            .last_tick
+            .replace(now)
+            .map_or(0.0, |last| (now - last).as_secs_f32() * 1000.0);
+
+        loop {
+            match self.listener.accept() {
+                Ok((stream, addr)) => {
+                    eprintln!("The dashboard at {addr} is connected");
+                    stream.set_nonblocking(false)?;
+                    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                    self.clients.push(stream);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        if self.clients.is_empty() {
+            return Ok(());
+        }
+
+        let location = vehicle.location().vector;
+        let nearby_actors = world
+            .actors()
+            .iter()
+            .filter(|actor| {
+                let type_id = actor.type_id();
+                actor.id() != vehicle.id()
+                    && (type_id.starts_with("vehicle.") || type_id.starts_with("walker."))
+                    && (actor.location().vector - location).norm() <= NEARBY_RADIUS
+            })
+            .count();
+        let control = vehicle.control();
+        let status = Status {
+            frame: world.snapshot().frame(),
+            speed,
+            target_speed,
+            steer: control.steer,
+            throttle: control.throttle,
+            brake: control.brake,
+            nearby_actors,
+            tick_latency,
+        };
+
+        let mut line = serde_json::to_vec(&status)?;
+        line.push(b'\n');
+        self.clients
+            .retain_mut(|client| match client.write_all(&line) {
+                Ok(()) => true,
+                Err(err) => {
+                    eprintln!("The dashboard is disconnected: {err}");
+                    false
+                }
+            });
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//! A day-night cycle driving the sun, the street lights and the
+//! headlights.
+
+use carla::{
+    client::{Vehicle, World},
+    rpc::{LightGroup, VehicleLightState},
+};
+use std::f32::consts::TAU;
+
+/// The interval in simulation seconds between the sun updates.
+const UPDATE_INTERVAL: f32 = 0.1;
+
+/// The highest sun altitude in degrees, reached at noon.
+const MAX_ALTITUDE: f32 = 70.0;
+
+/// The sun altitude in degrees below which the lights are on. The
+/// scene is dark well before the sun sets.
+const LIGHTS_ON_ALTITUDE: f32 = 5.0;
+
+/// Moves the sun through the hours of the day and switches the street
+/// lights and the headlights of the vehicle at dusk and dawn.
+#[derive(Debug, Clone)]
+pub struct DayNightCycle {
+    /// The simulation hours per second.
+    hours_per_second: f32,
+    /// The hour of the day from 0 to 24.
+    hour: f32,
+    elapsed: f32,
+    lights_on: Option<bool>,
+}
+
+impl DayNightCycle {
+    /// Start at `start_hour` and pass a whole day in `day_minutes` of
+    /// simulation time.
+    pub fn new(day_minutes: f32, start_hour: f32) -> Self {
+        Self {
+            hours_per_second: 24.0 / (day_minutes * 60.0),
+            hour: start_hour.rem_euclid(24.0),
+            elapsed: UPDATE_INTERVAL,
+            lights_on: None,
+        }
+    }
+
+    /// Advance the time of day by the time step in seconds.
+    pub fn update(&mut self, world: &mut World, vehicle: &Vehicle, dt: f32) {
+        self.elapsed += dt;
+        if self.elapsed < UPDATE_INTERVAL {
+            return;
+        }
+        self.hour = (self.hour + self.hours_per_second * self.elapsed) % 24.0;
+        self.elapsed = 0.0;
+
+        // The sun rises at 6 o'clock and sets at 18 o'clock.
+        let phase = (self.hour - 6.0) / 24.0 * TAU;
+        let altitude = MAX_ALTITUDE * phase.sin();
+        let mut weather = world.weather();
+        weather.sun_altitude_angle = altitude;
+        weather.sun_azimuth_angle = self.hour / 24.0 * 360.0;
+        world.set_weather(&weather);
+
+        let lights_on = altitude < LIGHTS_ON_ALTITUDE;
+        if self.lights_on == Some(lights_on) {
+            return;
+        }
+        self.lights_on = Some(lights_on);
+
+        let mut lights = world.light_manager().all_lights(LightGroup::Street);
+        for index in 0..lights.len() {
+            if let Some(mut light) = lights.get_mut(index) {
+                if lights_on {
+                    light.turn_on();
+                } else {
+                    light.turn_off();
+                }
+            }
+        }
+        vehicle.set_light_state(if lights_on {
+            &VehicleLightState::LowBeam
+        } else {
+            &VehicleLightState::None
+        });
+
+        let (hours, minutes) = (self.hour as u32, (self.hour.fract() * 60.0) as u32);
+        println!(
+            "Turn the {} street lights {} at {hours:02}:{minutes:02}",
+            lights.len(),
+            if lights_on { "on" } else { "off" }
+        );
+    }
+}
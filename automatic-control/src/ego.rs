@@ -0,0 +1,101 @@
+mod spec;
+
+pub use spec::*;
+
+use carla::client::{ActorBase, Vehicle};
+use nalgebra::{Isometry3, Point2, Vector2};
+use std::f32::consts::PI;
+
+/// The kinematic state of the ego vehicle sampled at one tick.
+#[derive(Debug, Clone)]
+pub struct EgoState {
+    pub transform: Isometry3<f32>,
+    /// The yaw angle in radians.
+    pub yaw: f32,
+    /// The pitch angle in radians, positive when the nose points up.
+    pub pitch: f32,
+    /// The forward speed in m/s.
+    pub speed: f32,
+}
+
+impl EgoState {
+    pub fn from_vehicle(vehicle: &Vehicle) -> Self {
+        Self::new(vehicle.transform(), vehicle.velocity().norm())
+    }
+
+    /// The state at the pose with the forward speed in m/s.
+    pub fn new(transform: Isometry3<f32>, speed: f32) -> Self {
+        // The rotation is built from the left-handed angles of CARLA,
+        // so its Euler angles are the CARLA pitch, positive nose-up,
+        // while the z of the rotated forward vector has the opposite
+        // sign.
+        let (_, pitch, yaw) = transform.rotation.euler_angles();
+
+        Self {
+            transform,
+            yaw,
+            pitch,
+            speed,
+        }
+    }
+
+    /// The state seen by a vehicle facing backwards, used to drive in
+    /// reverse with the forward controllers.
+    pub fn reversed(&self) -> Self {
+        Self {
+            transform: self.transform,
+            yaw: normalize_angle(self.yaw + PI),
+            pitch: -self.pitch,
+            speed: self.speed,
+        }
+    }
+
+    /// The vehicle location projected on the ground plane.
+    pub fn position(&self) -> Point2<f32> {
+        let t = &self.transform.translation;
+        Point2::new(t.x, t.y)
+    }
+
+    /// The unit vector pointing to the vehicle heading.
+    pub fn heading(&self) -> Vector2<f32> {
+        Vector2::new(self.yaw.cos(), self.yaw.sin())
+    }
+
+    /// The approximated location of the front axle center, assuming
+    /// the actor origin is halfway between the axles.
+    pub fn front_axle(&self, spec: &VehicleSpec) -> Point2<f32> {
+        self.position() + self.heading() * (spec.wheelbase / 2.0)
+    }
+
+    /// The approximated location of the rear axle center, assuming
+    /// the actor origin is halfway between the axles.
+    pub fn rear_axle(&self, spec: &VehicleSpec) -> Point2<f32> {
+        self.position() - self.heading() * (spec.wheelbase / 2.0)
+    }
+}
+
+/// Wrap an angle in radians to the range [-π, π).
+pub fn normalize_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(PI * 2.0) - PI
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{Translation3, UnitQuaternion};
+
+    #[test]
+    fn pitch_is_positive_uphill() {
+        // carla-rust converts a CARLA rotation of pitch 10° by
+        // `UnitQuaternion::from_euler_angles(roll, pitch, yaw)`.
+        let rotation = UnitQuaternion::from_euler_angles(0.0, 10_f32.to_radians(), 0.0);
+        let ego = EgoState::new(
+            Isometry3::from_parts(Translation3::identity(), rotation),
+            5.0,
+        );
+
+        assert!((ego.pitch - 10_f32.to_radians()).abs() < 1e-5);
+        assert!(ego.pitch.sin() > 0.0);
+        assert!(ego.reversed().pitch < 0.0);
+    }
+}
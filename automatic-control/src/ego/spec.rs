@@ -0,0 +1,55 @@
+//! The static vehicle parameters. The mpc-control example includes
+//! this file as well.
+
+use carla::{client::Vehicle, geom::Vector3DExt};
+use nalgebra::Vector3;
+use noisy_float::prelude::*;
+
+/// The wheelbase in meters assumed when it cannot be derived from
+/// the wheel positions.
+const DEFAULT_WHEELBASE: f32 = 2.8;
+
+/// Static vehicle parameters used by the controllers.
+#[derive(Debug, Clone)]
+pub struct VehicleSpec {
+    /// The distance between the front and rear axles in meters.
+    pub wheelbase: f32,
+    /// The maximum steering angle of the front wheels in radians.
+    pub max_steer_angle: f32,
+}
+
+impl VehicleSpec {
+    pub fn from_vehicle(vehicle: &Vehicle) -> Self {
+        let physics_control = vehicle.physics_control();
+        let wheels = &physics_control.wheels;
+
+        let max_steer_angle = wheels
+            .iter()
+            .map(|wheel| r32(wheel.max_steer_angle))
+            .max()
+            .expect("Unable to obtain max steering angle from the vehicle")
+            .raw()
+            .to_radians();
+
+        // Wheels are ordered as front-left, front-right, rear-left
+        // and rear-right. Their positions are given in centimeters.
+        // The rear axles of multi-axle trucks are averaged into one.
+        let wheelbase = match wheels.as_slice() {
+            [fl, fr, rear @ ..] if rear.len() >= 2 => {
+                let front = (fl.position.to_na() + fr.position.to_na()) / 2.0;
+                let rear = rear
+                    .iter()
+                    .map(|wheel| wheel.position.to_na())
+                    .sum::<Vector3<f32>>()
+                    / rear.len() as f32;
+                (front - rear).norm() / 100.0
+            }
+            _ => DEFAULT_WHEELBASE,
+        };
+
+        Self {
+            wheelbase,
+            max_steer_angle,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Log the discrete events of a run as JSON lines.
+//!
+//! Each line is an object with the frame, the simulation time in
+//! seconds and the event name, followed by the fields of the event.
+//! It complements the per-tick telemetry, which is too dense to read
+//! for what happened.
+
+use anyhow::{Context, Result};
+use carla::{client::World, rpc::ActorId};
+use serde::Serialize;
+use std::{
+    fmt,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Spawn {
+        actor_id: ActorId,
+        type_id: String,
+        location: [f32; 3],
+    },
+    Collision {
+        /// The type of the other actor, or `None` for the static scene.
+        other: Option<String>,
+        impulse: f32,
+        location: [f32; 3],
+    },
+    LaneInvasion {
+        markings: Vec<&'static str>,
+    },
+    TrafficLightStop {
+        light_id: ActorId,
+        state: &'static str,
+        distance: f32,
+    },
+    TrafficLightResume {
+        light_id: ActorId,
+    },
+    /// A traffic light ahead ended a phase of the duration in seconds.
+    TrafficLightPhase {
+        light_id: ActorId,
+        state: &'static str,
+        duration: f64,
+    },
+    /// The vehicle drove off after waiting at a traffic light for the
+    /// duration in seconds.
+    TrafficLightWait {
+        light_id: ActorId,
+        duration: f64,
+    },
+    /// A sign or signal of the map came into the lookahead on the
+    /// lane.
+    Landmark {
+        landmark_id: String,
+        name: String,
+        type_code: String,
+        kind: &'static str,
+        value: f64,
+        unit: String,
+        distance: f32,
+    },
+    StopSignStop {
+        landmark_id: String,
+        distance: f32,
+    },
+    /// The vehicle drove off after standing at a stop sign for the
+    /// duration in seconds.
+    StopSignResume {
+        landmark_id: String,
+        duration: f32,
+    },
+    /// The vehicle is teleported after a collision or leaving the road.
+    Reset {
+        reason: &'static str,
+        location: [f32; 3],
+    },
+    ParameterChange {
+        name: String,
+        value: String,
+    },
+}
+
+impl Event {
+    pub fn parameter(name: impl Into<String>, value: impl fmt::Display) -> Self {
+        Event::ParameterChange {
+            name: name.into(),
+            value: value.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    frame: usize,
+    timestamp: f64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// A shared writer of the event log.
+#[derive(Clone)]
+pub struct EventLog {
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl EventLog {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("unable to create {}", path.display()))?;
+        eprintln!("Log the events to {}", path.display());
+        Ok(Self {
+            writer: Arc::new(Mutex::new(BufWriter::new(file))),
+        })
+    }
+
+    /// Log an event at the current frame of the world.
+    pub fn log(&self, world: &World, event: Event) -> Result<()> {
+        let snapshot = world.snapshot();
+        self.log_at(
+            snapshot.frame(),
+            snapshot.timestamp().elapsed_seconds,
+            event,
+        )
+    }
+
+    /// Log an event at a frame, such as the frame of a measurement.
+    pub fn log_at(&self, frame: usize, timestamp: f64, event: Event) -> Result<()> {
+        let line = serde_json::to_string(&Record {
+            frame,
+            timestamp,
+            event: &event,
+        })?;
+        writeln!(self.writer.lock().unwrap(), "{line}")?;
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<()> {
+        self.writer.lock().unwrap().flush()?;
+        Ok(())
+    }
+}
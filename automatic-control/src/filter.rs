@@ -0,0 +1,84 @@
+/// A first-order low-pass filter.
+#[derive(Debug, Clone)]
+pub struct LowPass {
+    /// The time constant in seconds. Zero disables the filter.
+    pub time_constant: f32,
+    state: Option<f32>,
+}
+
+impl LowPass {
+    pub fn new(time_constant: f32) -> Self {
+        Self {
+            time_constant,
+            state: None,
+        }
+    }
+
+    /// Feed an input sample and return the filtered value. `dt` is
+    /// the elapsed time in seconds since the last sample.
+    pub fn apply(&mut self, input: f32, dt: f32) -> f32 {
+        let output = match self.state {
+            Some(prev) if self.time_constant > 0.0 => {
+                let alpha = dt / (self.time_constant + dt);
+                prev + alpha * (input - prev)
+            }
+            _ => input,
+        };
+        self.state = Some(output);
+        output
+    }
+
+    /// Forget the filter state so that the next input passes through.
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_the_first_sample() {
+        let mut filter = LowPass::new(0.5);
+        assert_eq!(filter.apply(3.0, 0.1), 3.0);
+    }
+
+    #[test]
+    fn zero_time_constant_disables_the_filter() {
+        let mut filter = LowPass::new(0.0);
+        filter.apply(0.0, 0.1);
+        assert_eq!(filter.apply(3.0, 0.1), 3.0);
+    }
+
+    #[test]
+    fn approaches_a_step_without_overshoot() {
+        let mut filter = LowPass::new(0.4);
+        filter.apply(0.0, 0.1);
+        // alpha = 0.1 / (0.4 + 0.1)
+        assert!((filter.apply(1.0, 0.1) - 0.2).abs() < 1e-6);
+
+        let mut prev = 0.2;
+        for _ in 0..100 {
+            let output = filter.apply(1.0, 0.1);
+            assert!(output >= prev && output <= 1.0);
+            prev = output;
+        }
+        assert!((prev - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn zero_time_step_holds_the_output() {
+        let mut filter = LowPass::new(0.4);
+        filter.apply(1.0, 0.1);
+        assert_eq!(filter.apply(5.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn reset_passes_the_next_sample() {
+        let mut filter = LowPass::new(0.4);
+        filter.apply(0.0, 0.1);
+        filter.reset();
+        assert_eq!(filter.apply(3.0, 0.1), 3.0);
+    }
+}
@@ -0,0 +1,196 @@
+//! Tire friction following the wetness of the weather.
+//!
+//! The simulator keeps the road grip regardless of the rain, so the
+//! tire friction of the vehicle is scaled down instead to emulate
+//! slippery roads. The friction trigger boxes of CARLA would also work
+//! but stay in the world after the run, as told in [`crate::leftover`].
+
+use anyhow::{bail, ensure, Error, Result};
+use carla::{
+    client::{Vehicle, World},
+    rpc::WeatherParameters,
+};
+use std::str::FromStr;
+
+/// The smallest change of the scale applied to the vehicle. Applying
+/// the physics control is costly, so the small weather steps are
+/// ignored.
+const MIN_SCALE_CHANGE: f32 = 0.01;
+
+/// A piecewise linear curve from the road wetness from 0 to 100 to the
+/// friction scale, written as `WET=SCALE` points separated by commas,
+/// e.g. `0=1.0,50=0.85,100=0.7`. The scale is held constant beyond the
+/// first and the last points.
+#[derive(Debug, Clone)]
+pub struct FrictionCurve {
+    points: Vec<(f32, f32)>,
+}
+
+impl FromStr for FrictionCurve {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut points = vec![];
+        for item in text.split(',').map(str::trim) {
+            let Some((wetness, scale)) = item.split_once('=') else {
+                bail!("expect WET=SCALE, but get '{item}'");
+            };
+            let wetness: f32 = wetness.trim().parse()?;
+            let scale: f32 = scale.trim().parse()?;
+            ensure!(
+                (0.0..=100.0).contains(&wetness),
+                "the wetness {wetness} is not within 0 and 100"
+            );
+            ensure!(
+                scale > 0.0,
+                "the friction scale of '{item}' must be positive"
+            );
+            points.push((wetness, scale));
+        }
+        points.sort_by(|lhs, rhs| lhs.0.total_cmp(&rhs.0));
+        ensure!(
+            points.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "the wetness of the friction points must be distinct"
+        );
+        Ok(Self { points })
+    }
+}
+
+impl FrictionCurve {
+    /// The friction scale at the wetness.
+    pub fn scale(&self, wetness: f32) -> f32 {
+        let first = self.points[0];
+        if wetness <= first.0 {
+            return first.1;
+        }
+        for pair in self.points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            if wetness <= x1 {
+                return y0 + (y1 - y0) * (wetness - x0) / (x1 - x0);
+            }
+        }
+        self.points[self.points.len() - 1].1
+    }
+}
+
+/// The road wetness from 0 to 100, which is the strongest of the rain,
+/// the puddles and the wetness of the weather.
+fn road_wetness(weather: &WeatherParameters) -> f32 {
+    weather
+        .precipitation
+        .max(weather.precipitation_deposits)
+        .max(weather.wetness)
+        .clamp(0.0, 100.0)
+}
+
+/// Scales the tire friction of the vehicle by the wetness of the
+/// current weather.
+pub struct WetFriction {
+    curve: FrictionCurve,
+    /// The tire friction of the wheels before the scaling.
+    base: Vec<f32>,
+    scale: f32,
+}
+
+impl WetFriction {
+    pub fn new(vehicle: &Vehicle, curve: FrictionCurve) -> Self {
+        let base = vehicle
+            .physics_control()
+            .wheels
+            .iter()
+            .map(|wheel| wheel.tire_friction)
+            .collect();
+        Self {
+            curve,
+            base,
+            scale: 1.0,
+        }
+    }
+
+    /// Follow the weather of the world. It returns the new scale if it
+    /// is applied to the vehicle.
+    pub fn update(&mut self, world: &World, vehicle: &Vehicle) -> Option<f32> {
+        let scale = self.curve.scale(road_wetness(&world.weather()));
+        if (scale - self.scale).abs() < MIN_SCALE_CHANGE {
+            return None;
+        }
+        self.apply(vehicle, scale);
+        Some(scale)
+    }
+
+    /// Give the vehicle its original tire friction back.
+    pub fn restore(&mut self, vehicle: &Vehicle) {
+        self.apply(vehicle, 1.0);
+    }
+
+    fn apply(&mut self, vehicle: &Vehicle, scale: f32) {
+        let mut control = vehicle.physics_control();
+        for (wheel, base) in control.wheels.iter_mut().zip(&self.base) {
+            wheel.tire_friction = base * scale;
+        }
+        vehicle.apply_physics_control(&control);
+        self.scale = scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(lhs: f32, rhs: f32) -> bool {
+        (lhs - rhs).abs() < 1e-5
+    }
+
+    #[test]
+    fn interpolates_between_points() {
+        let curve: FrictionCurve = "0=1.0,50=0.8,100=0.6".parse().unwrap();
+        assert!(close(curve.scale(0.0), 1.0));
+        assert!(close(curve.scale(25.0), 0.9));
+        assert!(close(curve.scale(50.0), 0.8));
+        assert!(close(curve.scale(75.0), 0.7));
+        assert!(close(curve.scale(100.0), 0.6));
+    }
+
+    #[test]
+    fn sorts_unsorted_points() {
+        let curve: FrictionCurve = "100=0.6, 0=1.0 ,50=0.8".parse().unwrap();
+        assert!(close(curve.scale(25.0), 0.9));
+        assert!(close(curve.scale(75.0), 0.7));
+    }
+
+    #[test]
+    fn holds_the_scale_beyond_the_points() {
+        let curve: FrictionCurve = "20=0.9,80=0.7".parse().unwrap();
+        assert!(close(curve.scale(0.0), 0.9));
+        assert!(close(curve.scale(-10.0), 0.9));
+        assert!(close(curve.scale(100.0), 0.7));
+        assert!(close(curve.scale(150.0), 0.7));
+    }
+
+    #[test]
+    fn single_point_is_constant() {
+        let curve: FrictionCurve = "50=0.8".parse().unwrap();
+        assert!(close(curve.scale(0.0), 0.8));
+        assert!(close(curve.scale(50.0), 0.8));
+        assert!(close(curve.scale(100.0), 0.8));
+    }
+
+    #[test]
+    fn rejects_duplicate_wetness() {
+        assert!("0=1.0,50=0.8,50=0.7".parse::<FrictionCurve>().is_err());
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        assert!("-1=1.0".parse::<FrictionCurve>().is_err());
+        assert!("101=1.0".parse::<FrictionCurve>().is_err());
+        assert!("50=0.0".parse::<FrictionCurve>().is_err());
+        assert!("50=-0.5".parse::<FrictionCurve>().is_err());
+    }
+
+    #[test]
+    fn rejects_items_without_a_scale() {
+        assert!("50".parse::<FrictionCurve>().is_err());
+        assert!("0=1.0,".parse::<FrictionCurve>().is_err());
+    }
+}
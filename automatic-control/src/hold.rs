@@ -0,0 +1,42 @@
+//! Hill hold at stops.
+
+/// The commanded speed in m/s regarded as a stop request.
+const STOP_SPEED: f32 = 0.01;
+
+/// Engages the handbrake once the vehicle has stopped on command and
+/// releases it when a positive speed is commanded again.
+#[derive(Debug, Clone)]
+pub struct HillHold {
+    /// The vehicle speed in m/s below which the handbrake engages.
+    pub speed_threshold: f32,
+    engaged: bool,
+}
+
+impl HillHold {
+    pub fn new(speed_threshold: f32) -> Self {
+        Self {
+            speed_threshold,
+            engaged: false,
+        }
+    }
+
+    /// Update the hold state from the commanded and the current speed
+    /// in m/s and return whether the handbrake is engaged.
+    pub fn update(&mut self, commanded_speed: f32, speed: f32) -> bool {
+        if commanded_speed > STOP_SPEED {
+            if self.engaged {
+                eprintln!("Release the hill hold");
+                self.engaged = false;
+            }
+        } else if !self.engaged && speed < self.speed_threshold {
+            eprintln!("Engage the hill hold");
+            self.engaged = true;
+        }
+
+        self.engaged
+    }
+
+    pub fn reset(&mut self) {
+        self.engaged = false;
+    }
+}
@@ -0,0 +1,127 @@
+//! The signs and signals of the map met ahead on the lane.
+
+use crate::events::Event;
+use carla::client::Waypoint;
+use std::collections::BTreeSet;
+
+/// The OpenDRIVE signal type of stop signs.
+pub const STOP_SIGN_TYPE: &str = "206";
+
+/// The names of the OpenDRIVE signal types used by the CARLA maps.
+const KINDS: &[(&str, &str)] = &[
+    ("101", "danger"),
+    ("121", "lanes merging"),
+    ("133", "caution pedestrian"),
+    ("138", "caution bicycle"),
+    ("150", "level crossing"),
+    ("205", "yield"),
+    (STOP_SIGN_TYPE, "stop"),
+    ("209", "mandatory turn"),
+    ("215", "roundabout"),
+    ("250", "access forbidden"),
+    ("267", "wrong direction"),
+    ("272", "no u-turn"),
+    ("274", "speed limit"),
+    ("276", "no overtaking"),
+    ("283", "no stopping"),
+    ("301", "right of way"),
+    ("306", "priority road"),
+    ("307", "priority road end"),
+    ("310", "city begin"),
+    ("311", "city end"),
+    ("330", "highway"),
+    ("357", "dead end"),
+    ("380", "recommended speed"),
+    ("381", "recommended speed end"),
+    ("1000001", "traffic light"),
+];
+
+/// A landmark ahead of the vehicle on its lane.
+#[derive(Debug, Clone)]
+pub struct LandmarkAhead {
+    pub id: String,
+    pub name: String,
+    /// The OpenDRIVE signal type.
+    pub type_code: String,
+    pub kind: &'static str,
+    pub value: f64,
+    pub unit: String,
+    /// The distance in meters along the lane.
+    pub distance: f32,
+}
+
+/// Enumerates the landmarks ahead on the lane every tick.
+pub struct LandmarkScanner {
+    /// The distance in meters along the lane to look for landmarks.
+    pub lookahead: f64,
+    /// The ids of the landmarks ahead in the last scan.
+    ahead: BTreeSet<String>,
+}
+
+impl LandmarkScanner {
+    pub fn new(lookahead: f64) -> Self {
+        Self {
+            lookahead,
+            ahead: BTreeSet::new(),
+        }
+    }
+
+    /// Return the landmarks ahead of the waypoint sorted by the
+    /// distance, and the events of the ones that came into the
+    /// lookahead since the last scan.
+    pub fn scan(&mut self, waypoint: &Waypoint) -> (Vec<LandmarkAhead>, Vec<Event>) {
+        // A landmark is listed once for every road it applies to, so
+        // only the nearest listing is kept.
+        let mut landmarks: Vec<LandmarkAhead> = vec![];
+        for landmark in waypoint
+            .all_landmarks_in_distance(self.lookahead, false)
+            .iter()
+        {
+            let id = landmark.id();
+            let distance = landmark.distance() as f32;
+            if let Some(other) = landmarks.iter_mut().find(|other| other.id == id) {
+                other.distance = other.distance.min(distance);
+                continue;
+            }
+            let type_code = landmark.type_();
+            landmarks.push(LandmarkAhead {
+                kind: landmark_kind(&type_code),
+                name: landmark.name(),
+                value: landmark.value(),
+                unit: landmark.unit(),
+                id,
+                type_code,
+                distance,
+            });
+        }
+        landmarks.sort_by(|lhs, rhs| lhs.distance.total_cmp(&rhs.distance));
+
+        let events = landmarks
+            .iter()
+            .filter(|landmark| !self.ahead.contains(&landmark.id))
+            .map(|landmark| Event::Landmark {
+                landmark_id: landmark.id.clone(),
+                name: landmark.name.clone(),
+                type_code: landmark.type_code.clone(),
+                kind: landmark.kind,
+                value: landmark.value,
+                unit: landmark.unit.clone(),
+                distance: landmark.distance,
+            })
+            .collect();
+        self.ahead = landmarks
+            .iter()
+            .map(|landmark| landmark.id.clone())
+            .collect();
+
+        (landmarks, events)
+    }
+}
+
+/// The human-readable name of an OpenDRIVE signal type.
+fn landmark_kind(type_code: &str) -> &'static str {
+    KINDS
+        .iter()
+        .find(|(code, _)| *code == type_code)
+        .map_or("other", |(_, kind)| kind)
+}
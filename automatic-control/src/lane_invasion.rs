@@ -0,0 +1,131 @@
+//! Counting of the lane marking crossings.
+
+use crate::{
+    counter::{FrameCount, FrameCounter},
+    events::{Event, EventLog},
+};
+use anyhow::Result;
+use carla::{
+    client::{Sensor, Vehicle, World},
+    prelude::*,
+    road::element::LaneMarking_Type,
+    sensor::data::LaneInvasionEvent,
+};
+use nalgebra::Isometry3;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// Counts the crossed lane markings by their types.
+pub struct LaneInvasionCounter {
+    sensor: Sensor,
+    frames: FrameCounter,
+    counts: Arc<Mutex<BTreeMap<&'static str, usize>>>,
+}
+
+impl LaneInvasionCounter {
+    /// Spawn the sensor on the vehicle. The crossings are also written
+    /// to the event log if given.
+    pub fn new(world: &mut World, vehicle: &Vehicle, events: Option<EventLog>) -> Result<Self> {
+        let sensor: Sensor = world
+            .actor_builder("sensor.other.lane_invasion")?
+            .spawn_sensor_opt(&Isometry3::identity(), Some(vehicle), None)?;
+
+        let frames = FrameCounter::default();
+        let counts = Arc::new(Mutex::new(BTreeMap::new()));
+        {
+            let frames = frames.clone();
+            let counts = counts.clone();
+            sensor.listen(move |data| {
+                let frame = data.frame();
+                let timestamp = data.timestamp();
+                frames.record(frame);
+                let event: LaneInvasionEvent = data.try_into().unwrap();
+                let names: Vec<_> = event
+                    .crossed_lane_markings()
+                    .iter()
+                    .map(|marking| marking_name(&marking.type_()))
+                    .collect();
+                eprintln!("Lane invasion at frame {frame}: {}", names.join(", "));
+                if let Some(events) = &events {
+                    let event = Event::LaneInvasion {
+                        markings: names.clone(),
+                    };
+                    if let Err(err) = events.log_at(frame, timestamp, event) {
+                        eprintln!("Unable to log the lane invasion: {err}");
+                    }
+                }
+
+                let mut counts = counts.lock().unwrap();
+                for name in names {
+                    *counts.entry(name).or_default() += 1;
+                }
+            });
+        }
+
+        Ok(Self {
+            sensor,
+            frames,
+            counts,
+        })
+    }
+
+    /// The measurements received by the sensor.
+    pub fn frames(&self) -> FrameCount {
+        self.frames.get()
+    }
+
+    /// The total number of crossings and the number of crossings over
+    /// solid markings.
+    pub fn totals(&self) -> (usize, usize) {
+        let counts = self.counts.lock().unwrap();
+        let solid = counts
+            .iter()
+            .filter(|(name, _)| is_solid(name))
+            .map(|(_, count)| count)
+            .sum();
+        (counts.values().sum(), solid)
+    }
+
+    /// Print the number of crossings per marking type.
+    pub fn print_summary(&self) {
+        let (total, solid) = self.totals();
+        let counts = self.counts.lock().unwrap();
+
+        println!("Lane invasions: {total} crossings, {solid} over solid markings");
+        for (name, count) in counts.iter() {
+            println!("  {name}: {count}");
+        }
+    }
+}
+
+impl Drop for LaneInvasionCounter {
+    fn drop(&mut self) {
+        self.sensor.stop();
+    }
+}
+
+fn marking_name(type_: &LaneMarking_Type) -> &'static str {
+    match type_ {
+        LaneMarking_Type::Other => "other",
+        LaneMarking_Type::Broken => "broken",
+        LaneMarking_Type::Solid => "solid",
+        LaneMarking_Type::SolidSolid => "solid_solid",
+        LaneMarking_Type::SolidBroken => "solid_broken",
+        LaneMarking_Type::BrokenSolid => "broken_solid",
+        LaneMarking_Type::BrokenBroken => "broken_broken",
+        LaneMarking_Type::BottsDots => "botts_dots",
+        LaneMarking_Type::Grass => "grass",
+        LaneMarking_Type::Curb => "curb",
+        LaneMarking_Type::None => "none",
+    }
+}
+
+/// Whether the marking forbids crossing on at least one side.
+fn is_solid(name: &str) -> bool {
+    matches!(
+        name,
+        "solid" | "solid_solid" | "solid_broken" | "broken_solid" | "grass" | "curb"
+    )
+}
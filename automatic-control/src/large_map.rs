@@ -0,0 +1,31 @@
+//! Support of the large maps, which stream their tiles around the hero
+//! vehicle.
+//!
+//! The tiles and the actors farther than the streaming distances from
+//! the hero are unloaded, and the unloaded actors become dormant. The
+//! ego vehicle is spawned with the `hero` role so that the streaming
+//! follows it.
+
+use carla::client::{ActorBase, Vehicle, World};
+
+/// The maps split into streamed tiles.
+const LARGE_MAPS: &[&str] = &["Town12", "Town13", "Town15"];
+
+/// The number of ticks to hold the vehicle still while the tiles
+/// around it are loaded.
+const STREAM_TICKS: usize = 40;
+
+pub fn is_large_map(map_name: &str) -> bool {
+    LARGE_MAPS.iter().any(|name| map_name.contains(name))
+}
+
+/// Hold the vehicle still without physics until the tiles around it
+/// are loaded, so that it does not fall through the missing ground
+/// after spawning or teleporting.
+pub fn wait_for_tiles(world: &mut World, vehicle: &Vehicle) {
+    vehicle.set_simulate_physics(false);
+    for _ in 0..STREAM_TICKS {
+        world.tick();
+    }
+    vehicle.set_simulate_physics(true);
+}
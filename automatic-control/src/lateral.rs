@@ -0,0 +1,198 @@
+//! Lateral (steering) controllers.
+
+mod heading;
+mod lqr;
+mod pure_pursuit;
+mod stanley;
+
+pub use heading::*;
+pub use lqr::*;
+pub use pure_pursuit::*;
+pub use stanley::*;
+
+use crate::ego::{normalize_angle, EgoState, VehicleSpec};
+use carla::client::Waypoint;
+use clap::ValueEnum;
+use nalgebra::{Point2, Vector2};
+use std::f32::consts::PI;
+
+/// The waypoints tracked by the lateral controller at one tick.
+pub struct Reference {
+    /// The waypoint closest to the vehicle.
+    pub nearest: Waypoint,
+    /// The waypoint ahead of the vehicle on the lane.
+    pub target: Waypoint,
+    /// The lateral offset in meters of the tracked path from the lane
+    /// center. Positive values are to the right of the lane
+    /// direction.
+    pub offset: f32,
+    /// Whether the vehicle backs up against the lane direction.
+    pub reverse: bool,
+}
+
+impl Reference {
+    /// Compute the error of a `point` with the vehicle yaw with
+    /// respect to the offset path in the travel direction.
+    pub fn error(&self, point: &Point2<f32>, yaw: f32) -> FrenetError {
+        let error = FrenetError::new(point, yaw, &self.nearest);
+        let lateral = error.lateral - self.offset;
+
+        if self.reverse {
+            // The path runs against the lane direction.
+            FrenetError {
+                lateral: -lateral,
+                heading: normalize_angle(error.heading + PI),
+            }
+        } else {
+            FrenetError {
+                lateral,
+                heading: error.heading,
+            }
+        }
+    }
+
+    /// The waypoint `distance` meters ahead of the nearest waypoint
+    /// in the travel direction.
+    pub fn waypoint_ahead(&self, distance: f64) -> Option<Waypoint> {
+        let waypoints = if self.reverse {
+            self.nearest.previous(distance)
+        } else {
+            self.nearest.next(distance)
+        };
+        waypoints.get(0)
+    }
+
+    /// The location of the target waypoint shifted by the offset.
+    pub fn target_point(&self) -> Point2<f32> {
+        offset_point(&self.target, self.offset)
+    }
+}
+
+/// The location of a waypoint shifted laterally by `offset` meters.
+/// Positive values are to the right.
+pub fn offset_point(waypoint: &Waypoint, offset: f32) -> Point2<f32> {
+    let transform = waypoint.transform();
+    let (_, _, yaw) = transform.rotation.euler_angles();
+    let t = &transform.translation;
+    Point2::new(t.x, t.y) + Vector2::new(-yaw.sin(), yaw.cos()) * offset
+}
+
+/// The pose error of a point with respect to the lane center in the
+/// Frenet frame.
+#[derive(Debug, Clone, Copy)]
+pub struct FrenetError {
+    /// The lateral offset in meters. Positive values are to the right
+    /// of the lane center.
+    pub lateral: f32,
+    /// The difference between the vehicle yaw and the lane yaw in
+    /// radians.
+    pub heading: f32,
+}
+
+impl FrenetError {
+    /// Compute the error of a `point` with the vehicle yaw with
+    /// respect to the lane center at `waypoint`.
+    pub fn new(point: &Point2<f32>, yaw: f32, waypoint: &Waypoint) -> Self {
+        let path_transform = waypoint.transform();
+        let (_, _, path_yaw) = path_transform.rotation.euler_angles();
+        let path_point = {
+            let t = &path_transform.translation;
+            Point2::new(t.x, t.y)
+        };
+
+        let right = Vector2::new(-path_yaw.sin(), path_yaw.cos());
+        Self {
+            lateral: (point - path_point).dot(&right),
+            heading: normalize_angle(yaw - path_yaw),
+        }
+    }
+}
+
+/// A controller that computes the steering angle to follow a
+/// reference.
+pub trait LateralController {
+    /// Compute the desired steering angle in radians. Positive values
+    /// steer to the right.
+    fn steer(&mut self, ego: &EgoState, spec: &VehicleSpec, reference: &Reference) -> f32;
+
+    /// Clear the internal states, e.g. after the vehicle is
+    /// teleported.
+    fn reset(&mut self) {}
+}
+
+/// Chooses the distance along the lane to the tracked waypoint.
+#[derive(Debug, Clone)]
+pub struct Lookahead {
+    pub mode: LookaheadMode,
+    /// The distance in meters in the fixed mode, or the minimum
+    /// distance in the speed mode.
+    pub min_distance: f64,
+    /// The distance in meters added per m/s of speed in the speed
+    /// mode.
+    pub gain: f64,
+}
+
+impl Lookahead {
+    /// The lookahead distance in meters at the speed in m/s.
+    pub fn distance(&self, speed: f32) -> f64 {
+        match self.mode {
+            LookaheadMode::Fixed => self.min_distance,
+            LookaheadMode::Speed => self.min_distance + self.gain * speed as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LookaheadMode {
+    /// Look ahead by a constant distance.
+    Fixed,
+    /// Look ahead by a distance proportional to the speed.
+    Speed,
+}
+
+/// Limits the steering angle and its rate of change.
+#[derive(Debug, Clone)]
+pub struct SteeringLimiter {
+    /// The maximum steering angle in radians.
+    pub max_angle: f32,
+    /// The maximum steering rate in rad/s.
+    pub max_rate: f32,
+    prev: f32,
+}
+
+impl SteeringLimiter {
+    pub fn new(max_angle: f32, max_rate: f32) -> Self {
+        Self {
+            max_angle,
+            max_rate,
+            prev: 0.0,
+        }
+    }
+
+    /// Clamp the desired angle in radians and limit its change since
+    /// the previous step. `dt` is the step duration in seconds.
+    pub fn apply(&mut self, angle: f32, dt: f32) -> f32 {
+        let angle = angle.clamp(-self.max_angle, self.max_angle);
+        let max_delta = self.max_rate * dt;
+        let angle = angle.clamp(self.prev - max_delta, self.prev + max_delta);
+        self.prev = angle;
+        angle
+    }
+
+    pub fn reset(&mut self) {
+        self.prev = 0.0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ControllerKind {
+    /// Steer towards the next waypoint by a PID on the heading
+    /// offset.
+    Heading,
+    /// The Stanley steering law.
+    Stanley,
+    /// Follow the circular arc to a lookahead waypoint.
+    PurePursuit,
+    /// The linear-quadratic regulator on the Frenet frame errors.
+    Lqr,
+}
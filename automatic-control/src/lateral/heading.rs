@@ -0,0 +1,38 @@
+use super::{LateralController, Reference};
+use crate::{
+    ego::{normalize_angle, EgoState, VehicleSpec},
+    pid::Pid,
+};
+
+/// Steers by a PID on the heading offset from the vehicle to the
+/// target waypoint.
+#[derive(Debug, Clone)]
+pub struct HeadingController {
+    pid: Pid,
+    /// The duration of a control step in seconds.
+    dt: f32,
+}
+
+impl HeadingController {
+    pub fn new(pid: Pid, dt: f32) -> Self {
+        Self { pid, dt }
+    }
+}
+
+impl LateralController for HeadingController {
+    fn steer(&mut self, ego: &EgoState, _spec: &VehicleSpec, reference: &Reference) -> f32 {
+        // Compute the displacement vector from the car to the target
+        // waypoint.
+        let dir = reference.target_point() - ego.position();
+
+        // Compute the heading offset towards the target waypoint.
+        let target_yaw = dir.y.atan2(dir.x);
+        let heading_offset = normalize_angle(target_yaw - ego.yaw);
+
+        self.pid.step(heading_offset, self.dt)
+    }
+
+    fn reset(&mut self) {
+        self.pid.reset();
+    }
+}
@@ -0,0 +1,158 @@
+use super::{LateralController, Reference};
+use crate::ego::{normalize_angle, EgoState, VehicleSpec};
+use nalgebra::{Matrix1, Matrix1x2, Matrix2, Vector2};
+
+/// The maximum number of Riccati iterations per step.
+const MAX_ITERATIONS: usize = 150;
+
+/// The convergence threshold of the Riccati iteration.
+const TOLERANCE: f32 = 1e-4;
+
+/// The minimum speed in m/s used to linearize the model, below which
+/// the steering has no effect on the heading.
+const MIN_SPEED: f32 = 1.0;
+
+/// A discrete LQR on the lateral and heading errors of the kinematic
+/// bicycle model. The gain is recomputed every step since the model
+/// depends on the speed.
+///
+/// ```text
+/// e_y[k+1] = e_y[k] + v dt e_ψ[k]
+/// e_ψ[k+1] = e_ψ[k] + v dt / L (δ[k] - δ_ff)
+/// ```
+///
+/// The feed-forward angle `δ_ff` follows the lane curvature.
+#[derive(Debug, Clone)]
+pub struct LqrController {
+    /// The state weights on the lateral and heading errors.
+    pub q: Matrix2<f32>,
+    /// The input weight on the steering angle.
+    pub r: f32,
+    /// The duration of a control step in seconds.
+    pub dt: f32,
+}
+
+impl LqrController {
+    pub fn new(q_lateral: f32, q_heading: f32, r: f32, dt: f32) -> Self {
+        Self {
+            q: Matrix2::new(q_lateral, 0.0, 0.0, q_heading),
+            r,
+            dt,
+        }
+    }
+
+    /// Solve the discrete algebraic Riccati equation by fixed-point
+    /// iteration and return the feedback gain.
+    fn gain(&self, a: &Matrix2<f32>, b: &Vector2<f32>) -> Matrix1x2<f32> {
+        let r = Matrix1::new(self.r);
+        let mut p = self.q;
+
+        for _ in 0..MAX_ITERATIONS {
+            let bt_p = b.transpose() * p;
+            let Some(inv) = (r + bt_p * b).try_inverse() else {
+                break;
+            };
+            let next = a.transpose() * p * a - a.transpose() * p * b * inv * bt_p * a + self.q;
+
+            let converged = (next - p).abs().max() < TOLERANCE;
+            p = next;
+            if converged {
+                break;
+            }
+        }
+
+        let bt_p = b.transpose() * p;
+        let inv = (r + bt_p * b).try_inverse().unwrap_or_else(Matrix1::zeros);
+        inv * bt_p * a
+    }
+}
+
+impl LateralController for LqrController {
+    fn steer(&mut self, ego: &EgoState, spec: &VehicleSpec, reference: &Reference) -> f32 {
+        let error = reference.error(&ego.position(), ego.yaw);
+
+        // Estimate the lane curvature from the yaw change between the
+        // nearest and the target waypoints.
+        let curvature = {
+            let near = reference.nearest.transform();
+            let target = reference.target.transform();
+            let (_, _, near_yaw) = near.rotation.euler_angles();
+            let (_, _, target_yaw) = target.rotation.euler_angles();
+            let distance = (target.translation.vector - near.translation.vector).norm();
+
+            if distance > f32::EPSILON {
+                normalize_angle(target_yaw - near_yaw) / distance
+            } else {
+                0.0
+            }
+        };
+
+        let v = ego.speed.max(MIN_SPEED);
+        let a = Matrix2::new(1.0, v * self.dt, 0.0, 1.0);
+        let b = Vector2::new(0.0, v * self.dt / spec.wheelbase);
+        let k = self.gain(&a, &b);
+
+        let feedback = -(k * Vector2::new(error.lateral, error.heading))[0];
+        let feedforward = (spec.wheelbase * curvature).atan();
+        feedback + feedforward
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.05;
+    const WHEELBASE: f32 = 2.8;
+
+    fn model(v: f32) -> (Matrix2<f32>, Vector2<f32>) {
+        let a = Matrix2::new(1.0, v * DT, 0.0, 1.0);
+        let b = Vector2::new(0.0, v * DT / WHEELBASE);
+        (a, b)
+    }
+
+    #[test]
+    fn gain_stabilizes_the_errors() {
+        let lqr = LqrController::new(1.0, 2.0, 10.0, DT);
+        for v in [MIN_SPEED, 5.0, 15.0, 30.0] {
+            let (a, b) = model(v);
+            let k = lqr.gain(&a, &b);
+            assert!(k[0] > 0.0 && k[1] > 0.0);
+
+            let closed = a - b * k;
+            let max_norm = closed
+                .complex_eigenvalues()
+                .iter()
+                .map(|eigenvalue| eigenvalue.re.hypot(eigenvalue.im))
+                .fold(0.0, f32::max);
+            assert!(max_norm < 1.0, "unstable at {v} m/s");
+        }
+    }
+
+    #[test]
+    fn gain_satisfies_the_riccati_equation() {
+        let lqr = LqrController::new(1.0, 2.0, 10.0, DT);
+        let (a, b) = model(10.0);
+        let k = lqr.gain(&a, &b);
+
+        // The cost-to-go of the closed loop from the Lyapunov equation
+        // P = Q + Kᵀ R K + (A - BK)ᵀ P (A - BK) gives back the gain.
+        let closed = a - b * k;
+        let mut p = lqr.q;
+        for _ in 0..10_000 {
+            p = lqr.q + k.transpose() * lqr.r * k + closed.transpose() * p * closed;
+        }
+        let bt_p = b.transpose() * p;
+        let expected = bt_p * a / (lqr.r + (bt_p * b)[0]);
+        assert!((k - expected).abs().max() < 1e-2 * expected.abs().max());
+    }
+
+    #[test]
+    fn heavier_steering_weight_lowers_the_gain() {
+        let (a, b) = model(10.0);
+        let soft = LqrController::new(1.0, 2.0, 100.0, DT).gain(&a, &b);
+        let stiff = LqrController::new(1.0, 2.0, 1.0, DT).gain(&a, &b);
+        assert!(soft[0] < stiff[0]);
+        assert!(soft[1] < stiff[1]);
+    }
+}
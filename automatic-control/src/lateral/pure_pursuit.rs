@@ -0,0 +1,42 @@
+use super::{offset_point, LateralController, Reference};
+use crate::ego::{EgoState, VehicleSpec};
+
+/// The pure-pursuit controller, which steers the rear axle along the
+/// circular arc passing through a waypoint ahead on the lane.
+#[derive(Debug, Clone)]
+pub struct PurePursuitController {
+    /// The distance in meters along the lane to the pursued
+    /// waypoint.
+    pub lookahead: f64,
+}
+
+impl PurePursuitController {
+    pub fn new(lookahead: f64) -> Self {
+        Self { lookahead }
+    }
+}
+
+impl LateralController for PurePursuitController {
+    fn steer(&mut self, ego: &EgoState, spec: &VehicleSpec, reference: &Reference) -> f32 {
+        // Fall back to the tracked waypoint if the lane ends before
+        // the lookahead distance.
+        let goal = reference
+            .waypoint_ahead(self.lookahead)
+            .unwrap_or_else(|| reference.target.clone());
+        let goal = offset_point(&goal, reference.offset);
+
+        let rear_axle = ego.rear_axle(spec);
+        let dir = goal - rear_axle;
+        let distance = dir.norm();
+        if distance <= f32::EPSILON {
+            return 0.0;
+        }
+
+        // The angle between the vehicle heading and the line of sight
+        // to the goal. Positive values are to the right.
+        let heading = ego.heading();
+        let alpha = (heading.x * dir.y - heading.y * dir.x).atan2(heading.dot(&dir));
+
+        (2.0 * spec.wheelbase * alpha.sin()).atan2(distance)
+    }
+}
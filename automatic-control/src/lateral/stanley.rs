@@ -0,0 +1,27 @@
+use super::{LateralController, Reference};
+use crate::ego::{normalize_angle, EgoState, VehicleSpec};
+
+/// The Stanley steering law, which combines the heading error and the
+/// cross-track error measured at the front axle.
+#[derive(Debug, Clone)]
+pub struct StanleyController {
+    /// The gain on the cross-track error.
+    pub gain: f32,
+    /// The speed in m/s added to the denominator to keep the
+    /// controller stable at low speed.
+    pub softening: f32,
+}
+
+impl StanleyController {
+    pub fn new(gain: f32, softening: f32) -> Self {
+        Self { gain, softening }
+    }
+}
+
+impl LateralController for StanleyController {
+    fn steer(&mut self, ego: &EgoState, spec: &VehicleSpec, reference: &Reference) -> f32 {
+        let error = reference.error(&ego.front_axle(spec), ego.yaw);
+        let correction = (-self.gain * error.lateral).atan2(self.softening + ego.speed);
+        normalize_angle(correction - error.heading)
+    }
+}
@@ -0,0 +1,64 @@
+//! The map layers that can be unloaded and loaded again.
+//!
+//! Only the maps with the `_Opt` suffix, such as `Town10HD_Opt`, are
+//! split into layers.
+
+use carla::{client::World, rpc::MapLayer};
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layer {
+    Buildings,
+    Decals,
+    Foliage,
+    Ground,
+    ParkedVehicles,
+    Particles,
+    Props,
+    StreetLights,
+    Walls,
+    All,
+}
+
+impl Layer {
+    fn map_layer(self) -> MapLayer {
+        match self {
+            Self::Buildings => MapLayer::Buildings,
+            Self::Decals => MapLayer::Decals,
+            Self::Foliage => MapLayer::Foliage,
+            Self::Ground => MapLayer::Ground,
+            Self::ParkedVehicles => MapLayer::ParkedVehicles,
+            Self::Particles => MapLayer::Particles,
+            Self::Props => MapLayer::Props,
+            Self::StreetLights => MapLayer::StreetLights,
+            Self::Walls => MapLayer::Walls,
+            Self::All => MapLayer::All,
+        }
+    }
+}
+
+/// Unload the layers, then load the other layers, and tick the world
+/// for the change to take effect.
+pub fn apply(world: &mut World, unload: &[Layer], load: &[Layer]) {
+    for &layer in unload {
+        world.unload_level_layer(layer.map_layer());
+    }
+    for &layer in load {
+        world.load_level_layer(layer.map_layer());
+    }
+    if !unload.is_empty() {
+        eprintln!("Unload the map layers {unload:?}");
+    }
+    if !load.is_empty() {
+        eprintln!("Load the map layers {load:?}");
+    }
+    world.tick();
+}
+
+/// Load the unloaded layers back since the server keeps them unloaded
+/// after the client exits.
+pub fn restore(world: &World, unload: &[Layer]) {
+    for &layer in unload {
+        world.load_level_layer(layer.map_layer());
+    }
+}
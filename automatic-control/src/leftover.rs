@@ -0,0 +1,10 @@
+//! The actors left in the world after a run.
+//!
+//! The client library offers no way to destroy actors, so the vehicles,
+//! pedestrians and props spawned by the run stay in the world until the
+//! map is reloaded.
+
+/// Tell how many actors of the kind stay in the world.
+pub fn report(count: usize, kind: &str) {
+    eprintln!("Left {count} {kind}. Reload the map to remove them.");
+}
@@ -0,0 +1,206 @@
+//! Overrides of the traffic light states and timings to make the runs
+//! repeatable.
+
+use anyhow::{bail, ensure, Error, Result};
+use carla::{
+    client::{ActorBase, TrafficLight, World},
+    rpc::{ActorId, TrafficLightState},
+};
+use nalgebra::Vector3;
+use std::{collections::HashSet, str::FromStr};
+
+/// The green, yellow and red durations in seconds of one traffic light
+/// or of all of them, written as `[ID:]green=S,yellow=S,red=S`. The
+/// durations left out are kept.
+#[derive(Debug, Clone)]
+pub struct LightTiming {
+    pub light_id: Option<ActorId>,
+    pub green: Option<f32>,
+    pub yellow: Option<f32>,
+    pub red: Option<f32>,
+}
+
+impl LightTiming {
+    fn apply(&self, light: &TrafficLight) {
+        if let Some(time) = self.green {
+            light.set_green_time(time);
+        }
+        if let Some(time) = self.yellow {
+            light.set_yellow_time(time);
+        }
+        if let Some(time) = self.red {
+            light.set_red_time(time);
+        }
+    }
+}
+
+impl FromStr for LightTiming {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (light_id, durations) = match text.split_once(':') {
+            Some((id, durations)) => (Some(id.trim().parse()?), durations),
+            None => (None, text),
+        };
+        let mut timing = Self {
+            light_id,
+            green: None,
+            yellow: None,
+            red: None,
+        };
+
+        for item in durations.split(',').map(str::trim) {
+            let Some((key, value)) = item.split_once('=') else {
+                bail!("expect green=S, yellow=S or red=S, but get '{item}'");
+            };
+            let value: f32 = value.trim().parse()?;
+            ensure!(value >= 0.0, "the duration of '{key}' must not be negative");
+            let slot = match key.trim() {
+                "green" => &mut timing.green,
+                "yellow" => &mut timing.yellow,
+                "red" => &mut timing.red,
+                key => bail!("unknown light state '{key}', expect green, yellow or red"),
+            };
+            *slot = Some(value);
+        }
+
+        Ok(timing)
+    }
+}
+
+/// Freezes the traffic lights and forces them green around the vehicle
+/// or everywhere. The lights are unfrozen by [`release`](Self::release)
+/// but the timings stay changed on the server.
+pub struct TrafficLightOverride {
+    lights: Vec<(TrafficLight, Vector3<f32>)>,
+    freeze: bool,
+    /// The radius in meters around the vehicle where the lights are
+    /// green. It is `None` to force all lights green.
+    green_radius: Option<f32>,
+    forced: HashSet<ActorId>,
+}
+
+impl TrafficLightOverride {
+    /// Apply the timings, then freeze all lights at their current
+    /// states if `freeze` is set. The lights are forced green within
+    /// the radius given in `force_green`, or everywhere if the radius
+    /// is `None`.
+    pub fn new(
+        world: &mut World,
+        freeze: bool,
+        force_green: Option<Option<f32>>,
+        timings: &[LightTiming],
+    ) -> Result<Self> {
+        let lights: Vec<_> = world
+            .actors()
+            .filter("traffic.traffic_light")
+            .iter()
+            .filter_map(|actor| TrafficLight::try_from(actor).ok())
+            .map(|light| {
+                let location = light.location().vector;
+                (light, location)
+            })
+            .collect();
+
+        for timing in timings {
+            let mut count = 0;
+            for (light, _) in &lights {
+                if timing.light_id.is_none_or(|id| id == light.id()) {
+                    timing.apply(light);
+                    count += 1;
+                }
+            }
+            if let Some(id) = timing.light_id {
+                ensure!(count > 0, "no traffic light has the id {id}");
+            } else {
+                eprintln!("Change the timings of {count} traffic lights");
+            }
+        }
+
+        if freeze {
+            world.freeze_all_traffic_lights(true);
+            eprintln!("Freeze {} traffic lights", lights.len());
+        }
+
+        let mut this = Self {
+            lights,
+            freeze,
+            green_radius: force_green.flatten(),
+            forced: HashSet::new(),
+        };
+        if force_green == Some(None) {
+            for (light, _) in &this.lights {
+                light.set_state(TrafficLightState::Green);
+                light.freeze(true);
+                this.forced.insert(light.id());
+            }
+            eprintln!("Force {} traffic lights green", this.lights.len());
+        }
+        Ok(this)
+    }
+
+    /// Force the lights entering the radius around the location green
+    /// and release the lights leaving it.
+    pub fn update(&mut self, location: &Vector3<f32>) {
+        let Some(radius) = self.green_radius else {
+            return;
+        };
+        for (light, light_location) in &self.lights {
+            let near = (light_location - location).norm() <= radius;
+            if near && self.forced.insert(light.id()) {
+                light.set_state(TrafficLightState::Green);
+                light.freeze(true);
+            } else if !near && self.forced.remove(&light.id()) {
+                // Keep the light frozen at green if all lights are
+                // frozen.
+                light.freeze(self.freeze);
+            }
+        }
+    }
+
+    /// Let the lights cycle again.
+    pub fn release(&mut self, world: &mut World) {
+        world.freeze_all_traffic_lights(false);
+        self.forced.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_durations() {
+        let timing: LightTiming = "green=10, yellow=2.5,red=0".parse().unwrap();
+        assert_eq!(timing.light_id, None);
+        assert_eq!(timing.green, Some(10.0));
+        assert_eq!(timing.yellow, Some(2.5));
+        assert_eq!(timing.red, Some(0.0));
+    }
+
+    #[test]
+    fn parses_the_light_id() {
+        let timing: LightTiming = "42: red=30".parse().unwrap();
+        assert_eq!(timing.light_id, Some(42));
+        assert_eq!(timing.green, None);
+        assert_eq!(timing.yellow, None);
+        assert_eq!(timing.red, Some(30.0));
+    }
+
+    #[test]
+    fn rejects_negative_durations() {
+        assert!("green=-1".parse::<LightTiming>().is_err());
+        assert!("7:yellow=-0.5".parse::<LightTiming>().is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_states() {
+        assert!("blue=10".parse::<LightTiming>().is_err());
+    }
+
+    #[test]
+    fn rejects_items_without_a_duration() {
+        assert!("green".parse::<LightTiming>().is_err());
+        assert!("42:".parse::<LightTiming>().is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! Phase timings of the traffic lights met along the route and the
+//! waits of the vehicle at them.
+
+use crate::{behavior::light_state_name, events::Event};
+use carla::{
+    client::{ActorBase, TrafficLight, Waypoint, World},
+    rpc::ActorId,
+};
+use nalgebra::Vector3;
+use std::collections::BTreeMap;
+
+/// The speed in m/s below which the vehicle is waiting.
+const WAIT_SPEED: f32 = 0.1;
+
+/// The lights farther than this multiple of the lookahead from the
+/// vehicle are no longer tracked.
+const FORGET_FACTOR: f64 = 2.0;
+
+struct TrackedLight {
+    light: TrafficLight,
+    state: &'static str,
+    /// The time when the current phase started, or `None` if the light
+    /// was first seen in the middle of the phase.
+    since: Option<f64>,
+}
+
+/// The statistics of one traffic light.
+#[derive(Debug, Clone, Default)]
+struct LightStats {
+    /// The number and the total duration in seconds of the complete
+    /// phases by the state.
+    phases: BTreeMap<&'static str, (usize, f64)>,
+    waits: usize,
+    wait_time: f64,
+}
+
+/// Tracks the state transitions of the traffic lights ahead on the
+/// lane and how long the vehicle waits at them.
+pub struct LightPhaseTracker {
+    lookahead: f64,
+    lights: BTreeMap<ActorId, TrackedLight>,
+    /// The light the vehicle is waiting at and the start time.
+    waiting: Option<(ActorId, f64)>,
+    stats: BTreeMap<ActorId, LightStats>,
+}
+
+impl LightPhaseTracker {
+    pub fn new(lookahead: f64) -> Self {
+        Self {
+            lookahead,
+            lights: BTreeMap::new(),
+            waiting: None,
+            stats: BTreeMap::new(),
+        }
+    }
+
+    /// Update the lights after a tick and return the ended phases and
+    /// waits as events.
+    pub fn update(
+        &mut self,
+        world: &World,
+        waypoint: &Waypoint,
+        location: &Vector3<f32>,
+        speed: f32,
+    ) -> Vec<Event> {
+        let time = world.snapshot().timestamp().elapsed_seconds;
+        let mut events = vec![];
+
+        // The lights are returned by the distance along the lane.
+        let ahead = world.traffic_lights_from_waypoint(waypoint, self.lookahead);
+        let ahead: Vec<_> = (0..ahead.len())
+            .filter_map(|index| ahead.get(index))
+            .filter_map(|actor| TrafficLight::try_from(actor).ok())
+            .collect();
+        for light in &ahead {
+            self.lights
+                .entry(light.id())
+                .or_insert_with(|| TrackedLight {
+                    state: light_state_name(light.state()),
+                    light: light.clone(),
+                    since: None,
+                });
+        }
+
+        let forget = (self.lookahead * FORGET_FACTOR) as f32;
+        self.lights
+            .retain(|_, tracked| (tracked.light.location().vector - location).norm() <= forget);
+        for (&id, tracked) in &mut self.lights {
+            let state = light_state_name(tracked.light.state());
+            if state == tracked.state {
+                continue;
+            }
+            if let Some(since) = tracked.since {
+                let duration = time - since;
+                let phase = self
+                    .stats
+                    .entry(id)
+                    .or_default()
+                    .phases
+                    .entry(tracked.state)
+                    .or_default();
+                phase.0 += 1;
+                phase.1 += duration;
+                events.push(Event::TrafficLightPhase {
+                    light_id: id,
+                    state: tracked.state,
+                    duration,
+                });
+            }
+            tracked.state = state;
+            tracked.since = Some(time);
+        }
+
+        // The vehicle waits while it stands before a red or yellow
+        // light.
+        let stopping_light = ahead
+            .first()
+            .filter(|light| matches!(light_state_name(light.state()), "red" | "yellow"))
+            .map(|light| light.id());
+        match (self.waiting, stopping_light) {
+            (None, Some(id)) if speed < WAIT_SPEED => self.waiting = Some((id, time)),
+            (Some((id, since)), _) if speed >= WAIT_SPEED => {
+                let duration = time - since;
+                let stats = self.stats.entry(id).or_default();
+                stats.waits += 1;
+                stats.wait_time += duration;
+                events.push(Event::TrafficLightWait {
+                    light_id: id,
+                    duration,
+                });
+                self.waiting = None;
+            }
+            _ => {}
+        }
+
+        events
+    }
+
+    pub fn print_summary(&self) {
+        let waits: usize = self.stats.values().map(|stats| stats.waits).sum();
+        let wait_time: f64 = self.stats.values().map(|stats| stats.wait_time).sum();
+        println!(
+            "Traffic lights: {} lights, {waits} waits for {wait_time:.1} s",
+            self.stats.len()
+        );
+        for (id, stats) in &self.stats {
+            let phases: Vec<_> = stats
+                .phases
+                .iter()
+                .map(|(state, (count, total))| {
+                    format!("{state} {:.1} s x{count}", total / *count as f64)
+                })
+                .collect();
+            println!(
+                "  {id}: waited {:.1} s in {} stops, mean phases: {}",
+                stats.wait_time,
+                stats.waits,
+                if phases.is_empty() {
+                    "-".to_string()
+                } else {
+                    phases.join(", ")
+                }
+            );
+        }
+    }
+}
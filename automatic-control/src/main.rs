@@ -1,30 +1,210 @@
-use anyhow::{Context, Result};
+mod acc;
+mod actuator;
+mod aeb;
+mod behavior;
+mod calibration;
+mod collision;
+mod counter;
+mod csv_log;
+mod dashboard;
+mod daylight;
+mod ego;
+mod events;
+mod filter;
+mod friction;
+mod hold;
+mod landmarks;
+mod lane_invasion;
+mod large_map;
+mod lateral;
+mod layers;
+mod leftover;
+mod light_override;
+mod light_phases;
+mod mcap_recorder;
+mod npc;
+mod obstacle;
+mod opendrive;
+mod osm;
+mod parquet_log;
+mod pid;
+mod preview;
+mod profile;
+mod prometheus;
+mod props;
+mod rendering;
+#[cfg(feature = "rerun")]
+mod rerun_logger;
+mod route;
+mod settings;
+mod spawn_points;
+mod spectator;
+mod telemetry;
+mod timelapse;
+mod tour;
+mod towns;
+mod traffic;
+mod tui;
+mod tune;
+mod video;
+mod walker_routes;
+mod walkers;
+mod weather;
+
+#[cfg(feature = "rerun")]
+use crate::rerun_logger::RerunLogger;
+use crate::{
+    acc::AdaptiveCruise,
+    actuator::{
+        AckermannActuator, Actuator, Command, ControlMode, DelayedActuator, ThrottleActuator,
+    },
+    aeb::EmergencyBrake,
+    behavior::{CurvatureBehavior, SpeedLimitBehavior, StopSignBehavior, TrafficLightBehavior},
+    calibration::PedalMap,
+    collision::{CollisionMonitor, CollisionReset},
+    counter::FrameCount,
+    csv_log::CsvLogger,
+    dashboard::DashboardServer,
+    daylight::DayNightCycle,
+    ego::{EgoState, VehicleSpec},
+    events::{Event, EventLog},
+    filter::LowPass,
+    friction::{FrictionCurve, WetFriction},
+    hold::HillHold,
+    landmarks::LandmarkScanner,
+    lane_invasion::LaneInvasionCounter,
+    lateral::{
+        ControllerKind, HeadingController, LateralController, Lookahead, LookaheadMode,
+        LqrController, PurePursuitController, Reference, StanleyController, SteeringLimiter,
+    },
+    layers::Layer,
+    light_override::{LightTiming, TrafficLightOverride},
+    light_phases::LightPhaseTracker,
+    mcap_recorder::McapRecorder,
+    npc::{FleetConfig, NpcFleet},
+    obstacle::ObstacleBehavior,
+    opendrive::OpenDriveConfig,
+    osm::OsmConfig,
+    parquet_log::ParquetLogger,
+    pid::Pid,
+    preview::Preview,
+    profile::SpeedProfile,
+    prometheus::{PrometheusServer, TickMetrics},
+    props::{PropSet, PropSpec},
+    rendering::QualityLevel,
+    settings::SettingsGuard,
+    spectator::{CameraMode, SpectatorCamera},
+    telemetry::{Sample, Telemetry},
+    timelapse::{Timelapse, TimelapseConfig},
+    tour::TourOpts,
+    traffic::TrafficConfig,
+    tui::{Tui, TuiFrame},
+    tune::TuneOpts,
+    video::VideoRecorder,
+    walker_routes::{ScriptedWalkers, WalkerRoute},
+    walkers::{CrowdConfig, SpeedDistribution, WalkerCrowd},
+    weather::{DynamicWeather, WeatherSpec},
+};
+use anyhow::{ensure, Context, Result};
 use carla::{
-    client::{ActorBase, Client, Vehicle},
-    rpc::{EpisodeSettings, VehicleAckermannControl},
+    client::{Actor, ActorBase, Client, Map, Vehicle, World},
+    rpc::EpisodeSettings,
+    traffic_manager::TrafficManager,
 };
-use clap::Parser;
-use nalgebra::{Isometry3, Translation3, UnitQuaternion};
-use noisy_float::prelude::*;
+use clap::{Parser, Subcommand};
+use nalgebra::{Isometry3, Vector3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
-    f32::consts::PI,
+    env,
+    io::{self, IsTerminal},
+    net::SocketAddr,
+    path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+/// The simulation time step in seconds.
+const FIXED_DELTA_SECONDS: f64 = 0.05;
+
+/// The gravitational acceleration in m/s².
+const GRAVITY: f32 = 9.81;
+
 fn main() -> Result<()> {
     let opts = Opts::parse();
 
     // Connect to the client and retrieve the world object
     let client = Client::connect(&opts.addr, opts.port, None);
+    if let Some(Action::Towns) = &opts.action {
+        towns::list(&client);
+        return Ok(());
+    }
+    if let Some(world) = &opts.world {
+        towns::validate(&client, world)?;
+    }
+
+    // Register a Ctrl-C handler
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let stop = stop.clone();
+
+        ctrlc::set_handler(move || {
+            stop.store(true, Ordering::SeqCst);
+        })
+        .with_context(|| "Error setting Ctrl-C handler")?;
+    }
+
+    match &opts.action {
+        Some(Action::Tour(tour_opts)) => tour::tour(&client, &opts, tour_opts, &stop),
+        _ => run(&client, &opts, &stop, None).map(|_| ()),
+    }
+}
+
+/// Load the world, spawn the vehicle with the configured sensors and
+/// behaviors, and run the action of `opts`. It returns the metrics of a
+/// plain driving run.
+pub fn run(
+    client: &Client,
+    opts: &Opts,
+    stop: &Arc<AtomicBool>,
+    max_ticks: Option<usize>,
+) -> Result<Option<Metrics>> {
+    ensure!(
+        !(opts.ego_autopilot && opts.action.is_some()),
+        "--ego-autopilot cannot be used with an action"
+    );
+    ensure!(
+        !(opts.plot.is_some() && opts.action.is_some()),
+        "--plot only records the plain driving run"
+    );
 
     // Set the world
-    let mut world = match &opts.world {
-        Some(world) => client.load_world(world),
-        None => {
+    let opendrive = OpenDriveConfig {
+        vertex_distance: opts.opendrive_vertex_distance,
+        max_road_length: opts.opendrive_max_road_length,
+        wall_height: opts.opendrive_wall_height,
+        additional_width: opts.opendrive_additional_width,
+        smooth_junctions: !opts.opendrive_no_smooth_junctions,
+        mesh_visibility: !opts.opendrive_hide_mesh,
+        pedestrian_navigation: !opts.opendrive_no_pedestrians,
+    };
+    let mut world = match (&opts.world, &opts.opendrive, &opts.osm) {
+        (_, _, Some(path)) => {
+            let osm = OsmConfig {
+                python: opts.osm_python.clone(),
+                lane_width: opts.osm_lane_width,
+                traffic_lights: opts.osm_traffic_lights,
+                all_junction_lights: opts.osm_all_junction_lights,
+                center_map: !opts.osm_no_center,
+                proj: opts.osm_proj.clone(),
+            };
+            opendrive.generate(client, &osm.convert(path)?)
+        }
+        (_, Some(path), None) => opendrive.load(client, path)?,
+        (Some(world), None, None) => client.load_world(world),
+        (None, None, None) => {
             // It causes the client to crash.
             // client.reload_world()
 
@@ -32,147 +212,1095 @@ fn main() -> Result<()> {
         }
     };
 
+    if let Some(Action::SpawnPoints) = &opts.action {
+        spawn_points::list(&world.map());
+        return Ok(None);
+    }
+
+    // Look up the vehicle before the synchronous mode, which would stay
+    // on at an error.
+    let mut vblu = world
+        .blueprint_library()
+        .find(&opts.vehicle)
+        .with_context(|| format!("no vehicle blueprint is named '{}'", opts.vehicle))?;
+
     // Set a/synchronous mode
+    let settings = world.settings();
+    let orig_settings = settings.clone();
     world.apply_settings(
         &EpisodeSettings {
             synchronous_mode: true, // Enables synchronous mode
-            fixed_delta_seconds: Some(0.05),
-            ..world.settings()
+            no_rendering_mode: opts.no_rendering || opts.headless,
+            fixed_delta_seconds: Some(FIXED_DELTA_SECONDS),
+            max_culling_distance: opts.quality_level.map_or(
+                settings.max_culling_distance,
+                QualityLevel::max_culling_distance,
+            ),
+            tile_stream_distance: opts
+                .tile_stream_distance
+                .unwrap_or(settings.tile_stream_distance),
+            actor_active_distance: opts
+                .actor_active_distance
+                .unwrap_or(settings.actor_active_distance),
+            ..settings
         },
         Duration::ZERO,
     );
+    let _settings_guard = SettingsGuard::new(&world, orig_settings);
+
+    if let Some(weather) = &opts.weather {
+        weather.apply(&mut world);
+    }
+    if !opts.unload_layers.is_empty() || !opts.load_layers.is_empty() {
+        layers::apply(&mut world, &opts.unload_layers, &opts.load_layers);
+    }
 
-    // Choose a start point randomly
     let map = world.map();
+    let large_map = large_map::is_large_map(&map.name());
+    if large_map {
+        let settings = world.settings();
+        eprintln!(
+            "Stream the tiles of the large map within {:.0} m and the actors within {:.0} m",
+            settings.tile_stream_distance, settings.actor_active_distance
+        );
+    }
 
-    // spawn the vehicle in ...
-    let start_point = Isometry3 {
-        translation: Translation3::new(83.075226, 13.414804, 0.600000),
-        rotation: UnitQuaternion::from_euler_angles(0.0, 0.0, -179.840_79_f32.to_radians()),
+    let event_log = opts.log_events.as_ref().map(EventLog::new).transpose()?;
+
+    // Spawn vehicles
+    // The hybrid physics of the Traffic Manager and the tile streaming
+    // of the large maps center on the hero.
+    let _ = vblu.set_attribute("role_name", "hero");
+    // Choose a start point by the index or randomly
+    let mut rng = match opts.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
     };
-    eprintln!("Spawn a vehicle at {start_point}");
+    let (actor, start_point) = spawn_points::spawn(&mut world, &vblu, opts.spawn_index, &mut rng)?;
+    let vehicle: Vehicle = actor.try_into().unwrap();
+    if large_map {
+        large_map::wait_for_tiles(&mut world, &vehicle);
+    }
+    let spec = VehicleSpec::from_vehicle(&vehicle);
+    eprintln!(
+        "Drive {} with the wheelbase {:.2} m and the steering angle up to {:.1}°",
+        opts.vehicle,
+        spec.wheelbase,
+        spec.max_steer_angle.to_degrees()
+    );
+    vehicle.set_autopilot(false);
 
-    // Register a Ctrl-C handler
-    let stop = Arc::new(AtomicBool::new(false));
-    {
-        let stop = stop.clone();
+    // The Traffic Manager drives the NPC vehicles and optionally the ego
+    // vehicle.
+    let traffic = TrafficConfig {
+        port: opts.tm_port,
+        synchronous: !opts.tm_async,
+        speed_difference: opts.tm_speed_difference,
+        min_distance: opts.tm_min_distance,
+        ignore_lights: opts.tm_ignore_lights,
+        hybrid_physics_radius: opts.tm_hybrid_physics,
+    };
+    let mut traffic_manager =
+        (opts.npc_vehicles > 0 || opts.ego_autopilot).then(|| traffic.connect(client));
+    if let (true, Some(traffic_manager)) = (opts.ego_autopilot, &mut traffic_manager) {
+        traffic.drive(traffic_manager, &vehicle);
+        eprintln!("The Traffic Manager drives the vehicle");
+    }
+    if let Some(events) = &event_log {
+        let location = start_point.translation;
+        events.log(
+            &world,
+            Event::Spawn {
+                actor_id: vehicle.id(),
+                type_id: vehicle.type_id(),
+                location: [location.x, location.y, location.z],
+            },
+        )?;
+    }
 
-        ctrlc::set_handler(move || {
-            stop.store(true, Ordering::SeqCst);
+    let npcs = traffic_manager
+        .as_mut()
+        .filter(|_| opts.npc_vehicles > 0)
+        .map(|traffic_manager| {
+            NpcFleet::spawn(
+                traffic_manager,
+                &traffic,
+                &mut world,
+                &FleetConfig {
+                    count: opts.npc_vehicles,
+                    filter: opts.npc_filter.clone(),
+                    no_bikes: opts.npc_no_bikes,
+                },
+                &start_point,
+                &mut rng,
+            )
         })
-        .with_context(|| "Error setting Ctrl-C handler")?;
+        .transpose()?;
+    let crowd = CrowdConfig {
+        count: match (opts.walker_density, opts.walker_radius) {
+            (Some(density), Some(radius)) => CrowdConfig::count_for_density(density, radius),
+            _ => opts.walkers,
+        },
+        radius: opts.walker_radius,
+        cross_factor: opts.walker_cross_factor,
+        speed: opts.walker_speed,
+    };
+    let walkers = (crowd.count > 0)
+        .then(|| WalkerCrowd::spawn(&mut world, &crowd, &start_point, &mut rng))
+        .transpose()?;
+    let scripted_walkers = opts
+        .walker_routes
+        .as_ref()
+        .map(|path| ScriptedWalkers::spawn(&mut world, &WalkerRoute::load(path)?))
+        .transpose()?;
+    let mut prop_specs = opts.prop.clone();
+    if let Some(path) = &opts.props_file {
+        prop_specs.extend(PropSpec::load(path)?);
+    }
+    let props = (!prop_specs.is_empty())
+        .then(|| PropSet::spawn(&mut world, &prop_specs))
+        .transpose()?;
+    if let Some(events) = &event_log {
+        let npcs = npcs.iter().flat_map(|npcs| npcs.vehicles()).cloned();
+        let walkers = walkers
+            .iter()
+            .flat_map(|walkers| walkers.walkers())
+            .cloned();
+        let scripted_walkers = scripted_walkers
+            .iter()
+            .flat_map(|walkers| walkers.walkers())
+            .cloned();
+        let props = props.iter().flat_map(|props| props.props()).cloned();
+        for actor in npcs
+            .map(Vehicle::into_actor)
+            .chain(walkers)
+            .chain(scripted_walkers)
+            .chain(props)
+        {
+            let location = actor.location();
+            events.log(
+                &world,
+                Event::Spawn {
+                    actor_id: actor.id(),
+                    type_id: actor.type_id(),
+                    location: [location.x, location.y, location.z],
+                },
+            )?;
+        }
     }
 
-    // Spawn vehicles
-    let vblu = world
-        .blueprint_library()
-        .find("vehicle.tesla.model3")
-        .unwrap();
-    let vehicle: Vehicle = world.spawn_actor(&vblu, &start_point)?.try_into().unwrap();
-    vehicle.set_autopilot(false);
+    let light_override =
+        (opts.freeze_lights || opts.force_green.is_some() || !opts.light_timing.is_empty())
+            .then(|| {
+                TrafficLightOverride::new(
+                    &mut world,
+                    opts.freeze_lights,
+                    opts.force_green,
+                    &opts.light_timing,
+                )
+            })
+            .transpose()?;
 
     let spectator = world.spectator();
+    let acc = opts
+        .acc
+        .then(|| AdaptiveCruise::new(&mut world, &vehicle, opts.time_gap, opts.min_distance))
+        .transpose()?;
+    let aeb = opts
+        .aeb
+        .then(|| {
+            EmergencyBrake::new(
+                &mut world,
+                &vehicle,
+                opts.aeb_distance,
+                opts.aeb_ttc,
+                opts.aeb_min_distance,
+            )
+        })
+        .transpose()?;
+    let obstacles = opts
+        .obstacle_detection
+        .then(|| {
+            ObstacleBehavior::new(
+                &mut world,
+                &vehicle,
+                opts.obstacle_distance,
+                opts.obstacle_hit_radius,
+                opts.obstacle_margin,
+                opts.stop_deceleration,
+            )
+        })
+        .transpose()?;
+    let collisions = (!opts.ignore_collisions)
+        .then(|| CollisionMonitor::new(&mut world, &vehicle))
+        .transpose()?;
+    let lane_invasions = (!opts.ignore_lane_invasions)
+        .then(|| LaneInvasionCounter::new(&mut world, &vehicle, event_log.clone()))
+        .transpose()?;
+    let preview = opts
+        .preview
+        .then(|| {
+            Preview::new(
+                &mut world,
+                &vehicle,
+                opts.preview_width,
+                opts.preview_height,
+            )
+        })
+        .transpose()?;
+    let dashboard = opts.dashboard.map(DashboardServer::new).transpose()?;
+    let prometheus = opts.prometheus.map(PrometheusServer::new).transpose()?;
+    let mcap_recorder = opts
+        .mcap
+        .as_ref()
+        .map(|path| McapRecorder::new(path, &mut world, &vehicle, FIXED_DELTA_SECONDS))
+        .transpose()?;
+    let csv_logger = opts.log_csv.as_ref().map(CsvLogger::new).transpose()?;
+    let parquet_logger = opts
+        .log_parquet
+        .as_ref()
+        .map(|path| {
+            let metadata = [
+                ("map", map.name()),
+                ("vehicle", vehicle.type_id()),
+                ("fixed_delta_seconds", FIXED_DELTA_SECONDS.to_string()),
+                ("command_line", env::args().collect::<Vec<_>>().join(" ")),
+            ];
+            ParquetLogger::new(path, &metadata)
+        })
+        .transpose()?;
+    let video_recorder = opts
+        .record_video
+        .as_ref()
+        .map(|path| {
+            VideoRecorder::new(
+                path,
+                &mut world,
+                &vehicle,
+                opts.video_width,
+                opts.video_height,
+                FIXED_DELTA_SECONDS,
+            )
+        })
+        .transpose()?;
+    let timelapse = opts
+        .timelapse
+        .as_ref()
+        .map(|path| {
+            let config = TimelapseConfig {
+                every: opts.timelapse_every,
+                max_frames: opts.timelapse_max_frames,
+                width: opts.timelapse_width,
+                height: opts.timelapse_height,
+                fps: opts.timelapse_fps,
+            };
+            Timelapse::new(path, &mut world, &vehicle, &config, FIXED_DELTA_SECONDS)
+        })
+        .transpose()?;
+    #[cfg(feature = "rerun")]
+    let rerun_logger = opts
+        .rerun
+        .then(|| RerunLogger::new(&mut world, &vehicle, FIXED_DELTA_SECONDS))
+        .transpose()?;
 
-    while !stop.load(Ordering::SeqCst) {
-        // Get the current waypoint.
-        let vehicle_transform = vehicle.transform();
-        let vehicle_location = vehicle_transform.translation;
+    // Take over the terminal after the setup messages are printed.
+    let tui =
+        (!opts.no_tui && !opts.headless && opts.action.is_none() && io::stdout().is_terminal())
+            .then(|| Tui::new(stop.clone()))
+            .transpose()?;
 
-        let Some(curr_waypoint) = map.waypoint(&vehicle_location) else {
-            vehicle.set_transform(&start_point);
-            continue;
-        };
+    let mut session = Session {
+        world,
+        map,
+        vehicle,
+        npcs,
+        traffic_manager,
+        walkers,
+        scripted_walkers,
+        props,
+        light_override,
+        light_phases: opts
+            .traffic_light_stats
+            .then(|| LightPhaseTracker::new(opts.traffic_light_lookahead)),
+        spectator,
+        start_point,
+        rng,
+        large_map,
+        stop: stop.clone(),
+        acc,
+        aeb,
+        obstacles,
+        collisions,
+        lane_invasions,
+        preview,
+        telemetry: opts.plot.is_some().then(Telemetry::default),
+        dashboard,
+        prometheus,
+        mcap_recorder,
+        csv_logger,
+        parquet_logger,
+        event_log,
+        video_recorder,
+        timelapse,
+        tui,
+        #[cfg(feature = "rerun")]
+        rerun_logger,
+    };
 
-        // Choose a next waypoint
-        let Some(next_waypoint) = curr_waypoint.next(1.0).get(0) else {
-            vehicle.set_transform(&start_point);
-            continue;
-        };
+    let metrics = match &opts.action {
+        None => Some(session.drive(opts, max_ticks)?),
+        Some(Action::Tune(tune_opts)) => {
+            tune::tune(&mut session, opts, tune_opts)?;
+            None
+        }
+        Some(Action::SpawnPoints | Action::Towns) => {
+            unreachable!("the listings are printed before the setup")
+        }
+        Some(Action::Tour(_)) => unreachable!("the tour runs the towns without the action"),
+    };
 
-        // Set the spectator viewpoint
-        let s_point = vehicle_transform * Translation3::new(-10.0, 0.0, 7.0);
-        spectator.set_transform(&s_point);
+    // Restore the terminal before printing the summaries.
+    session.tui = None;
 
-        // Compute the displacement vector from the car to the next
-        // waypoint.
-        let next_location = next_waypoint.transform().translation;
-        let dir = next_location.vector - vehicle_transform.translation.vector;
+    if let Some(metrics) = &metrics {
+        metrics.print_summary();
+    }
 
-        // Compute the heading offset towards the next waypoint.
-        let heading_offset = {
-            let (_, _, current_yaw) = vehicle_transform.rotation.euler_angles();
-            let target_yaw = (dir.y).atan2(dir.x);
+    if let Some(lane_invasions) = &session.lane_invasions {
+        lane_invasions.print_summary();
+    }
+    if let Some(light_phases) = &session.light_phases {
+        light_phases.print_summary();
+    }
+    if let (Some(telemetry), Some(path)) = (&session.telemetry, &opts.plot) {
+        telemetry.plot(path)?;
+    }
+    if let Some(recorder) = &mut session.mcap_recorder {
+        recorder.finish()?;
+    }
+    if let Some(logger) = &mut session.csv_logger {
+        logger.finish()?;
+    }
+    let sensors = session.sensor_frames();
+    if let Some(logger) = &mut session.parquet_logger {
+        logger.finish(&sensors)?;
+    }
+    if let Some(events) = &session.event_log {
+        events.finish()?;
+    }
+    if let Some(recorder) = &mut session.video_recorder {
+        recorder.finish()?;
+    }
+    if let Some(timelapse) = &mut session.timelapse {
+        timelapse.finish()?;
+    }
 
-            let current_yaw = current_yaw; // Change range [0, 2π] to [-π, π]
-            let offset = target_yaw - current_yaw;
+    // Restore the world settings. The episode settings are restored by
+    // the guard when it is dropped.
+    if let Some(light_override) = &mut session.light_override {
+        light_override.release(&mut session.world);
+    }
+    if let Some(traffic_manager) = &mut session.traffic_manager {
+        traffic_manager.set_synchronous_mode(false);
+    }
+    let world = &mut session.world;
+    layers::restore(world, &opts.unload_layers);
 
-            if offset >= PI {
-                offset - PI * 2.0
-            } else if offset <= -PI {
-                offset + PI * 2.0
-            } else {
-                offset
+    Ok(metrics)
+}
+
+/// The simulation objects shared by the driving episodes.
+pub struct Session {
+    pub world: World,
+    pub map: Map,
+    pub vehicle: Vehicle,
+    pub npcs: Option<NpcFleet>,
+    /// It is declared after the NPC vehicles so that it shuts down only
+    /// after they are released.
+    pub traffic_manager: Option<TrafficManager>,
+    pub walkers: Option<WalkerCrowd>,
+    pub scripted_walkers: Option<ScriptedWalkers>,
+    pub props: Option<PropSet>,
+    pub light_override: Option<TrafficLightOverride>,
+    pub light_phases: Option<LightPhaseTracker>,
+    pub spectator: Actor,
+    pub start_point: Isometry3<f32>,
+    /// The random choices of the run, seeded by --seed.
+    pub rng: StdRng,
+    /// Wait for the tiles to stream in after teleporting the vehicle.
+    pub large_map: bool,
+    pub stop: Arc<AtomicBool>,
+    pub acc: Option<AdaptiveCruise>,
+    pub aeb: Option<EmergencyBrake>,
+    pub obstacles: Option<ObstacleBehavior>,
+    pub collisions: Option<CollisionMonitor>,
+    pub lane_invasions: Option<LaneInvasionCounter>,
+    pub preview: Option<Preview>,
+    pub telemetry: Option<Telemetry>,
+    pub dashboard: Option<DashboardServer>,
+    pub prometheus: Option<PrometheusServer>,
+    pub mcap_recorder: Option<McapRecorder>,
+    pub csv_logger: Option<CsvLogger>,
+    pub parquet_logger: Option<ParquetLogger>,
+    pub event_log: Option<EventLog>,
+    pub video_recorder: Option<VideoRecorder>,
+    pub timelapse: Option<Timelapse>,
+    pub tui: Option<Tui>,
+    #[cfg(feature = "rerun")]
+    pub rerun_logger: Option<RerunLogger>,
+}
+
+/// The tracking errors accumulated while driving.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    /// The number of controlled ticks.
+    pub ticks: usize,
+    /// The sum of the absolute cross-track errors in meters.
+    pub lateral_error: f32,
+    /// The sum of the absolute speed tracking errors in m/s.
+    pub speed_error: f32,
+    /// The number of times the vehicle left the road and was
+    /// teleported back.
+    pub resets: usize,
+    /// The number of collisions.
+    pub collisions: usize,
+    /// The wall time spent driving.
+    pub wall_time: Duration,
+}
+
+impl Metrics {
+    /// Print the mean tracking errors, which show how the controllers
+    /// cope with the vehicle, e.g. the longer wheelbase of a truck, and
+    /// the tick throughput.
+    pub fn print_summary(&self) {
+        let ticks = self.ticks.max(1) as f32;
+        println!(
+            "Tracking: {} ticks, mean cross-track error {:.3} m, mean speed error {:.3} m/s, \
+             {} resets, {} collisions",
+            self.ticks,
+            self.lateral_error / ticks,
+            self.speed_error / ticks,
+            self.resets,
+            self.collisions
+        );
+        let seconds = self.wall_time.as_secs_f64().max(f64::EPSILON);
+        let rate = self.ticks as f64 / seconds;
+        println!(
+            "Throughput: {rate:.1} ticks/s, {:.1}x real time",
+            rate * FIXED_DELTA_SECONDS
+        );
+    }
+}
+
+/// The measurements received by the sensors so far.
+fn sensor_frames(
+    acc: &Option<AdaptiveCruise>,
+    aeb: &Option<EmergencyBrake>,
+    obstacles: &Option<ObstacleBehavior>,
+    collisions: &Option<CollisionMonitor>,
+    lane_invasions: &Option<LaneInvasionCounter>,
+) -> Vec<(&'static str, FrameCount)> {
+    let mut sensors = vec![];
+    if let Some(acc) = acc {
+        sensors.push(("radar", acc.frames()));
+    }
+    if let Some(aeb) = aeb {
+        sensors.push(("aeb obstacle", aeb.frames()));
+    }
+    if let Some(obstacles) = obstacles {
+        sensors.push(("obstacle", obstacles.frames()));
+    }
+    if let Some(collisions) = collisions {
+        sensors.push(("collision", collisions.frames()));
+    }
+    if let Some(lane_invasions) = lane_invasions {
+        sensors.push(("lane invasion", lane_invasions.frames()));
+    }
+    sensors
+}
+
+impl Session {
+    /// The measurements received by the sensors so far.
+    pub fn sensor_frames(&self) -> Vec<(&'static str, FrameCount)> {
+        sensor_frames(
+            &self.acc,
+            &self.aeb,
+            &self.obstacles,
+            &self.collisions,
+            &self.lane_invasions,
+        )
+    }
+
+    /// Place the vehicle at rest on the start point.
+    pub fn reset(&mut self) {
+        self.vehicle.set_transform(&self.start_point);
+        self.vehicle.set_target_velocity(&Vector3::zeros());
+        self.vehicle.set_target_angular_velocity(&Vector3::zeros());
+        self.world.tick();
+        if self.large_map {
+            large_map::wait_for_tiles(&mut self.world, &self.vehicle);
+        }
+        if let Some(collisions) = &self.collisions {
+            collisions.take();
+        }
+    }
+
+    /// Drive the vehicle with the controllers configured by `opts`
+    /// until Ctrl-C is pressed or `max_ticks` ticks elapse.
+    pub fn drive(&mut self, opts: &Opts, max_ticks: Option<usize>) -> Result<Metrics> {
+        let Self {
+            world,
+            map,
+            vehicle,
+            walkers,
+            scripted_walkers,
+            light_override,
+            light_phases,
+            spectator,
+            start_point,
+            rng,
+            large_map,
+            stop,
+            acc,
+            aeb,
+            obstacles,
+            collisions,
+            lane_invasions,
+            preview,
+            telemetry,
+            dashboard,
+            prometheus,
+            mcap_recorder,
+            csv_logger,
+            parquet_logger,
+            event_log,
+            tui,
+            #[cfg(feature = "rerun")]
+            rerun_logger,
+            ..
+        } = self;
+
+        let spec = VehicleSpec::from_vehicle(vehicle);
+        let cruise_speed = if opts.reverse {
+            opts.reverse_speed
+        } else {
+            opts.target_speed
+        };
+        let cruise_speed = cruise_speed * 10.0 / 36.0;
+        let mut speed_pid = Pid::new(opts.speed_kp, opts.speed_ki, opts.speed_kd)
+            .with_limits(-opts.max_deceleration, opts.max_acceleration);
+        let mut lateral: Box<dyn LateralController> = match opts.controller {
+            ControllerKind::Heading => Box::new(HeadingController::new(
+                Pid::new(opts.steer_kp, opts.steer_ki, opts.steer_kd),
+                FIXED_DELTA_SECONDS as f32,
+            )),
+            ControllerKind::Stanley => Box::new(StanleyController::new(
+                opts.stanley_gain,
+                opts.stanley_softening,
+            )),
+            ControllerKind::PurePursuit => {
+                Box::new(PurePursuitController::new(opts.pursuit_lookahead))
             }
+            ControllerKind::Lqr => Box::new(LqrController::new(
+                opts.lqr_q_lateral,
+                opts.lqr_q_heading,
+                opts.lqr_r,
+                FIXED_DELTA_SECONDS as f32,
+            )),
+        };
+        let mut actuator: Box<dyn Actuator> = match opts.control_mode {
+            ControlMode::Ackermann => Box::new(AckermannActuator {
+                steer_speed: opts.max_steer_rate,
+                max_steer_angle: spec.max_steer_angle,
+                max_deceleration: opts.max_deceleration,
+                brake_deadband: opts.brake_deadband,
+            }),
+            ControlMode::Throttle => Box::new(ThrottleActuator {
+                max_steer_angle: spec.max_steer_angle,
+                max_acceleration: opts.max_acceleration,
+                max_deceleration: opts.max_deceleration,
+                brake_deadband: opts.brake_deadband,
+                pedal_map: opts.pedal_map.as_ref().map(PedalMap::load).transpose()?,
+            }),
+        };
+        if opts.actuator_delay > 0.0 {
+            let delay_ticks = (opts.actuator_delay / 1000.0 / FIXED_DELTA_SECONDS).round() as usize;
+            eprintln!(
+                "Delay the actuator by {delay_ticks} ticks ({:.0} ms)",
+                delay_ticks as f64 * FIXED_DELTA_SECONDS * 1000.0
+            );
+            actuator = Box::new(DelayedActuator::new(
+                actuator,
+                delay_ticks,
+                opts.log_commands,
+            ));
         }
-        .to_degrees();
-
-        // Compute the steering ratio
-        let steer = {
-            let physics_control = vehicle.physics_control();
-            let max_steer_angle = physics_control
-                .wheels
-                .iter()
-                .map(|wheel| r32(wheel.max_steer_angle))
-                .max()
-                .expect("Unable to obtain max steering angle from the vehicle")
-                .raw();
-            (heading_offset / max_steer_angle).clamp(-1.0, 1.0)
+        let lookahead = Lookahead {
+            mode: opts.lookahead_mode,
+            min_distance: opts.lookahead,
+            gain: opts.lookahead_gain,
         };
-
-        // Compute the steering speed
-        let steer_speed = if heading_offset.abs() < 3.0 {
+        let mut steering = SteeringLimiter::new(spec.max_steer_angle, opts.max_steer_rate);
+        let smoothing = if opts.no_spectator_smoothing {
             0.0
-        } else if heading_offset > 0.0 {
-            0.1
         } else {
-            -0.1
+            opts.spectator_smoothing
         };
+        let mut camera = SpectatorCamera::new(opts.camera, !opts.fixed_spectator, smoothing);
+        let mut steer_filter = LowPass::new(opts.steer_time_constant);
+        let mut speed_filter = LowPass::new(opts.speed_time_constant);
+        let mut profile =
+            SpeedProfile::new(opts.max_acceleration, opts.max_deceleration, opts.max_jerk);
+        let mut traffic_lights = (!opts.ignore_traffic_lights && !opts.reverse).then(|| {
+            TrafficLightBehavior::new(
+                opts.traffic_light_lookahead,
+                opts.stop_margin,
+                opts.stop_deceleration,
+            )
+        });
+        let mut speed_limits = (opts.obey_speed_limits && !opts.reverse).then(|| {
+            SpeedLimitBehavior::new(
+                opts.speed_limit_lookahead,
+                opts.speed_limit_offset,
+                opts.stop_deceleration,
+            )
+        });
+        let mut landmarks = (opts.obey_stop_signs || event_log.is_some())
+            .then(|| LandmarkScanner::new(opts.landmark_lookahead));
+        let mut stop_signs = (opts.obey_stop_signs && !opts.reverse).then(|| {
+            StopSignBehavior::new(
+                opts.stop_margin,
+                opts.stop_deceleration,
+                opts.stop_sign_wait,
+            )
+        });
+        let curvature = (!opts.ignore_curvature && !opts.reverse).then(|| {
+            CurvatureBehavior::new(
+                opts.curvature_lookahead,
+                opts.max_lateral_acceleration,
+                opts.stop_deceleration,
+            )
+        });
+        let mut hill_hold = (!opts.no_hill_hold).then(|| HillHold::new(opts.hill_hold_speed));
+        let mut wet_friction = opts
+            .wet_friction
+            .clone()
+            .map(|curve| WetFriction::new(vehicle, curve));
+        let mut weather = opts
+            .dynamic_weather
+            .map(|speed_factor| DynamicWeather::new(world, speed_factor));
+        let mut daylight = opts
+            .day_night_cycle
+            .map(|day_minutes| DayNightCycle::new(day_minutes, opts.start_hour));
+        let mut metrics = Metrics::default();
+        let start = Instant::now();
 
-        // Get the current car speed.
-        let vehicle_speed = vehicle.velocity().norm();
+        // The ticks spent on the resets count toward `max_ticks` too, so
+        // that a start point without a reference cannot hang the run.
+        let mut ticks = 0;
+        while !stop.load(Ordering::SeqCst) && max_ticks.is_none_or(|max| ticks < max) {
+            ticks += 1;
+            let ego = EgoState::from_vehicle(vehicle);
 
-        // Compute the acceleration
-        let acceleration = if vehicle_speed < 5.0 { 1.0 } else { 0.0 };
+            // Drive in reverse as if the vehicle faced backwards.
+            let ego = if opts.reverse { ego.reversed() } else { ego };
 
-        // Apply the control to the car
-        let control = VehicleAckermannControl {
-            //TODO: the parameter of 'steer' has bug
-            steer,
-            steer_speed,
-            speed: opts.target_speed * 10.0 / 36.0,
-            acceleration,
-            jerk: 0.0,
-        };
-        vehicle.apply_ackermann_control(&control);
+            // Restart instead of driving on after a crash
+            let collision = collisions.as_ref().and_then(CollisionMonitor::take);
+            let restart_point = collision.as_ref().map(|collision| {
+                eprintln!(
+                    "Collision at frame {} with {} at ({:.1}, {:.1}, {:.1}), impulse {:.0} N·s",
+                    collision.frame,
+                    collision.other.as_deref().unwrap_or("the static scene"),
+                    collision.location.x,
+                    collision.location.y,
+                    collision.location.z,
+                    collision.impulse
+                );
+                metrics.collisions += 1;
+                if let Some(server) = prometheus {
+                    server.add_infraction("collision");
+                }
 
-        world.tick();
-    }
+                let spawn_points = map.recommended_spawn_points();
+                match opts.collision_reset {
+                    CollisionReset::SpawnPoint if !spawn_points.is_empty() => {
+                        let index = rng.gen_range(0..spawn_points.len());
+                        spawn_points.get(index).unwrap()
+                    }
+                    _ => *start_point,
+                }
+            });
 
-    // Restore the world settings
-    world.apply_settings(
-        &EpisodeSettings {
-            synchronous_mode: false,
-            fixed_delta_seconds: None,
-            ..world.settings()
-        },
-        Duration::ZERO,
-    );
+            if let (Some(events), Some(collision)) = (&*event_log, &collision) {
+                let location = collision.location;
+                events.log_at(
+                    collision.frame,
+                    collision.timestamp,
+                    Event::Collision {
+                        other: collision.other.clone(),
+                        impulse: collision.impulse,
+                        location: [location.x, location.y, location.z],
+                    },
+                )?;
+            }
+
+            // Get the current waypoint and choose a next waypoint
+            let reference = map
+                .waypoint(&ego.transform.translation)
+                .filter(|_| restart_point.is_none())
+                .and_then(|nearest| {
+                    // Keep the vehicle center within the lane.
+                    let half_width = nearest.lane_width() as f32 / 2.0;
+                    let offset = opts.lane_offset.clamp(-half_width, half_width);
+
+                    let mut reference = Reference {
+                        target: nearest.clone(),
+                        nearest,
+                        offset,
+                        reverse: opts.reverse,
+                    };
+                    reference.target = reference.waypoint_ahead(lookahead.distance(ego.speed))?;
+                    Some(reference)
+                });
+            let Some(reference) = reference else {
+                if restart_point.is_none() {
+                    metrics.resets += 1;
+                    if let Some(server) = prometheus {
+                        server.add_infraction("off_road_reset");
+                    }
+                }
+                let restart_point = restart_point.unwrap_or(*start_point);
+                if let Some(events) = event_log {
+                    let location = restart_point.translation;
+                    let reason = if collision.is_some() {
+                        "collision"
+                    } else {
+                        "off_road"
+                    };
+                    events.log(
+                        world,
+                        Event::Reset {
+                            reason,
+                            location: [location.x, location.y, location.z],
+                        },
+                    )?;
+                }
+                vehicle.set_transform(&restart_point);
+                vehicle.set_target_velocity(&Vector3::zeros());
+                vehicle.set_target_angular_velocity(&Vector3::zeros());
+                speed_pid.reset();
+                lateral.reset();
+                steering.reset();
+                steer_filter.reset();
+                speed_filter.reset();
+                profile.reset();
+                actuator.reset();
+                if let Some(hold) = &mut hill_hold {
+                    hold.reset();
+                }
+                world.tick();
+                if *large_map {
+                    large_map::wait_for_tiles(world, vehicle);
+                }
+
+                // Drop the events of the crash reported meanwhile.
+                if let Some(collisions) = collisions {
+                    collisions.take();
+                }
+                continue;
+            };
 
-    Ok(())
+            // Set the spectator viewpoint
+            if !opts.no_spectator && !opts.no_rendering && !opts.headless {
+                if let Some(s_point) =
+                    camera.update(world, &ego.transform, FIXED_DELTA_SECONDS as f32)
+                {
+                    spectator.set_transform(&s_point);
+                }
+            }
+
+            // Compute the steering angle
+            let raw_steer = lateral.steer(&ego, &spec, &reference);
+
+            // The steered wheels trail when backing up, which flips the
+            // steering direction.
+            let raw_steer = if opts.reverse { -raw_steer } else { raw_steer };
+            let filtered_steer = steer_filter.apply(raw_steer, FIXED_DELTA_SECONDS as f32);
+            let steer = steering.apply(filtered_steer, FIXED_DELTA_SECONDS as f32);
+
+            // Limit the target speed by the behaviors
+            let mut target_speed = cruise_speed;
+            if let Some(behavior) = &mut traffic_lights {
+                if let Some(max_speed) = behavior.max_speed(world, &ego, &reference.nearest) {
+                    target_speed = target_speed.min(max_speed);
+                }
+                if let (Some(events), Some(event)) = (&*event_log, behavior.take_event()) {
+                    events.log(world, event)?;
+                }
+            }
+            if let Some(tracker) = light_phases {
+                let location = ego.transform.translation.vector;
+                for event in tracker.update(world, &reference.nearest, &location, ego.speed) {
+                    if let Some(events) = event_log {
+                        events.log(world, event)?;
+                    }
+                }
+            }
+            let landmarks_ahead = match &mut landmarks {
+                Some(scanner) => {
+                    let (landmarks_ahead, events) = scanner.scan(&reference.nearest);
+                    if let Some(log) = event_log {
+                        for event in events {
+                            log.log(world, event)?;
+                        }
+                    }
+                    landmarks_ahead
+                }
+                None => vec![],
+            };
+            if let Some(behavior) = &mut stop_signs {
+                if let Some(max_speed) =
+                    behavior.max_speed(&landmarks_ahead, &ego, FIXED_DELTA_SECONDS as f32)
+                {
+                    target_speed = target_speed.min(max_speed);
+                }
+                if let (Some(events), Some(event)) = (&*event_log, behavior.take_event()) {
+                    events.log(world, event)?;
+                }
+            }
+            if let Some(behavior) = &mut speed_limits {
+                if let Some(max_speed) = behavior.max_speed(vehicle, &reference.nearest) {
+                    target_speed = target_speed.min(max_speed);
+                }
+            }
+            if let Some(behavior) = &curvature {
+                if let Some(max_speed) = behavior.max_speed(&reference.nearest) {
+                    target_speed = target_speed.min(max_speed);
+                }
+            }
+            if let Some(acc) = acc {
+                if let Some(max_speed) = acc.max_speed(&ego) {
+                    target_speed = target_speed.min(max_speed);
+                }
+            }
+            if let Some(behavior) = obstacles {
+                let lane_width = reference.nearest.lane_width() as f32;
+                if let Some(max_speed) = behavior.max_speed(lane_width, world.snapshot().frame()) {
+                    target_speed = target_speed.min(max_speed);
+                }
+            }
+
+            // Smooth the speed command
+            let raw_speed = target_speed;
+            let target_speed = speed_filter.apply(raw_speed, FIXED_DELTA_SECONDS as f32);
+
+            if opts.log_commands {
+                println!(
+                    "steer raw={raw_steer:.4} filtered={steer:.4} \
+                     speed raw={raw_speed:.3} filtered={target_speed:.3}"
+                );
+            }
+
+            // Follow a jerk-limited profile towards the target speed. The
+            // PID corrects the tracking error on top of the profile
+            // acceleration.
+            let setpoint = profile.step(ego.speed, target_speed, FIXED_DELTA_SECONDS as f32);
+            let feedback = speed_pid.step(setpoint.speed - ego.speed, FIXED_DELTA_SECONDS as f32);
+
+            // Compensate the gravity along the slope.
+            let slope = if opts.ignore_slope {
+                0.0
+            } else {
+                GRAVITY * ego.pitch.sin()
+            };
+
+            let acceleration = (setpoint.acceleration + feedback + slope)
+                .clamp(-opts.max_deceleration, opts.max_acceleration);
+
+            let error = reference.error(&ego.position(), ego.yaw);
+            metrics.ticks += 1;
+            metrics.lateral_error += error.lateral.abs();
+            metrics.speed_error += (setpoint.speed - ego.speed).abs();
+
+            if let Some(telemetry) = telemetry {
+                telemetry.push(Sample {
+                    time: world.snapshot().timestamp().elapsed_seconds,
+                    speed: ego.speed,
+                    target_speed,
+                    steer,
+                    heading_error: error.heading,
+                    lateral_error: error.lateral,
+                });
+            }
+
+            // Override the controller if a collision is imminent
+            let emergency = aeb
+                .as_mut()
+                .is_some_and(|aeb| aeb.update(&ego, world.snapshot().frame()));
+            if emergency {
+                if opts.constant_velocity {
+                    vehicle.disable_constant_velocity();
+                }
+                speed_pid.reset();
+                profile.reset();
+                actuator.reset();
+            }
+
+            // Hold the vehicle by the handbrake once stopped. The speed
+            // controller is kept idle meanwhile.
+            let hand_brake = hill_hold
+                .as_mut()
+                .is_some_and(|hold| hold.update(raw_speed, ego.speed));
+            if hand_brake {
+                speed_pid.reset();
+                profile.reset();
+            }
+
+            // Let the simulator hold the speed along the vehicle heading.
+            // The actuator only steers effectively in this mode.
+            if opts.constant_velocity && !emergency {
+                let speed = if opts.reverse {
+                    -target_speed
+                } else {
+                    target_speed
+                };
+                vehicle.enable_constant_velocity(&Vector3::new(speed, 0.0, 0.0));
+            }
+
+            let timestamp = world.snapshot().timestamp().elapsed_seconds;
+            let command = if emergency {
+                Command {
+                    steer: 0.0,
+                    speed: 0.0,
+                    acceleration: -opts.max_deceleration,
+                    jerk: 0.0,
+                    timestamp,
+                    reverse: false,
+                    hand_brake: true,
+                }
+            } else {
+                Command {
+                    steer,
+                    speed: setpoint.speed,
+                    acceleration,
+                    jerk: setpoint.jerk.abs(),
+                    timestamp,
+                    reverse: opts.reverse,
+                    hand_brake,
+                }
+            };
+            if emergency {
+                vehicle.apply_control(&EmergencyBrake::control());
+            } else if !opts.ego_autopilot {
+                actuator.apply(vehicle, &command);
+            }
+
+            if let Some(recorder) = mcap_recorder {
+                recorder.record(&ego, &command, target_speed)?;
+            }
+            if let Some(logger) = csv_logger {
+                logger.log(world, vehicle, &ego, &reference, &command, target_speed)?;
+            }
+            if let Some(logger) = parquet_logger {
+                logger.log(world, vehicle, &ego, &reference, &command, target_speed)?;
+            }
+
+            #[cfg(feature = "rerun")]
+            if let Some(logger) = rerun_logger {
+                let frame = world.snapshot().frame();
+                logger.log(frame, &ego, &reference, &command, target_speed)?;
+            }
+
+            let tick_start = Instant::now();
+            let server_frame = world.tick();
+            let tick_duration = tick_start.elapsed();
+
+            if let Some(weather) = &mut weather {
+                weather.update(world, FIXED_DELTA_SECONDS as f32);
+            }
+            if let Some(daylight) = &mut daylight {
+                daylight.update(world, vehicle, FIXED_DELTA_SECONDS as f32);
+            }
+            if let Some(friction) = &mut wet_friction {
+                if let Some(scale) = friction.update(world, vehicle) {
+                    if let Some(events) = event_log {
+                        events.log(world, Event::parameter("tire_friction_scale", scale))?;
+                    }
+                }
+            }
+            if let Some(walkers) = walkers {
+                walkers.update(map, FIXED_DELTA_SECONDS as f32);
+            }
+            if let Some(walkers) = scripted_walkers {
+                walkers.update(FIXED_DELTA_SECONDS as f32);
+            }
+            if let Some(light_override) = light_override {
+                light_override.update(&vehicle.location().vector);
+            }
+
+            if let Some(preview) = preview {
+                preview.update()?;
+                for key in preview.keys() {
+                    if camera.handle_key(key) {
+                        if let Some(events) = event_log {
+                            events.log(world, Event::parameter("camera", camera.mode()))?;
+                        }
+                    }
+                }
+            }
+            if let Some(dashboard) = dashboard {
+                dashboard.publish(world, vehicle, ego.speed, target_speed)?;
+            }
+            let sensors = sensor_frames(acc, aeb, obstacles, collisions, lane_invasions);
+            if let Some(server) = prometheus {
+                server.update(&TickMetrics {
+                    frame: server_frame,
+                    tick_duration,
+                    frame_lag: server_frame.saturating_sub(world.snapshot().frame() as u64),
+                    speed: ego.speed,
+                    target_speed,
+                    sensors: &sensors,
+                    lane_invasions: lane_invasions.as_ref().map(LaneInvasionCounter::totals),
+                });
+            }
+            if let Some(tui) = tui {
+                for key in tui.keys()? {
+                    if camera.handle_key(key) {
+                        if let Some(events) = event_log {
+                            events.log(world, Event::parameter("camera", camera.mode()))?;
+                        }
+                    }
+                }
+                let state = TuiFrame {
+                    frame: world.snapshot().frame(),
+                    ego: &ego,
+                    target_speed,
+                    command: &command,
+                    camera: camera.mode(),
+                    sensors,
+                    collisions: metrics.collisions,
+                    resets: metrics.resets,
+                    lane_invasions: lane_invasions.as_ref().map(LaneInvasionCounter::totals),
+                };
+                tui.draw(world, &state)?;
+            }
+        }
+
+        if opts.constant_velocity {
+            vehicle.disable_constant_velocity();
+        }
+        if let Some(friction) = &mut wet_friction {
+            friction.restore(vehicle);
+        }
+        metrics.wall_time = start.elapsed();
+
+        Ok(metrics)
+    }
 }
 
-#[derive(Parser)]
-struct Opts {
+#[derive(Clone, Parser)]
+pub struct Opts {
     #[clap(long, default_value = "localhost")]
     pub addr: String,
 
@@ -182,6 +1310,707 @@ struct Opts {
     #[clap(long)]
     pub world: Option<String>,
 
+    /// The distance in meters from the vehicle within which the tiles
+    /// of the large maps such as Town12 and Town13 are loaded.
+    #[clap(long)]
+    pub tile_stream_distance: Option<f32>,
+
+    /// The distance in meters from the vehicle within which the actors
+    /// of the large maps are simulated. The farther ones are dormant.
+    #[clap(long)]
+    pub actor_active_distance: Option<f32>,
+
+    /// Generate the world from an OpenDRIVE file instead of loading a
+    /// map.
+    #[clap(long, value_name = "FILE", conflicts_with = "world")]
+    pub opendrive: Option<PathBuf>,
+
+    /// The distance in meters between the vertices of the generated
+    /// road mesh.
+    #[clap(long, default_value = "2.0")]
+    pub opendrive_vertex_distance: f64,
+
+    /// The maximum length in meters of a piece of the generated road
+    /// mesh.
+    #[clap(long, default_value = "50.0")]
+    pub opendrive_max_road_length: f64,
+
+    /// The height in meters of the walls generated at the road
+    /// borders.
+    #[clap(long, default_value = "1.0")]
+    pub opendrive_wall_height: f64,
+
+    /// The width in meters added to the junction lanes of the
+    /// generated roads.
+    #[clap(long, default_value = "0.6")]
+    pub opendrive_additional_width: f64,
+
+    /// Do not smooth the junctions of the generated roads.
+    #[clap(long)]
+    pub opendrive_no_smooth_junctions: bool,
+
+    /// Hide the generated road mesh.
+    #[clap(long)]
+    pub opendrive_hide_mesh: bool,
+
+    /// Do not generate the navigation mesh of the pedestrians.
+    #[clap(long)]
+    pub opendrive_no_pedestrians: bool,
+
+    /// Convert an OpenStreetMap file to OpenDRIVE and generate the
+    /// world from it with the --opendrive-* parameters. The conversion
+    /// runs in the CARLA Python package.
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["world", "opendrive"])]
+    pub osm: Option<PathBuf>,
+
+    /// The Python interpreter with the CARLA package used by --osm.
+    #[clap(long, default_value = "python3")]
+    pub osm_python: String,
+
+    /// The width in meters of the lanes whose width is not given in
+    /// the OpenStreetMap file.
+    #[clap(long, default_value = "4.0")]
+    pub osm_lane_width: f64,
+
+    /// Generate traffic lights at the junctions tagged with them.
+    #[clap(long)]
+    pub osm_traffic_lights: bool,
+
+    /// Generate traffic lights at all junctions.
+    #[clap(long)]
+    pub osm_all_junction_lights: bool,
+
+    /// Keep the projected coordinates instead of centering the map at
+    /// the origin.
+    #[clap(long)]
+    pub osm_no_center: bool,
+
+    /// The PROJ string of the projection from the geographic
+    /// coordinates, which defaults to the one of the converter.
+    #[clap(long)]
+    pub osm_proj: Option<String>,
+
+    #[clap(subcommand)]
+    pub action: Option<Action>,
+
+    /// The blueprint of the vehicle, e.g. vehicle.carlamotors.carlacola
+    /// or vehicle.carlamotors.firetruck to drive a truck.
+    #[clap(long, default_value = "vehicle.tesla.model3")]
+    pub vehicle: String,
+
+    /// Spawn the vehicle at the recommended spawn point of the index
+    /// listed by the spawn-points subcommand. A random spawn point is
+    /// chosen if it is not given. The other spawn points are tried if
+    /// the chosen one is occupied.
+    #[clap(long, value_name = "INDEX")]
+    pub spawn_index: Option<usize>,
+
+    /// The seed of the random choices of the client, which are the
+    /// spawn point, the restart points after collisions, and the NPC
+    /// vehicles and pedestrians.
+    #[clap(long)]
+    pub seed: Option<u64>,
+
+    /// Set the weather by a preset such as ClearNoon, WetCloudySunset
+    /// or HardRainNight, followed by comma-separated key=value
+    /// overrides, e.g. `ClearNoon,fog_density=30,sun_altitude_angle=10`.
+    /// The keys are the fields of the CARLA weather parameters. The
+    /// overrides apply to the current weather without a preset.
+    #[clap(long, allow_hyphen_values = true)]
+    pub weather: Option<WeatherSpec>,
+
+    /// The comma-separated map layers to unload for faster or cleaner
+    /// runs, e.g. `buildings,foliage,parked-vehicles`. They are loaded
+    /// back on exit. Only the `_Opt` maps have layers.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    pub unload_layers: Vec<Layer>,
+
+    /// The comma-separated map layers to load after the unloaded ones,
+    /// e.g. `--unload-layers all --load-layers buildings`.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    pub load_layers: Vec<Layer>,
+
+    /// Evolve the sun and a cycle of storms while driving, starting
+    /// from the weather above. The value is the speed factor of the
+    /// changes, which defaults to 1.
+    #[clap(long, num_args = 0..=1, default_missing_value = "1.0")]
+    pub dynamic_weather: Option<f32>,
+
+    /// Scale the tire friction of the vehicle down on wet roads. The
+    /// value maps the road wetness from 0 to 100, the strongest of the
+    /// precipitation, the puddles and the wetness of the weather, to
+    /// the friction scale by `WET=SCALE` points, which default to
+    /// `0=1.0,50=0.85,100=0.7`.
+    #[clap(
+        long,
+        value_name = "CURVE",
+        num_args = 0..=1,
+        default_missing_value = "0=1.0,50=0.85,100=0.7"
+    )]
+    pub wet_friction: Option<FrictionCurve>,
+
+    /// Move the sun through the hours of the day and switch the street
+    /// lights and the headlights at dusk and dawn. The value is the
+    /// length of a day in minutes of simulation time, which defaults to
+    /// 10.
+    /// It cannot be used with --dynamic-weather, which also moves the
+    /// sun.
+    #[clap(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "10.0",
+        conflicts_with = "dynamic_weather"
+    )]
+    pub day_night_cycle: Option<f32>,
+
+    /// The hour of the day from 0 to 24 where --day-night-cycle starts.
+    #[clap(long, default_value = "12.0")]
+    pub start_hour: f32,
+
+    /// The number of autopilot vehicles spawned at random spawn points
+    /// as background traffic.
+    #[clap(long, default_value = "0")]
+    pub npc_vehicles: usize,
+
+    /// The blueprint pattern of the NPC vehicles.
+    #[clap(long, default_value = "vehicle.*")]
+    pub npc_filter: String,
+
+    /// Exclude the bicycles and motorcycles from the NPC vehicles.
+    #[clap(long)]
+    pub npc_no_bikes: bool,
+
+    /// The number of pedestrians spawned on the sidewalks. They are
+    /// moved along the sidewalks by the client since the AI walker
+    /// controller is not available to it.
+    #[clap(long, default_value = "0")]
+    pub walkers: usize,
+
+    /// The number of pedestrians per hectare spawned within
+    /// --walker-radius instead of --walkers.
+    #[clap(long, conflicts_with = "walkers", requires = "walker_radius")]
+    pub walker_density: Option<f32>,
+
+    /// Spawn the pedestrians within the radius in meters around the
+    /// start point.
+    #[clap(long)]
+    pub walker_radius: Option<f32>,
+
+    /// The probability from 0 to 1 that a pedestrian crosses the road
+    /// at the end of its sidewalk instead of turning around.
+    #[clap(long, default_value = "0.0")]
+    pub walker_cross_factor: f32,
+
+    /// The normal distribution of the walking speeds in m/s, written as
+    /// MEAN[,STDDEV].
+    #[clap(long, default_value = "1.4,0.2")]
+    pub walker_speed: SpeedDistribution,
+
+    /// Spawn pedestrians walking the scripted routes of a JSON file,
+    /// each a list of target locations with dwell times.
+    #[clap(long, value_name = "FILE")]
+    pub walker_routes: Option<PathBuf>,
+
+    /// Spawn a static prop written as `BLUEPRINT@X,Y[,Z[,YAW]]`, e.g.
+    /// `cone@80,13` or `barrier@60,13.4,0,90`. The short names are
+    /// cone, traffic-cone, barrier, box and barrel. The prop is placed
+    /// on the nearest road without the height. It may be given multiple
+    /// times.
+    #[clap(long, allow_hyphen_values = true)]
+    pub prop: Vec<PropSpec>,
+
+    /// Spawn the props listed in a file of one `BLUEPRINT@X,Y[,Z[,YAW]]`
+    /// per line, with `#` comments.
+    #[clap(long, value_name = "FILE")]
+    pub props_file: Option<PathBuf>,
+
+    /// Let the Traffic Manager drive the vehicle. The controllers keep
+    /// running and are logged, but their commands are not applied.
+    #[clap(long, conflicts_with_all = ["aeb", "constant_velocity", "reverse"])]
+    pub ego_autopilot: bool,
+
+    /// The port of the Traffic Manager.
+    #[clap(long, default_value = "8000")]
+    pub tm_port: u16,
+
+    /// Run the Traffic Manager asynchronously to the ticks of the
+    /// client.
+    #[clap(long)]
+    pub tm_async: bool,
+
+    /// The percentage below the speed limit driven by the autopilot
+    /// vehicles. Negative values exceed the limit.
+    #[clap(long, allow_negative_numbers = true)]
+    pub tm_speed_difference: Option<f32>,
+
+    /// The distance in meters kept by the autopilot vehicles to the
+    /// leading vehicle.
+    #[clap(long)]
+    pub tm_min_distance: Option<f32>,
+
+    /// The percentage of the red lights run by the autopilot vehicles.
+    #[clap(long, default_value = "0.0")]
+    pub tm_ignore_lights: f32,
+
+    /// Simulate the physics of the autopilot vehicles only within the
+    /// radius in meters around the ego vehicle, which defaults to 50.
+    #[clap(long, num_args = 0..=1, default_missing_value = "50.0")]
+    pub tm_hybrid_physics: Option<f32>,
+
+    /// The target speed in km/h.
     #[clap(long, default_value = "5.0")]
     pub target_speed: f32,
+
+    /// Back up along the lane in reverse gear. The behaviors looking
+    /// ahead on the lane are disabled, and --acc, --aeb and
+    /// --obstacle-detection, which rely on front sensors, cannot be used.
+    #[clap(long, conflicts_with_all = ["acc", "aeb", "obstacle_detection"])]
+    pub reverse: bool,
+
+    /// The target speed in km/h when driving in reverse.
+    #[clap(long, default_value = "5.0")]
+    pub reverse_speed: f32,
+
+    /// Do not engage the handbrake at stops.
+    #[clap(long)]
+    pub no_hill_hold: bool,
+
+    /// The vehicle speed in m/s below which the handbrake engages at
+    /// stops.
+    #[clap(long, default_value = "0.3")]
+    pub hill_hold_speed: f32,
+
+    /// The proportional gain of the speed controller.
+    #[clap(long, default_value = "0.5")]
+    pub speed_kp: f32,
+
+    /// The integral gain of the speed controller.
+    #[clap(long, default_value = "0.05")]
+    pub speed_ki: f32,
+
+    /// The derivative gain of the speed controller.
+    #[clap(long, default_value = "0.0")]
+    pub speed_kd: f32,
+
+    /// The maximum acceleration in m/s² commanded by the speed
+    /// controller.
+    #[clap(long, default_value = "3.0")]
+    pub max_acceleration: f32,
+
+    /// The maximum deceleration in m/s² commanded by the speed
+    /// controller.
+    #[clap(long, default_value = "6.0")]
+    pub max_deceleration: f32,
+
+    /// Drive at the target speed enforced by the simulator instead of
+    /// the speed controller.
+    #[clap(long)]
+    pub constant_velocity: bool,
+
+    /// Disable the gravity feed-forward on slopes.
+    #[clap(long)]
+    pub ignore_slope: bool,
+
+    /// The maximum jerk in m/s³ of the speed profile.
+    #[clap(long, default_value = "2.0")]
+    pub max_jerk: f32,
+
+    /// The deceleration in m/s² above which the brake is applied
+    /// instead of coasting.
+    #[clap(long, default_value = "0.3")]
+    pub brake_deadband: f32,
+
+    /// The proportional gain of the heading steering controller.
+    #[clap(long, default_value = "1.0")]
+    pub steer_kp: f32,
+
+    /// The integral gain of the heading steering controller.
+    #[clap(long, default_value = "0.0")]
+    pub steer_ki: f32,
+
+    /// The derivative gain of the heading steering controller.
+    #[clap(long, default_value = "0.05")]
+    pub steer_kd: f32,
+
+    /// The maximum steering rate in rad/s.
+    #[clap(long, default_value = "1.0")]
+    pub max_steer_rate: f32,
+
+    /// The time constant in seconds of the steering command filter.
+    /// Zero disables the filter.
+    #[clap(long, default_value = "0.1")]
+    pub steer_time_constant: f32,
+
+    /// The time constant in seconds of the speed command filter. Zero
+    /// disables the filter.
+    #[clap(long, default_value = "0.3")]
+    pub speed_time_constant: f32,
+
+    /// Print the raw and filtered commands on every tick, and the
+    /// issued and applied timestamps of delayed commands.
+    #[clap(long)]
+    pub log_commands: bool,
+
+    /// The backend that applies the commands to the vehicle.
+    #[clap(long, value_enum, default_value = "ackermann")]
+    pub control_mode: ControlMode,
+
+    /// The actuator latency in milliseconds, rounded to whole ticks.
+    /// Zero applies the commands immediately.
+    #[clap(long, default_value = "0.0")]
+    pub actuator_delay: f64,
+
+    /// The pedal map recorded by the `calibrate` binary, used by the
+    /// throttle control mode.
+    #[clap(long)]
+    pub pedal_map: Option<PathBuf>,
+
+    /// The lateral controller used to steer the vehicle.
+    #[clap(long, value_enum, default_value = "heading")]
+    pub controller: ControllerKind,
+
+    /// How the distance to the tracked waypoint is chosen.
+    #[clap(long, value_enum, default_value = "fixed")]
+    pub lookahead_mode: LookaheadMode,
+
+    /// The distance in meters along the lane to the tracked waypoint,
+    /// or the minimum distance in the speed mode.
+    #[clap(long, default_value = "1.0")]
+    pub lookahead: f64,
+
+    /// The lookahead distance in meters added per m/s of speed in the
+    /// speed mode.
+    #[clap(long, default_value = "0.5")]
+    pub lookahead_gain: f64,
+
+    /// The lateral offset in meters of the tracked path from the lane
+    /// center, positive to the right. It is clamped within the lane.
+    #[clap(long, default_value = "0.0", allow_negative_numbers = true)]
+    pub lane_offset: f32,
+
+    /// The cross-track error gain of the Stanley controller.
+    #[clap(long, default_value = "1.0")]
+    pub stanley_gain: f32,
+
+    /// The softening speed in m/s of the Stanley controller.
+    #[clap(long, default_value = "1.0")]
+    pub stanley_softening: f32,
+
+    /// The lookahead distance in meters of the pure-pursuit
+    /// controller.
+    #[clap(long, default_value = "6.0")]
+    pub pursuit_lookahead: f64,
+
+    /// The LQR weight on the lateral error.
+    #[clap(long, default_value = "1.0")]
+    pub lqr_q_lateral: f32,
+
+    /// The LQR weight on the heading error.
+    #[clap(long, default_value = "1.0")]
+    pub lqr_q_heading: f32,
+
+    /// The LQR weight on the steering angle.
+    #[clap(long, default_value = "5.0")]
+    pub lqr_r: f32,
+
+    /// Drive through traffic lights regardless of their states.
+    #[clap(long)]
+    pub ignore_traffic_lights: bool,
+
+    /// The distance in meters ahead on the lane to look for traffic
+    /// lights.
+    #[clap(long, default_value = "40.0")]
+    pub traffic_light_lookahead: f64,
+
+    /// Track the phases of the traffic lights ahead and the waits of
+    /// the vehicle at them. They are logged to --log-events and
+    /// summarized on exit.
+    #[clap(long)]
+    pub traffic_light_stats: bool,
+
+    /// Freeze all traffic lights at their current states, which makes
+    /// the runs repeatable.
+    #[clap(long)]
+    pub freeze_lights: bool,
+
+    /// Force the traffic lights green within the radius in meters
+    /// around the vehicle, or everywhere if no radius is given.
+    #[clap(long, value_name = "RADIUS")]
+    pub force_green: Option<Option<f32>>,
+
+    /// Override the durations in seconds of the traffic light states,
+    /// e.g. `green=10,yellow=3` for all lights or `42:red=5` for the
+    /// light with the actor id 42. It may be given multiple times.
+    #[clap(long, value_name = "[ID:]STATE=S,...")]
+    pub light_timing: Vec<LightTiming>,
+
+    /// The distance in meters kept before the stop line.
+    #[clap(long, default_value = "1.0")]
+    pub stop_margin: f32,
+
+    /// The deceleration in m/s² used to plan stops.
+    #[clap(long, default_value = "3.0")]
+    pub stop_deceleration: f32,
+
+    /// Cap the target speed by the posted speed limits.
+    #[clap(long)]
+    pub obey_speed_limits: bool,
+
+    /// The percentage added to the posted speed limits, e.g. -10 to
+    /// drive 10% below the limits.
+    #[clap(long, default_value = "0.0", allow_negative_numbers = true)]
+    pub speed_limit_offset: f32,
+
+    /// The distance in meters ahead on the lane to look for speed
+    /// limit signs.
+    #[clap(long, default_value = "50.0")]
+    pub speed_limit_lookahead: f64,
+
+    /// Stop at the stop signs of the map before driving on.
+    #[clap(long)]
+    pub obey_stop_signs: bool,
+
+    /// The time in seconds to stand at a stop sign.
+    #[clap(long, default_value = "2.0")]
+    pub stop_sign_wait: f32,
+
+    /// The distance in meters ahead on the lane to look for the signs
+    /// and signals of the map. They are logged to --log-events when
+    /// they come into this distance.
+    #[clap(long, default_value = "50.0")]
+    pub landmark_lookahead: f64,
+
+    /// Keep the target speed regardless of the lane curvature.
+    #[clap(long)]
+    pub ignore_curvature: bool,
+
+    /// The maximum lateral acceleration in m/s² allowed in turns.
+    #[clap(long, default_value = "2.5")]
+    pub max_lateral_acceleration: f32,
+
+    /// The distance in meters ahead on the lane inspected for turns.
+    #[clap(long, default_value = "40.0")]
+    pub curvature_lookahead: f64,
+
+    /// Enable the adaptive cruise control using a front radar.
+    #[clap(long)]
+    pub acc: bool,
+
+    /// The time gap in seconds kept from the lead vehicle.
+    #[clap(long, default_value = "2.0")]
+    pub time_gap: f32,
+
+    /// The minimum distance in meters kept from the lead vehicle.
+    #[clap(long, default_value = "5.0")]
+    pub min_distance: f32,
+
+    /// Drive on after collisions instead of restarting the run.
+    #[clap(long)]
+    pub ignore_collisions: bool,
+
+    /// Where to place the vehicle after a collision.
+    #[clap(long, value_enum, default_value = "start")]
+    pub collision_reset: CollisionReset,
+
+    /// Do not count the crossings of lane markings.
+    #[clap(long)]
+    pub ignore_lane_invasions: bool,
+
+    /// Slow down behind the obstacles in the lane detected by the
+    /// obstacle sensor.
+    #[clap(long)]
+    pub obstacle_detection: bool,
+
+    /// The detection distance in meters of the obstacle sensor.
+    #[clap(long, default_value = "30.0")]
+    pub obstacle_distance: f32,
+
+    /// The radius in meters of the sphere swept by the obstacle
+    /// sensor.
+    #[clap(long, default_value = "0.5")]
+    pub obstacle_hit_radius: f32,
+
+    /// The distance in meters kept behind obstacles.
+    #[clap(long, default_value = "5.0")]
+    pub obstacle_margin: f32,
+
+    /// Enable the automatic emergency braking.
+    #[clap(long)]
+    pub aeb: bool,
+
+    /// The detection distance in meters of the obstacle sensor.
+    #[clap(long, default_value = "20.0")]
+    pub aeb_distance: f32,
+
+    /// The time-to-collision in seconds that triggers the emergency
+    /// brake.
+    #[clap(long, default_value = "1.5")]
+    pub aeb_ttc: f32,
+
+    /// The obstacle distance in meters that always triggers the
+    /// emergency brake.
+    #[clap(long, default_value = "3.0")]
+    pub aeb_min_distance: f32,
+
+    /// Plot the speed, steering and tracking errors over the run to an
+    /// SVG file at exit.
+    #[clap(long)]
+    pub plot: Option<PathBuf>,
+
+    /// Record the camera, the lidar, the ego pose and the control
+    /// commands to an MCAP file, which can be opened in Foxglove
+    /// Studio.
+    #[clap(long)]
+    pub mcap: Option<PathBuf>,
+
+    /// Write the pose, the velocity, the commands, the tracking errors
+    /// and the nearest waypoint of every tick to a CSV file.
+    #[clap(long)]
+    pub log_csv: Option<PathBuf>,
+
+    /// Write the spawns, collisions, lane invasions, traffic light
+    /// stops, resets and parameter changes to a JSON lines file.
+    #[clap(long)]
+    pub log_events: Option<PathBuf>,
+
+    /// Write the same columns as --log-csv to a Parquet file, which
+    /// is smaller and faster to load for long runs. The run settings
+    /// and the sensor measurement counts are stored in the file
+    /// metadata.
+    #[clap(long)]
+    pub log_parquet: Option<PathBuf>,
+
+    /// Serve the vehicle status to the `dashboard` binary on the
+    /// address, which defaults to 127.0.0.1:7878.
+    #[clap(long, num_args = 0..=1, default_missing_value = "127.0.0.1:7878")]
+    pub dashboard: Option<SocketAddr>,
+
+    /// Serve the tick duration, the frame lag, the sensor counts, the
+    /// speed and the infraction counters to Prometheus at /metrics on
+    /// the address, which defaults to 127.0.0.1:9898.
+    #[clap(long, num_args = 0..=1, default_missing_value = "127.0.0.1:9898")]
+    pub prometheus: Option<SocketAddr>,
+
+    /// Record a chase camera to a video file by ffmpeg, which must be
+    /// installed.
+    #[clap(long)]
+    pub record_video: Option<PathBuf>,
+
+    /// The width in pixels of the video.
+    #[clap(long, default_value = "1280")]
+    pub video_width: usize,
+
+    /// The height in pixels of the video.
+    #[clap(long, default_value = "720")]
+    pub video_height: usize,
+
+    /// Save a timelapse of a chase camera to an animated GIF, or an
+    /// APNG if the file ends with .png or .apng, at exit.
+    #[clap(long)]
+    pub timelapse: Option<PathBuf>,
+
+    /// Capture a timelapse frame every this many ticks.
+    #[clap(long, default_value = "10")]
+    pub timelapse_every: usize,
+
+    /// The most timelapse frames. The captured frames are halved when
+    /// the limit is reached.
+    #[clap(long, default_value = "200")]
+    pub timelapse_max_frames: usize,
+
+    /// The width in pixels of the timelapse.
+    #[clap(long, default_value = "480")]
+    pub timelapse_width: usize,
+
+    /// The height in pixels of the timelapse.
+    #[clap(long, default_value = "270")]
+    pub timelapse_height: usize,
+
+    /// The playback frame rate of the timelapse.
+    #[clap(long, default_value = "10")]
+    pub timelapse_fps: u16,
+
+    /// Print the plain messages instead of the terminal view, which is
+    /// also the case when stdout is not a terminal.
+    #[clap(long)]
+    pub no_tui: bool,
+
+    /// Stop the server from rendering and the spectator from following
+    /// the vehicle, which ticks as fast as possible for data collection
+    /// and saves the GPU on headless machines. The cameras render
+    /// nothing in this mode. The original rendering setting is restored
+    /// at exit.
+    #[clap(long, conflicts_with_all = ["preview", "record_video", "timelapse"])]
+    pub no_rendering: bool,
+
+    /// The rendering quality, which sets the draw distance of the map
+    /// meshes. The shading quality is chosen at the server launch by
+    /// -quality-level=Low or Epic.
+    #[clap(long, value_enum)]
+    pub quality_level: Option<QualityLevel>,
+
+    /// Leave the spectator where it is instead of following the
+    /// vehicle.
+    #[clap(long)]
+    pub no_spectator: bool,
+
+    /// Run on a headless machine, which is --no-rendering without the
+    /// terminal view.
+    #[clap(long, conflicts_with_all = ["preview", "record_video", "timelapse"])]
+    pub headless: bool,
+
+    /// The spectator placement. Press c in the preview window or the
+    /// terminal view to cycle the modes, or 1 to 5 to choose one.
+    #[clap(long, value_enum, default_value = "chase")]
+    pub camera: CameraMode,
+
+    /// Keep the chase camera at the fixed offset behind the vehicle
+    /// even when a building or the terrain blocks the view.
+    #[clap(long)]
+    pub fixed_spectator: bool,
+
+    /// The fraction from 0 to 1 of the distance to the target
+    /// spectator pose left behind in a tick. Higher values follow the
+    /// vehicle more smoothly but with more lag.
+    #[clap(long, default_value = "0.6")]
+    pub spectator_smoothing: f32,
+
+    /// Place the spectator exactly on each tick for deterministic
+    /// captures.
+    #[clap(long)]
+    pub no_spectator_smoothing: bool,
+
+    /// Show a chase camera behind the vehicle in a window.
+    #[clap(long)]
+    pub preview: bool,
+
+    /// The width in pixels of the preview.
+    #[clap(long, default_value = "800")]
+    pub preview_width: usize,
+
+    /// The height in pixels of the preview.
+    #[clap(long, default_value = "450")]
+    pub preview_height: usize,
+
+    /// Stream the sensors and the controller state to a rerun viewer,
+    /// which must be started beforehand.
+    #[cfg(feature = "rerun")]
+    #[clap(long)]
+    pub rerun: bool,
+}
+
+#[derive(Clone, Subcommand)]
+pub enum Action {
+    /// Search the controller gains that minimize the tracking errors
+    /// over repeated episodes.
+    Tune(TuneOpts),
+    /// Print the recommended spawn points of the map with their
+    /// indices, coordinates and nearest roads and lanes.
+    SpawnPoints,
+    /// Print the maps available on the server, which are the values
+    /// taken by --world.
+    Towns,
+    /// Drive a fixed-duration episode in each of the towns and compare
+    /// the tracking metrics by town.
+    Tour(TourOpts),
 }
@@ -0,0 +1,356 @@
+//! Record the sensors and the controller state to an MCAP file.
+//!
+//! The messages are JSON encoded with the Foxglove schemas so that the
+//! recording can be replayed in Foxglove Studio. CARLA uses a
+//! left-handed frame, which is mirrored along the y axis to the
+//! right-handed frame of Foxglove.
+
+use crate::{actuator::Command, ego::EgoState};
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use carla::{
+    client::{Sensor, Vehicle, World},
+    prelude::*,
+    sensor::data::{Image, LidarMeasurement},
+};
+use mcap::{records::MessageHeader, WriteOptions, Writer};
+use nalgebra::{Isometry3, Point3, Translation3, UnitQuaternion};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// The frame of the world fixed poses.
+const WORLD_FRAME: &str = "world";
+
+/// The frame of the front camera.
+const CAMERA_FRAME: &str = "camera";
+
+/// The type of `float32` fields in `foxglove.PointCloud`.
+const FLOAT32: u8 = 7;
+
+/// Records a front camera, a roof lidar, the ego pose and the control
+/// commands.
+pub struct McapRecorder {
+    recording: Arc<Mutex<Recording>>,
+    pose_channel: u16,
+    control_channel: u16,
+    camera: Sensor,
+    lidar: Sensor,
+}
+
+/// The file shared by the control loop and the sensor threads.
+struct Recording {
+    writer: Writer<BufWriter<File>>,
+    sequence: u32,
+}
+
+impl Recording {
+    fn write(&mut self, channel_id: u16, time: f64, message: &Value) -> Result<()> {
+        let time = (time * 1e9) as u64;
+        let header = MessageHeader {
+            channel_id,
+            sequence: self.sequence,
+            log_time: time,
+            publish_time: time,
+        };
+        self.sequence += 1;
+        let data = serde_json::to_vec(message)?;
+        self.writer.write_to_known_channel(&header, &data)?;
+        Ok(())
+    }
+
+    /// Add a JSON channel with the given Foxglove schema.
+    fn add_channel(&mut self, topic: &str, schema_name: &str, schema: &Value) -> Result<u16> {
+        let schema_id =
+            self.writer
+                .add_schema(schema_name, "jsonschema", &serde_json::to_vec(schema)?)?;
+        let channel_id = self
+            .writer
+            .add_channel(schema_id, topic, "json", &BTreeMap::new())?;
+        Ok(channel_id)
+    }
+}
+
+impl McapRecorder {
+    /// Create the file and attach the sensors to the vehicle.
+    pub fn new(
+        path: impl AsRef<Path>,
+        world: &mut World,
+        vehicle: &Vehicle,
+        delta_seconds: f64,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("unable to create {}", path.display()))?;
+        let writer = WriteOptions::new()
+            .create(BufWriter::new(file))
+            .with_context(|| format!("unable to write {}", path.display()))?;
+        let mut recording = Recording {
+            writer,
+            sequence: 0,
+        };
+
+        let camera_channel =
+            recording.add_channel("/camera", "foxglove.RawImage", &raw_image_schema())?;
+        let lidar_channel =
+            recording.add_channel("/lidar", "foxglove.PointCloud", &point_cloud_schema())?;
+        let pose_channel =
+            recording.add_channel("/ego/pose", "foxglove.PoseInFrame", &pose_in_frame_schema())?;
+        let control_channel = recording.add_channel("/control", "Control", &control_schema())?;
+        let recording = Arc::new(Mutex::new(recording));
+
+        let camera_pose =
+            Isometry3::from_parts(Translation3::new(1.5, 0.0, 2.4), UnitQuaternion::identity());
+        let camera: Sensor = world
+            .actor_builder("sensor.camera.rgb")?
+            .set_attribute("image_size_x", "640")?
+            .set_attribute("image_size_y", "360")?
+            .spawn_sensor_opt(&camera_pose, Some(vehicle), None)?;
+
+        // Complete a full revolution in every tick.
+        let lidar_pose =
+            Isometry3::from_parts(Translation3::new(0.0, 0.0, 2.4), UnitQuaternion::identity());
+        let lidar: Sensor = world
+            .actor_builder("sensor.lidar.ray_cast")?
+            .set_attribute("channels", "32")?
+            .set_attribute("range", "50")?
+            .set_attribute("points_per_second", "100000")?
+            .set_attribute("rotation_frequency", &(1.0 / delta_seconds).to_string())?
+            .spawn_sensor_opt(&lidar_pose, Some(vehicle), None)?;
+
+        {
+            let recording = recording.clone();
+            camera.listen(move |data| {
+                let time = data.timestamp();
+                let image: Image = data.try_into().unwrap();
+                let pixels: Vec<u8> = image
+                    .as_slice()
+                    .iter()
+                    .flat_map(|color| [color.r, color.g, color.b])
+                    .collect();
+                let message = json!({
+                    "timestamp": to_time(time),
+                    "frame_id": CAMERA_FRAME,
+                    "width": image.width(),
+                    "height": image.height(),
+                    "encoding": "rgb8",
+                    "step": image.width() * 3,
+                    "data": BASE64.encode(pixels),
+                });
+                if let Err(err) = recording
+                    .lock()
+                    .unwrap()
+                    .write(camera_channel, time, &message)
+                {
+                    eprintln!("Unable to record the camera image: {err}");
+                }
+            });
+        }
+        {
+            let recording = recording.clone();
+            lidar.listen(move |data| {
+                let time = data.timestamp();
+                let transform = data.sensor_transform();
+                let measure: LidarMeasurement = data.try_into().unwrap();
+                let points: Vec<u8> = measure
+                    .as_slice()
+                    .iter()
+                    .flat_map(|detection| {
+                        let point = &detection.point;
+                        to_foxglove(&(transform * Point3::new(point.x, point.y, point.z)))
+                    })
+                    .flat_map(f32::to_le_bytes)
+                    .collect();
+                let message = json!({
+                    "timestamp": to_time(time),
+                    "frame_id": WORLD_FRAME,
+                    "pose": to_pose(&Isometry3::identity()),
+                    "point_stride": 12,
+                    "fields": [
+                        { "name": "x", "offset": 0, "type": FLOAT32 },
+                        { "name": "y", "offset": 4, "type": FLOAT32 },
+                        { "name": "z", "offset": 8, "type": FLOAT32 },
+                    ],
+                    "data": BASE64.encode(points),
+                });
+                if let Err(err) = recording
+                    .lock()
+                    .unwrap()
+                    .write(lidar_channel, time, &message)
+                {
+                    eprintln!("Unable to record the lidar points: {err}");
+                }
+            });
+        }
+
+        Ok(Self {
+            recording,
+            pose_channel,
+            control_channel,
+            camera,
+            lidar,
+        })
+    }
+
+    /// Record the state of the vehicle and the controller in a tick.
+    pub fn record(&self, ego: &EgoState, command: &Command, target_speed: f32) -> Result<()> {
+        let time = command.timestamp;
+        let mut recording = self.recording.lock().unwrap();
+        recording.write(
+            self.pose_channel,
+            time,
+            &json!({
+                "timestamp": to_time(time),
+                "frame_id": WORLD_FRAME,
+                "pose": to_pose(&ego.transform),
+            }),
+        )?;
+        recording.write(
+            self.control_channel,
+            time,
+            &json!({
+                "steer": command.steer,
+                "acceleration": command.acceleration,
+                "speed": ego.speed,
+                "setpoint_speed": command.speed,
+                "target_speed": target_speed,
+                "reverse": command.reverse,
+                "hand_brake": command.hand_brake,
+            }),
+        )?;
+        Ok(())
+    }
+
+    /// Stop the sensors and write the summary of the file.
+    pub fn finish(&mut self) -> Result<()> {
+        self.camera.stop();
+        self.lidar.stop();
+        self.recording
+            .lock()
+            .unwrap()
+            .writer
+            .finish()
+            .with_context(|| "unable to finish the MCAP file")?;
+        Ok(())
+    }
+}
+
+impl Drop for McapRecorder {
+    fn drop(&mut self) {
+        self.camera.stop();
+        self.lidar.stop();
+    }
+}
+
+/// Mirror a point in the CARLA frame to the Foxglove frame.
+fn to_foxglove(point: &Point3<f32>) -> [f32; 3] {
+    [point.x, -point.y, point.z]
+}
+
+fn to_pose(transform: &Isometry3<f32>) -> Value {
+    let [x, y, z] = to_foxglove(&Point3::from(transform.translation.vector));
+    let rotation = transform.rotation;
+    json!({
+        "position": { "x": x, "y": y, "z": z },
+        "orientation": {
+            "x": -rotation.i,
+            "y": rotation.j,
+            "z": -rotation.k,
+            "w": rotation.w,
+        },
+    })
+}
+
+fn to_time(seconds: f64) -> Value {
+    let sec = seconds.floor();
+    json!({
+        "sec": sec as u32,
+        "nsec": ((seconds - sec) * 1e9) as u32,
+    })
+}
+
+fn object_schema(properties: Value) -> Value {
+    json!({ "type": "object", "properties": properties })
+}
+
+fn time_schema() -> Value {
+    object_schema(json!({
+        "sec": { "type": "integer" },
+        "nsec": { "type": "integer" },
+    }))
+}
+
+fn vector3_schema() -> Value {
+    object_schema(json!({
+        "x": { "type": "number" },
+        "y": { "type": "number" },
+        "z": { "type": "number" },
+    }))
+}
+
+fn pose_schema() -> Value {
+    object_schema(json!({
+        "position": vector3_schema(),
+        "orientation": object_schema(json!({
+            "x": { "type": "number" },
+            "y": { "type": "number" },
+            "z": { "type": "number" },
+            "w": { "type": "number" },
+        })),
+    }))
+}
+
+fn raw_image_schema() -> Value {
+    object_schema(json!({
+        "timestamp": time_schema(),
+        "frame_id": { "type": "string" },
+        "width": { "type": "integer" },
+        "height": { "type": "integer" },
+        "encoding": { "type": "string" },
+        "step": { "type": "integer" },
+        "data": { "type": "string", "contentEncoding": "base64" },
+    }))
+}
+
+fn point_cloud_schema() -> Value {
+    object_schema(json!({
+        "timestamp": time_schema(),
+        "frame_id": { "type": "string" },
+        "pose": pose_schema(),
+        "point_stride": { "type": "integer" },
+        "fields": {
+            "type": "array",
+            "items": object_schema(json!({
+                "name": { "type": "string" },
+                "offset": { "type": "integer" },
+                "type": { "type": "integer" },
+            })),
+        },
+        "data": { "type": "string", "contentEncoding": "base64" },
+    }))
+}
+
+fn pose_in_frame_schema() -> Value {
+    object_schema(json!({
+        "timestamp": time_schema(),
+        "frame_id": { "type": "string" },
+        "pose": pose_schema(),
+    }))
+}
+
+fn control_schema() -> Value {
+    object_schema(json!({
+        "steer": { "type": "number" },
+        "acceleration": { "type": "number" },
+        "speed": { "type": "number" },
+        "setpoint_speed": { "type": "number" },
+        "target_speed": { "type": "number" },
+        "reverse": { "type": "boolean" },
+        "hand_brake": { "type": "boolean" },
+    }))
+}
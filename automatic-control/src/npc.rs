@@ -0,0 +1,134 @@
+//! Background traffic driven by the Traffic Manager.
+
+use crate::{leftover, traffic::TrafficConfig};
+use anyhow::{ensure, Result};
+use carla::{
+    client::{ActorBase, Vehicle, World},
+    traffic_manager::TrafficManager,
+};
+use nalgebra::Isometry3;
+use rand::{seq::SliceRandom, Rng};
+
+/// The two-wheeled vehicle blueprints. The blueprint attributes are
+/// not readable from the client, so `number_of_wheels` cannot be
+/// checked.
+const TWO_WHEELERS: &[&str] = &[
+    "vehicle.bh.crossbike",
+    "vehicle.diamondback.century",
+    "vehicle.gazelle.omafiets",
+    "vehicle.harley-davidson.low_rider",
+    "vehicle.kawasaki.ninja",
+    "vehicle.vespa.zx125",
+    "vehicle.yamaha.yzf",
+];
+
+/// The distance in meters from the ego start point within which no
+/// vehicle is spawned.
+const EGO_CLEARANCE: f32 = 10.0;
+
+/// The fleet settings given on the command line.
+#[derive(Debug, Clone)]
+pub struct FleetConfig {
+    pub count: usize,
+    /// The blueprint filter of the vehicles, e.g. `vehicle.*`.
+    pub filter: String,
+    /// Skip the bicycles and the motorcycles.
+    pub no_bikes: bool,
+}
+
+/// The vehicles spawned at random spawn points and driven by the
+/// autopilot.
+pub struct NpcFleet {
+    tm_port: u16,
+    vehicles: Vec<Vehicle>,
+}
+
+impl NpcFleet {
+    /// Spawn up to `config.count` vehicles of the blueprints matching
+    /// `config.filter`. The spawn points that are occupied or near the ego
+    /// start point are skipped, so fewer vehicles may be spawned on
+    /// small maps.
+    pub fn spawn(
+        traffic_manager: &mut TrafficManager,
+        traffic: &TrafficConfig,
+        world: &mut World,
+        config: &FleetConfig,
+        ego_start: &Isometry3<f32>,
+        rng: &mut impl Rng,
+    ) -> Result<Self> {
+        let FleetConfig {
+            count,
+            ref filter,
+            no_bikes,
+        } = *config;
+        let library = world.blueprint_library().filter(filter);
+        let blueprints: Vec<_> = library
+            .iter()
+            .filter(|blueprint| {
+                let id = blueprint.id();
+                id.starts_with("vehicle.") && !(no_bikes && TWO_WHEELERS.contains(&id.as_str()))
+            })
+            .collect();
+        ensure!(
+            !blueprints.is_empty(),
+            "no vehicle blueprint matches '{filter}'"
+        );
+
+        let mut spawn_points: Vec<_> = world
+            .map()
+            .recommended_spawn_points()
+            .iter()
+            .filter(|point| {
+                (point.translation.vector - ego_start.translation.vector).norm() > EGO_CLEARANCE
+            })
+            .collect();
+        spawn_points.shuffle(rng);
+
+        let mut vehicles = vec![];
+        for point in spawn_points {
+            if vehicles.len() == count {
+                break;
+            }
+            let blueprint = blueprints.choose(rng).unwrap();
+            // The spawn fails if the point is occupied.
+            let Ok(actor) = world.spawn_actor(blueprint, &point) else {
+                continue;
+            };
+            let Ok(vehicle) = Vehicle::try_from(actor) else {
+                continue;
+            };
+            traffic.drive(traffic_manager, &vehicle);
+            vehicles.push(vehicle);
+        }
+        if vehicles.len() < count {
+            eprintln!(
+                "Spawned {} of {count} NPC vehicles for lack of free spawn points",
+                vehicles.len()
+            );
+        } else {
+            eprintln!("Spawned {count} NPC vehicles");
+        }
+
+        Ok(Self {
+            tm_port: traffic.port,
+            vehicles,
+        })
+    }
+
+    pub fn vehicles(&self) -> &[Vehicle] {
+        &self.vehicles
+    }
+}
+
+impl Drop for NpcFleet {
+    /// Release the vehicles from the Traffic Manager and stop them.
+    fn drop(&mut self) {
+        for vehicle in &self.vehicles {
+            if vehicle.is_alive() {
+                vehicle.set_autopilot_opt(false, self.tm_port);
+                vehicle.set_target_velocity(&Default::default());
+            }
+        }
+        leftover::report(self.vehicles.len(), "stopped NPC vehicles");
+    }
+}
@@ -0,0 +1,135 @@
+//! Slowing down behind obstacles using the obstacle detection sensor.
+
+use crate::counter::{FrameCount, FrameCounter};
+use anyhow::Result;
+use carla::{
+    client::{ActorBase, Sensor, Vehicle, World},
+    prelude::*,
+    rpc::ActorId,
+    sensor::data::ObstacleDetectionEvent,
+};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use std::sync::{Arc, Mutex};
+
+/// The number of frames without detections after which the obstacle
+/// is considered gone.
+const CLEAR_FRAMES: usize = 10;
+
+/// The obstacle reported by the latest detection event.
+#[derive(Debug, Clone)]
+struct Obstacle {
+    frame: usize,
+    id: ActorId,
+    type_id: String,
+    distance: f32,
+    /// The lateral offset in meters of the obstacle from the sensor
+    /// axis.
+    lateral: f32,
+}
+
+/// Lowers the target speed to stop behind the obstacles in the lane.
+pub struct ObstacleBehavior {
+    /// The distance in meters kept behind the obstacle.
+    pub margin: f32,
+    /// The deceleration in m/s² used to plan the stop.
+    pub deceleration: f32,
+    sensor: Sensor,
+    frames: FrameCounter,
+    obstacle: Arc<Mutex<Option<Obstacle>>>,
+    following: Option<ActorId>,
+}
+
+impl ObstacleBehavior {
+    /// Attach an obstacle detector that sweeps a sphere of
+    /// `hit_radius` meters up to `distance` meters ahead.
+    pub fn new(
+        world: &mut World,
+        vehicle: &Vehicle,
+        distance: f32,
+        hit_radius: f32,
+        margin: f32,
+        deceleration: f32,
+    ) -> Result<Self> {
+        let pose =
+            Isometry3::from_parts(Translation3::new(2.5, 0.0, 0.7), UnitQuaternion::identity());
+        let sensor: Sensor = world
+            .actor_builder("sensor.other.obstacle")?
+            .set_attribute("distance", &distance.to_string())?
+            .set_attribute("hit_radius", &hit_radius.to_string())?
+            .set_attribute("only_dynamics", "false")?
+            .spawn_sensor_opt(&pose, Some(vehicle), None)?;
+
+        let frames = FrameCounter::default();
+        let obstacle = Arc::new(Mutex::new(None));
+        {
+            let frames = frames.clone();
+            let obstacle = obstacle.clone();
+            sensor.listen(move |data| {
+                let frame = data.frame();
+                frames.record(frame);
+                let sensor_pose = data.sensor_transform();
+                let event: ObstacleDetectionEvent = data.try_into().unwrap();
+                let other = event.other_actor();
+                let local = sensor_pose.inverse() * other.transform().translation.vector;
+
+                *obstacle.lock().unwrap() = Some(Obstacle {
+                    frame,
+                    id: other.id(),
+                    type_id: other.type_id(),
+                    distance: event.distance(),
+                    lateral: local.y,
+                });
+            });
+        }
+
+        Ok(Self {
+            margin,
+            deceleration,
+            sensor,
+            frames,
+            obstacle,
+            following: None,
+        })
+    }
+
+    /// The measurements received by the sensor.
+    pub fn frames(&self) -> FrameCount {
+        self.frames.get()
+    }
+
+    /// Compute the maximum speed in m/s allowed to stop behind the
+    /// obstacle ahead. It returns `None` if the lane is clear.
+    pub fn max_speed(&mut self, lane_width: f32, frame: usize) -> Option<f32> {
+        let obstacle = self.obstacle.lock().unwrap().clone();
+        let obstacle = obstacle.filter(|obstacle| {
+            // Ignore the objects by the road in turns.
+            frame <= obstacle.frame + CLEAR_FRAMES && obstacle.lateral.abs() <= lane_width / 2.0
+        });
+
+        match obstacle {
+            Some(obstacle) => {
+                if self.following != Some(obstacle.id) {
+                    eprintln!(
+                        "Slow down behind {} ({}) at {:.1} m",
+                        obstacle.type_id, obstacle.id, obstacle.distance
+                    );
+                    self.following = Some(obstacle.id);
+                }
+                let distance = (obstacle.distance - self.margin).max(0.0);
+                Some((2.0 * self.deceleration * distance).sqrt())
+            }
+            None => {
+                if let Some(id) = self.following.take() {
+                    eprintln!("The obstacle {id} is gone");
+                }
+                None
+            }
+        }
+    }
+}
+
+impl Drop for ObstacleBehavior {
+    fn drop(&mut self) {
+        self.sensor.stop();
+    }
+}
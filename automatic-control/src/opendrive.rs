@@ -0,0 +1,51 @@
+//! Worlds generated from OpenDRIVE files.
+
+use anyhow::{Context, Result};
+use autocxx::WithinUniquePtr;
+use carla::{
+    client::{Client, World},
+    rpc::OpendriveGenerationParameters,
+};
+use std::{fs, path::Path};
+
+/// The mesh generation parameters of the OpenDRIVE world.
+#[derive(Debug, Clone)]
+pub struct OpenDriveConfig {
+    /// The distance in meters between the vertices of the road mesh.
+    pub vertex_distance: f64,
+    /// The maximum length in meters of a road mesh piece.
+    pub max_road_length: f64,
+    /// The height in meters of the walls at the road borders.
+    pub wall_height: f64,
+    /// The width in meters added to the junction lanes.
+    pub additional_width: f64,
+    pub smooth_junctions: bool,
+    pub mesh_visibility: bool,
+    /// Generate the navigation mesh for the pedestrians.
+    pub pedestrian_navigation: bool,
+}
+
+impl OpenDriveConfig {
+    /// Replace the world by the roads of the OpenDRIVE file.
+    pub fn load(&self, client: &Client, path: &Path) -> Result<World> {
+        let opendrive = fs::read_to_string(path)
+            .with_context(|| format!("unable to read the OpenDRIVE file {}", path.display()))?;
+        eprintln!("Generate the world from {}", path.display());
+        Ok(self.generate(client, &opendrive))
+    }
+
+    /// Replace the world by the roads of the OpenDRIVE document.
+    pub fn generate(&self, client: &Client, opendrive: &str) -> World {
+        let params = OpendriveGenerationParameters::new1(
+            self.vertex_distance,
+            self.max_road_length,
+            self.wall_height,
+            self.additional_width,
+            self.smooth_junctions,
+            self.mesh_visibility,
+            self.pedestrian_navigation,
+        )
+        .within_unique_ptr();
+        client.generate_open_drive_world(opendrive, &params, true)
+    }
+}
@@ -0,0 +1,79 @@
+//! Conversion of OpenStreetMap files to OpenDRIVE.
+//!
+//! The converter of CARLA is only bound in its Python package, so the
+//! conversion runs in a Python interpreter that imports it.
+
+use anyhow::{ensure, Context, Result};
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// The script converting the OpenStreetMap file of the first argument
+/// with the settings of the following arguments, and writing the
+/// OpenDRIVE document to the standard output.
+const CONVERT_SCRIPT: &str = r#"
+import sys
+import carla
+
+path, lane_width, traffic_lights, all_junction_lights, center_map, *proj = sys.argv[1:]
+settings = carla.Osm2OdrSettings()
+settings.default_lane_width = float(lane_width)
+settings.generate_traffic_lights = traffic_lights == "1"
+settings.all_junctions_with_traffic_lights = all_junction_lights == "1"
+settings.center_map = center_map == "1"
+if proj:
+    settings.proj_string = proj[0]
+with open(path, encoding="utf-8") as file:
+    osm = file.read()
+sys.stdout.write(carla.Osm2Odr.convert(osm, settings))
+"#;
+
+/// The settings of the OpenStreetMap conversion.
+#[derive(Debug, Clone)]
+pub struct OsmConfig {
+    /// The Python interpreter with the CARLA package.
+    pub python: String,
+    /// The width in meters of the lanes without a given width.
+    pub lane_width: f64,
+    /// Generate traffic lights at the junctions tagged with them.
+    pub traffic_lights: bool,
+    /// Generate traffic lights at all junctions.
+    pub all_junction_lights: bool,
+    /// Move the center of the map to the origin.
+    pub center_map: bool,
+    /// The PROJ string of the projection, or the default of the
+    /// converter if it is `None`.
+    pub proj: Option<String>,
+}
+
+impl OsmConfig {
+    /// Convert the OpenStreetMap file to an OpenDRIVE document.
+    pub fn convert(&self, path: &Path) -> Result<String> {
+        let flag = |value: bool| if value { "1" } else { "0" };
+        let mut command = Command::new(&self.python);
+        command
+            .arg("-c")
+            .arg(CONVERT_SCRIPT)
+            .arg(path)
+            .arg(self.lane_width.to_string())
+            .arg(flag(self.traffic_lights))
+            .arg(flag(self.all_junction_lights))
+            .arg(flag(self.center_map))
+            .args(&self.proj)
+            .stderr(Stdio::inherit());
+
+        eprintln!("Convert {} to OpenDRIVE", path.display());
+        let output = command
+            .output()
+            .with_context(|| format!("unable to run {}", self.python))?;
+        ensure!(
+            output.status.success(),
+            "the conversion of {} failed with {}. Is the CARLA Python package installed for {}?",
+            path.display(),
+            output.status,
+            self.python
+        );
+        String::from_utf8(output.stdout).context("the converted OpenDRIVE is not UTF-8")
+    }
+}
@@ -0,0 +1,263 @@
+//! Log the controller state of every tick to a Parquet file.
+//!
+//! The columns are the same as the CSV log but typed, and the rows
+//! are compressed in row groups, which suits runs of several hours.
+//! The run settings and the measurement counts of the sensors are
+//! stored in the key-value metadata of the file.
+
+use crate::{actuator::Command, counter::FrameCount, ego::EgoState, lateral::Reference};
+use anyhow::{Context, Result};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float32Array, Float64Array, Int32Array, RecordBatch, UInt32Array,
+    UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use carla::client::{ActorBase, Vehicle, World};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    file::{metadata::KeyValue, properties::WriterProperties},
+};
+use std::{collections::HashMap, fs::File, path::Path, sync::Arc};
+
+/// The number of rows buffered before they are handed to the writer.
+const BATCH_ROWS: usize = 1024;
+
+/// The columns with their types and units.
+const COLUMNS: &[(&str, DataType, &str)] = &[
+    ("frame", DataType::UInt64, ""),
+    ("timestamp", DataType::Float64, "s"),
+    ("x", DataType::Float32, "m"),
+    ("y", DataType::Float32, "m"),
+    ("z", DataType::Float32, "m"),
+    ("yaw", DataType::Float32, "deg"),
+    ("pitch", DataType::Float32, "deg"),
+    ("speed", DataType::Float32, "m/s"),
+    ("vx", DataType::Float32, "m/s"),
+    ("vy", DataType::Float32, "m/s"),
+    ("vz", DataType::Float32, "m/s"),
+    ("target_speed", DataType::Float32, "m/s"),
+    ("command_speed", DataType::Float32, "m/s"),
+    ("command_acceleration", DataType::Float32, "m/s^2"),
+    ("command_steer", DataType::Float32, "deg"),
+    ("hand_brake", DataType::Boolean, ""),
+    ("reverse", DataType::Boolean, ""),
+    ("lateral_error", DataType::Float32, "m"),
+    ("heading_error", DataType::Float32, "deg"),
+    ("road_id", DataType::UInt32, ""),
+    ("section_id", DataType::UInt32, ""),
+    ("lane_id", DataType::Int32, ""),
+    ("waypoint_s", DataType::Float64, "m"),
+    ("waypoint_x", DataType::Float32, "m"),
+    ("waypoint_y", DataType::Float32, "m"),
+    ("is_junction", DataType::Boolean, ""),
+    ("lane_offset", DataType::Float32, "m"),
+];
+
+/// The values of a tick in the order of [`COLUMNS`].
+struct Row {
+    frame: u64,
+    timestamp: f64,
+    position: [f32; 3],
+    yaw: f32,
+    pitch: f32,
+    speed: f32,
+    velocity: [f32; 3],
+    target_speed: f32,
+    command_speed: f32,
+    command_acceleration: f32,
+    command_steer: f32,
+    hand_brake: bool,
+    reverse: bool,
+    lateral_error: f32,
+    heading_error: f32,
+    road_id: u32,
+    section_id: u32,
+    lane_id: i32,
+    waypoint_s: f64,
+    waypoint_x: f32,
+    waypoint_y: f32,
+    is_junction: bool,
+    lane_offset: f32,
+}
+
+/// Writes a row per tick. The angles are in degrees and the other
+/// values in SI units, also noted in the `unit` metadata of the
+/// fields.
+pub struct ParquetLogger {
+    writer: Option<ArrowWriter<File>>,
+    schema: SchemaRef,
+    rows: Vec<Row>,
+    total_rows: usize,
+}
+
+impl ParquetLogger {
+    /// Create the file. `metadata` is stored in the file along with
+    /// the sensor counts given to [`finish`](Self::finish).
+    pub fn new(path: impl AsRef<Path>, metadata: &[(&str, String)]) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("unable to create {}", path.display()))?;
+
+        let fields: Vec<Field> = COLUMNS
+            .iter()
+            .map(|(name, data_type, unit)| {
+                let field = Field::new(*name, data_type.clone(), false);
+                if unit.is_empty() {
+                    field
+                } else {
+                    field.with_metadata(HashMap::from([("unit".to_string(), unit.to_string())]))
+                }
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .set_key_value_metadata(Some(
+                metadata
+                    .iter()
+                    .map(|(key, value)| KeyValue::new(key.to_string(), value.clone()))
+                    .collect(),
+            ))
+            .build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(properties))?;
+        eprintln!("Log the ticks to {}", path.display());
+
+        Ok(Self {
+            writer: Some(writer),
+            schema,
+            rows: Vec::with_capacity(BATCH_ROWS),
+            total_rows: 0,
+        })
+    }
+
+    pub fn log(
+        &mut self,
+        world: &World,
+        vehicle: &Vehicle,
+        ego: &EgoState,
+        reference: &Reference,
+        command: &Command,
+        target_speed: f32,
+    ) -> Result<()> {
+        let snapshot = world.snapshot();
+        let location = ego.transform.translation;
+        let velocity = vehicle.velocity();
+        let error = reference.error(&ego.position(), ego.yaw);
+        let waypoint = &reference.nearest;
+        let waypoint_location = waypoint.transform().translation;
+
+        self.rows.push(Row {
+            frame: snapshot.frame() as u64,
+            timestamp: snapshot.timestamp().elapsed_seconds,
+            position: [location.x, location.y, location.z],
+            yaw: ego.yaw.to_degrees(),
+            pitch: ego.pitch.to_degrees(),
+            speed: ego.speed,
+            velocity: [velocity.x, velocity.y, velocity.z],
+            target_speed,
+            command_speed: command.speed,
+            command_acceleration: command.acceleration,
+            command_steer: command.steer.to_degrees(),
+            hand_brake: command.hand_brake,
+            reverse: command.reverse,
+            lateral_error: error.lateral,
+            heading_error: error.heading.to_degrees(),
+            road_id: waypoint.road_id(),
+            section_id: waypoint.section_id(),
+            lane_id: waypoint.lane_id(),
+            waypoint_s: waypoint.distance(),
+            waypoint_x: waypoint_location.x,
+            waypoint_y: waypoint_location.y,
+            is_junction: waypoint.is_junction(),
+            lane_offset: reference.offset,
+        });
+        if self.rows.len() >= BATCH_ROWS {
+            self.write_rows()?;
+        }
+        Ok(())
+    }
+
+    /// Write the remaining rows and the sensor counts, and close the
+    /// file.
+    pub fn finish(&mut self, sensors: &[(&str, FrameCount)]) -> Result<()> {
+        self.write_rows()?;
+        let Some(mut writer) = self.writer.take() else {
+            return Ok(());
+        };
+        for (name, frames) in sensors {
+            let key = format!("sensor.{}.measurements", name.replace(' ', "_"));
+            writer.append_key_value_metadata(KeyValue::new(key, frames.count.to_string()));
+        }
+        writer.close()?;
+        eprintln!("Logged {} ticks", self.total_rows);
+        Ok(())
+    }
+
+    fn write_rows(&mut self) -> Result<()> {
+        let Some(writer) = &mut self.writer else {
+            return Ok(());
+        };
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+
+        let rows = &self.rows;
+        let f32s = |get: fn(&Row) -> f32| -> ArrayRef {
+            Arc::new(Float32Array::from_iter_values(rows.iter().map(get)))
+        };
+        let bools = |get: fn(&Row) -> bool| -> ArrayRef {
+            Arc::new(BooleanArray::from_iter(
+                rows.iter().map(|row| Some(get(row))),
+            ))
+        };
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(
+                rows.iter().map(|row| row.frame),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                rows.iter().map(|row| row.timestamp),
+            )),
+            f32s(|row| row.position[0]),
+            f32s(|row| row.position[1]),
+            f32s(|row| row.position[2]),
+            f32s(|row| row.yaw),
+            f32s(|row| row.pitch),
+            f32s(|row| row.speed),
+            f32s(|row| row.velocity[0]),
+            f32s(|row| row.velocity[1]),
+            f32s(|row| row.velocity[2]),
+            f32s(|row| row.target_speed),
+            f32s(|row| row.command_speed),
+            f32s(|row| row.command_acceleration),
+            f32s(|row| row.command_steer),
+            bools(|row| row.hand_brake),
+            bools(|row| row.reverse),
+            f32s(|row| row.lateral_error),
+            f32s(|row| row.heading_error),
+            Arc::new(UInt32Array::from_iter_values(
+                rows.iter().map(|row| row.road_id),
+            )),
+            Arc::new(UInt32Array::from_iter_values(
+                rows.iter().map(|row| row.section_id),
+            )),
+            Arc::new(Int32Array::from_iter_values(
+                rows.iter().map(|row| row.lane_id),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                rows.iter().map(|row| row.waypoint_s),
+            )),
+            f32s(|row| row.waypoint_x),
+            f32s(|row| row.waypoint_y),
+            bools(|row| row.is_junction),
+            f32s(|row| row.lane_offset),
+        ];
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+        writer.write(&batch)?;
+
+        self.total_rows += self.rows.len();
+        self.rows.clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+/// A discrete PID controller.
+#[derive(Debug, Clone)]
+pub struct Pid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    /// The lower and upper bounds of the output.
+    pub limits: Option<(f32, f32)>,
+    integral: f32,
+    prev_error: Option<f32>,
+}
+
+impl Pid {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            limits: None,
+            integral: 0.0,
+            prev_error: None,
+        }
+    }
+
+    /// Bound the output within `[min, max]`. The integral stops
+    /// accumulating while the output saturates to prevent windup.
+    pub fn with_limits(mut self, min: f32, max: f32) -> Self {
+        self.limits = Some((min, max));
+        self
+    }
+
+    /// Feed the error of the current step and compute the control
+    /// output. `dt` is the elapsed time in seconds since the last
+    /// step.
+    pub fn step(&mut self, error: f32, dt: f32) -> f32 {
+        let integral = self.integral + error * dt;
+
+        // Skip the derivative term on the first step to avoid a kick.
+        let derivative = match self.prev_error {
+            Some(prev_error) if dt > 0.0 => (error - prev_error) / dt,
+            _ => 0.0,
+        };
+        self.prev_error = Some(error);
+
+        let output = self.kp * error + self.ki * integral + self.kd * derivative;
+
+        let Some((min, max)) = self.limits else {
+            self.integral = integral;
+            return output;
+        };
+
+        // Integrate only if it does not drive the output further into
+        // saturation.
+        let saturated = (output > max && error > 0.0) || (output < min && error < 0.0);
+        if !saturated {
+            self.integral = integral;
+        }
+
+        output.clamp(min, max)
+    }
+
+    /// Clear the accumulated integral and derivative states.
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.prev_error = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_the_derivative_on_the_first_step() {
+        let mut pid = Pid::new(2.0, 0.0, 1.0);
+        assert_eq!(pid.step(1.0, 0.1), 2.0);
+        // The error rises by 1 in 0.1 s.
+        assert!((pid.step(2.0, 0.1) - (4.0 + 10.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn accumulates_the_integral() {
+        let mut pid = Pid::new(0.0, 1.0, 0.0);
+        pid.step(1.0, 0.5);
+        assert!((pid.step(1.0, 0.5) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn clamps_the_output() {
+        let mut pid = Pid::new(10.0, 0.0, 0.0).with_limits(-1.0, 2.0);
+        assert_eq!(pid.step(1.0, 0.1), 2.0);
+        assert_eq!(pid.step(-1.0, 0.1), -1.0);
+    }
+
+    #[test]
+    fn stops_integrating_in_saturation() {
+        let mut pid = Pid::new(1.0, 1.0, 0.0).with_limits(-1.0, 1.0);
+        for _ in 0..100 {
+            assert_eq!(pid.step(10.0, 0.1), 1.0);
+        }
+        // A wound-up integral would hold the output at the upper limit.
+        let output = pid.step(-0.5, 0.1);
+        assert!((output + 0.55).abs() < 1e-5);
+    }
+
+    #[test]
+    fn reset_clears_the_state() {
+        let mut pid = Pid::new(1.0, 1.0, 1.0);
+        pid.step(1.0, 0.1);
+        pid.step(3.0, 0.1);
+        pid.reset();
+        assert!((pid.step(1.0, 0.1) - 1.1).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,103 @@
+//! A window showing a chase camera behind the vehicle.
+
+use anyhow::{Context, Result};
+use carla::{
+    client::{Sensor, Vehicle, World},
+    sensor::data::Image,
+};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use std::sync::{Arc, Mutex};
+
+/// Streams the camera images to a window. The window is refreshed
+/// from the control loop since it must be driven by the main thread.
+pub struct Preview {
+    sensor: Sensor,
+    window: Window,
+    width: usize,
+    height: usize,
+    frame: Arc<Mutex<Option<Vec<u32>>>>,
+}
+
+impl Preview {
+    pub fn new(world: &mut World, vehicle: &Vehicle, width: usize, height: usize) -> Result<Self> {
+        let pose = Isometry3::from_parts(
+            Translation3::new(-6.0, 0.0, 3.0),
+            UnitQuaternion::from_euler_angles(0.0, -15f32.to_radians(), 0.0),
+        );
+        let sensor: Sensor = world
+            .actor_builder("sensor.camera.rgb")?
+            .set_attribute("image_size_x", &width.to_string())?
+            .set_attribute("image_size_y", &height.to_string())?
+            .spawn_sensor_opt(&pose, Some(vehicle), None)?;
+
+        let window = Window::new("preview", width, height, WindowOptions::default())
+            .with_context(|| "unable to open the preview window")?;
+
+        // Convert the image to the 0RGB pixels of the window in the
+        // sensor thread.
+        let frame = Arc::new(Mutex::new(None));
+        {
+            let frame = frame.clone();
+            sensor.listen(move |data| {
+                let image: Image = data.try_into().unwrap();
+                let pixels = image
+                    .as_slice()
+                    .iter()
+                    .map(|color| u32::from_be_bytes([0, color.r, color.g, color.b]))
+                    .collect();
+                *frame.lock().unwrap() = Some(pixels);
+            });
+        }
+
+        Ok(Self {
+            sensor,
+            window,
+            width,
+            height,
+            frame,
+        })
+    }
+
+    /// Draw the latest image, or only process the window events if no
+    /// new image arrived. Nothing is done after the window is closed.
+    pub fn update(&mut self) -> Result<()> {
+        if !self.window.is_open() {
+            return Ok(());
+        }
+        match self.frame.lock().unwrap().take() {
+            Some(pixels) => self
+                .window
+                .update_with_buffer(&pixels, self.width, self.height)
+                .with_context(|| "unable to update the preview window")?,
+            None => self.window.update(),
+        }
+        Ok(())
+    }
+
+    /// The keys pressed in the window since the last update.
+    pub fn keys(&self) -> Vec<char> {
+        self.window
+            .get_keys_pressed(KeyRepeat::No)
+            .into_iter()
+            .filter_map(|key| {
+                let key = match key {
+                    Key::C => 'c',
+                    Key::Key1 => '1',
+                    Key::Key2 => '2',
+                    Key::Key3 => '3',
+                    Key::Key4 => '4',
+                    Key::Key5 => '5',
+                    _ => return None,
+                };
+                Some(key)
+            })
+            .collect()
+    }
+}
+
+impl Drop for Preview {
+    fn drop(&mut self) {
+        self.sensor.stop();
+    }
+}
@@ -0,0 +1,171 @@
+//! Jerk-limited speed profiles.
+
+/// A sample of the speed profile.
+#[derive(Debug, Clone, Copy)]
+pub struct ProfilePoint {
+    /// The reference speed in m/s.
+    pub speed: f32,
+    /// The reference acceleration in m/s².
+    pub acceleration: f32,
+    /// The reference jerk in m/s³.
+    pub jerk: f32,
+}
+
+/// Shapes the speed changes so that both the acceleration and jerk
+/// stay within limits.
+#[derive(Debug, Clone)]
+pub struct SpeedProfile {
+    /// The maximum acceleration in m/s².
+    pub max_acceleration: f32,
+    /// The maximum deceleration in m/s².
+    pub max_deceleration: f32,
+    /// The maximum jerk in m/s³.
+    pub max_jerk: f32,
+    state: Option<(f32, f32)>,
+}
+
+impl SpeedProfile {
+    pub fn new(max_acceleration: f32, max_deceleration: f32, max_jerk: f32) -> Self {
+        Self {
+            max_acceleration,
+            max_deceleration,
+            max_jerk,
+            state: None,
+        }
+    }
+
+    /// Advance the profile by `dt` seconds towards the target speed.
+    /// The profile starts from `current_speed` after a reset.
+    pub fn step(&mut self, current_speed: f32, target_speed: f32, dt: f32) -> ProfilePoint {
+        let (speed, acceleration) = self.state.unwrap_or((current_speed, 0.0));
+        let error = target_speed - speed;
+
+        // The largest acceleration that can still be ramped down to
+        // zero by the jerk limit before reaching the target.
+        let reachable = (2.0 * self.max_jerk * error.abs()).sqrt();
+        let desired = if error >= 0.0 {
+            reachable.min(self.max_acceleration)
+        } else {
+            -reachable.min(self.max_deceleration)
+        };
+
+        let max_change = self.max_jerk * dt;
+        let mut next_acceleration =
+            acceleration + (desired - acceleration).clamp(-max_change, max_change);
+        let mut next_speed = speed + next_acceleration * dt;
+
+        // Settle on the target instead of overshooting it.
+        if (target_speed - next_speed) * error <= 0.0 {
+            next_speed = target_speed;
+            next_acceleration = 0.0;
+        }
+        let next_speed = next_speed.max(0.0);
+
+        let jerk = if dt > 0.0 {
+            (next_acceleration - acceleration) / dt
+        } else {
+            0.0
+        };
+        self.state = Some((next_speed, next_acceleration));
+
+        ProfilePoint {
+            speed: next_speed,
+            acceleration: next_acceleration,
+            jerk,
+        }
+    }
+
+    /// Forget the profile state so that the next step starts from the
+    /// vehicle speed.
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.05;
+
+    fn run(profile: &mut SpeedProfile, start: f32, target: f32, steps: usize) -> Vec<ProfilePoint> {
+        let mut speed = start;
+        (0..steps)
+            .map(|_| {
+                let point = profile.step(speed, target, DT);
+                speed = point.speed;
+                point
+            })
+            .collect()
+    }
+
+    #[test]
+    fn speeds_up_within_the_limits() {
+        let mut profile = SpeedProfile::new(2.0, 4.0, 1.0);
+        let points = run(&mut profile, 0.0, 10.0, 400);
+
+        for point in &points {
+            assert!(point.acceleration <= 2.0 + 1e-5);
+            assert!(point.speed <= 10.0);
+            // The acceleration drops to zero at once on the target.
+            if point.speed < 10.0 {
+                assert!(point.jerk.abs() <= 1.0 + 1e-3);
+            }
+        }
+        let peak = points
+            .iter()
+            .map(|point| point.acceleration)
+            .fold(0.0, f32::max);
+        assert!((peak - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn settles_on_the_target() {
+        let mut profile = SpeedProfile::new(2.0, 4.0, 1.0);
+        let last = *run(&mut profile, 0.0, 10.0, 400).last().unwrap();
+        assert_eq!(last.speed, 10.0);
+        assert_eq!(last.acceleration, 0.0);
+
+        // It holds the target once reached.
+        let point = profile.step(9.0, 10.0, DT);
+        assert_eq!(point.speed, 10.0);
+        assert_eq!(point.acceleration, 0.0);
+    }
+
+    #[test]
+    fn slows_down_within_the_limits() {
+        let mut profile = SpeedProfile::new(2.0, 4.0, 1.0);
+        let points = run(&mut profile, 10.0, 2.0, 400);
+        for point in &points {
+            assert!(point.acceleration >= -4.0 - 1e-5);
+            assert!(point.acceleration <= 0.0);
+            assert!(point.speed >= 2.0);
+        }
+        assert_eq!(points.last().unwrap().speed, 2.0);
+    }
+
+    #[test]
+    fn never_plans_a_negative_speed() {
+        let mut profile = SpeedProfile::new(2.0, 4.0, 100.0);
+        let point = profile.step(0.1, -5.0, 1.0);
+        assert_eq!(point.speed, 0.0);
+    }
+
+    #[test]
+    fn restarts_from_the_vehicle_speed_after_a_reset() {
+        let mut profile = SpeedProfile::new(2.0, 4.0, 1.0);
+        run(&mut profile, 0.0, 10.0, 20);
+        profile.reset();
+        let point = profile.step(5.0, 5.0, DT);
+        assert_eq!(point.speed, 5.0);
+        assert_eq!(point.acceleration, 0.0);
+    }
+
+    #[test]
+    fn zero_time_step_has_no_jerk() {
+        let mut profile = SpeedProfile::new(2.0, 4.0, 1.0);
+        let point = profile.step(0.0, 10.0, 0.0);
+        assert_eq!(point.jerk, 0.0);
+        assert_eq!(point.speed, 0.0);
+    }
+}
@@ -0,0 +1,236 @@
+//! Serve the run metrics to Prometheus.
+//!
+//! The metrics are rendered in the text exposition format after each
+//! tick and served at `/metrics` by a background thread, so that a
+//! scrape never waits for the simulation.
+
+use crate::counter::FrameCount;
+use anyhow::{Context, Result};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// The upper bounds in seconds of the tick duration histogram.
+const TICK_BUCKETS: [f64; 9] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+/// The time to wait for a slow scraper before dropping it.
+const IO_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The state of the vehicle and the simulation in a tick.
+pub struct TickMetrics<'a> {
+    /// The server frame returned by the tick.
+    pub frame: u64,
+    /// The wall time spent in the tick.
+    pub tick_duration: Duration,
+    /// The frames by which the snapshot received by the client trails
+    /// the server.
+    pub frame_lag: u64,
+    /// The forward speed in m/s.
+    pub speed: f32,
+    /// The target speed in m/s after the behaviors.
+    pub target_speed: f32,
+    pub sensors: &'a [(&'static str, FrameCount)],
+    /// The total number of lane marking crossings and the crossings
+    /// over solid markings.
+    pub lane_invasions: Option<(usize, usize)>,
+}
+
+/// Accumulates the metrics and serves them over HTTP.
+pub struct PrometheusServer {
+    page: Arc<Mutex<String>>,
+    tick_buckets: [u64; TICK_BUCKETS.len()],
+    tick_sum: f64,
+    tick_count: u64,
+    infractions: BTreeMap<&'static str, usize>,
+}
+
+impl PrometheusServer {
+    pub fn new(addr: SocketAddr) -> Result<Self> {
+        let listener =
+            TcpListener::bind(addr).with_context(|| format!("unable to listen on {addr}"))?;
+        eprintln!("Serving the Prometheus metrics on http://{addr}/metrics");
+
+        let page = Arc::new(Mutex::new(String::new()));
+        {
+            let page = page.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let result = stream
+                        .map_err(Into::into)
+                        .and_then(|stream| respond(stream, &page.lock().unwrap().clone()));
+                    if let Err(err) = result {
+                        eprintln!("Unable to serve the metrics: {err}");
+                    }
+                }
+            });
+        }
+
+        Ok(Self {
+            page,
+            tick_buckets: [0; TICK_BUCKETS.len()],
+            tick_sum: 0.0,
+            tick_count: 0,
+            infractions: BTreeMap::new(),
+        })
+    }
+
+    /// Count an infraction such as a collision.
+    pub fn add_infraction(&mut self, kind: &'static str) {
+        *self.infractions.entry(kind).or_default() += 1;
+    }
+
+    /// Update the metrics served to the scrapers.
+    pub fn update(&mut self, tick: &TickMetrics) {
+        let seconds = tick.tick_duration.as_secs_f64();
+        for (count, bound) in self.tick_buckets.iter_mut().zip(TICK_BUCKETS) {
+            if seconds <= bound {
+                *count += 1;
+            }
+        }
+        self.tick_sum += seconds;
+        self.tick_count += 1;
+
+        let mut infractions = self.infractions.clone();
+        if let Some((total, solid)) = tick.lane_invasions {
+            infractions.insert("lane_invasion", total);
+            infractions.insert("solid_lane_invasion", solid);
+        }
+
+        let mut page = String::new();
+        let mut buckets: Vec<_> = self
+            .tick_buckets
+            .iter()
+            .zip(TICK_BUCKETS)
+            .map(|(count, bound)| (format!("le=\"{bound}\""), *count as f64))
+            .collect();
+        buckets.push(("le=\"+Inf\"".to_string(), self.tick_count as f64));
+        family(
+            &mut page,
+            "carla_tick_duration_seconds_bucket",
+            "histogram",
+            "The wall time spent in a world tick.",
+            buckets,
+        );
+        writeln!(page, "carla_tick_duration_seconds_sum {}", self.tick_sum).unwrap();
+        writeln!(
+            page,
+            "carla_tick_duration_seconds_count {}",
+            self.tick_count
+        )
+        .unwrap();
+
+        let gauge = |value: f64| vec![(String::new(), value)];
+        family(
+            &mut page,
+            "carla_server_frame",
+            "gauge",
+            "The latest server frame.",
+            gauge(tick.frame as f64),
+        );
+        family(
+            &mut page,
+            "carla_frame_lag",
+            "gauge",
+            "The frames by which the client snapshot trails the server.",
+            gauge(tick.frame_lag as f64),
+        );
+        family(
+            &mut page,
+            "carla_vehicle_speed",
+            "gauge",
+            "The vehicle speed in m/s.",
+            gauge(tick.speed as f64),
+        );
+        family(
+            &mut page,
+            "carla_target_speed",
+            "gauge",
+            "The target speed in m/s after the behaviors.",
+            gauge(tick.target_speed as f64),
+        );
+
+        let sensor = |name: &str| format!("sensor=\"{}\"", name.replace(' ', "_"));
+        family(
+            &mut page,
+            "carla_sensor_measurements_total",
+            "counter",
+            "The measurements received by a sensor.",
+            tick.sensors
+                .iter()
+                .map(|(name, frames)| (sensor(name), frames.count as f64))
+                .collect(),
+        );
+        family(
+            &mut page,
+            "carla_sensor_frame_lag",
+            "gauge",
+            "The frames since the latest measurement of a sensor.",
+            tick.sensors
+                .iter()
+                .filter_map(|(name, frames)| {
+                    let lag = tick.frame.saturating_sub(frames.last_frame? as u64);
+                    Some((sensor(name), lag as f64))
+                })
+                .collect(),
+        );
+        family(
+            &mut page,
+            "carla_infractions_total",
+            "counter",
+            "The collisions, resets and lane invasions.",
+            infractions
+                .iter()
+                .map(|(kind, count)| (format!("kind=\"{kind}\""), *count as f64))
+                .collect(),
+        );
+
+        *self.page.lock().unwrap() = page;
+    }
+}
+
+/// Write a metric family with the samples given by their labels.
+fn family(page: &mut String, name: &str, kind: &str, help: &str, samples: Vec<(String, f64)>) {
+    let family = name.strip_suffix("_bucket").unwrap_or(name);
+    writeln!(page, "# HELP {family} {help}").unwrap();
+    writeln!(page, "# TYPE {family} {kind}").unwrap();
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            writeln!(page, "{name} {value}").unwrap();
+        } else {
+            writeln!(page, "{name}{{{labels}}} {value}").unwrap();
+        }
+    }
+}
+
+/// Answer a request with the metrics page. Any path other than
+/// `/metrics` is not found.
+fn respond(mut stream: TcpStream, page: &str) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    // Only the request line matters.
+    let mut buffer = [0; 1024];
+    let len = stream.read(&mut buffer)?;
+    let request = String::from_utf8_lossy(&buffer[..len]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    let (status, body) = match path {
+        "/metrics" => ("200 OK", page),
+        _ => ("404 Not Found", "not found\n"),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    Ok(())
+}
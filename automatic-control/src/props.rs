@@ -0,0 +1,170 @@
+//! Static props placed on the roads as obstacle courses.
+
+use crate::leftover;
+use anyhow::{bail, Context, Error, Result};
+use carla::client::{Actor, World};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use std::{fs, path::Path, str::FromStr};
+
+/// The short names of the common obstacle blueprints.
+const ALIASES: &[(&str, &str)] = &[
+    ("barrel", "static.prop.barrel"),
+    ("barrier", "static.prop.streetbarrier"),
+    ("box", "static.prop.box01"),
+    ("cone", "static.prop.constructioncone"),
+    ("traffic-cone", "static.prop.trafficcone01"),
+];
+
+/// A prop written as `BLUEPRINT@X,Y[,Z[,YAW]]` with the location in
+/// meters and the yaw in degrees, e.g. `cone@80,13` or
+/// `static.prop.streetbarrier@60,13.4,0,90`. The blueprint is a short
+/// name such as cone, barrier, box or barrel, or a full blueprint id.
+/// The prop is placed on the nearest road if the height is not given.
+#[derive(Debug, Clone)]
+pub struct PropSpec {
+    pub blueprint: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: Option<f32>,
+    pub yaw: f32,
+}
+
+impl PropSpec {
+    /// Load the props from a file of one prop per line. The empty
+    /// lines and the lines starting with `#` are skipped.
+    pub fn load(path: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        text.lines()
+            .enumerate()
+            .map(|(lineno, line)| (lineno, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(lineno, line)| {
+                line.parse()
+                    .with_context(|| format!("invalid prop at {}:{}", path.display(), lineno + 1))
+            })
+            .collect()
+    }
+}
+
+impl FromStr for PropSpec {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let Some((blueprint, location)) = text.split_once('@') else {
+            bail!("expect BLUEPRINT@X,Y[,Z[,YAW]], but get '{text}'");
+        };
+        let blueprint = blueprint.trim();
+        let blueprint = ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == blueprint)
+            .map_or(blueprint, |(_, id)| id);
+
+        let values: Vec<f32> = location
+            .split(',')
+            .map(|value| value.trim().parse())
+            .collect::<Result<_, _>>()?;
+        let (x, y, z, yaw) = match *values.as_slice() {
+            [x, y] => (x, y, None, 0.0),
+            [x, y, z] => (x, y, Some(z), 0.0),
+            [x, y, z, yaw] => (x, y, Some(z), yaw),
+            _ => bail!("expect 2 to 4 coordinates, but get '{location}'"),
+        };
+
+        Ok(Self {
+            blueprint: blueprint.to_string(),
+            x,
+            y,
+            z,
+            yaw,
+        })
+    }
+}
+
+/// The props spawned at the given transforms.
+pub struct PropSet {
+    props: Vec<Actor>,
+}
+
+impl PropSet {
+    /// Spawn the props. A prop is skipped with a message if its
+    /// location is occupied.
+    pub fn spawn(world: &mut World, specs: &[PropSpec]) -> Result<Self> {
+        let library = world.blueprint_library();
+        let map = world.map();
+
+        let mut props = vec![];
+        for spec in specs {
+            let Some(blueprint) = library.find(&spec.blueprint) else {
+                bail!("no prop blueprint is named '{}'", spec.blueprint);
+            };
+            let z = match spec.z {
+                Some(z) => z,
+                None => map
+                    .waypoint(&Translation3::new(spec.x, spec.y, 0.0))
+                    .map_or(0.0, |waypoint| waypoint.transform().translation.z),
+            };
+            let pose = Isometry3::from_parts(
+                Translation3::new(spec.x, spec.y, z),
+                UnitQuaternion::from_euler_angles(0.0, 0.0, spec.yaw.to_radians()),
+            );
+            match world.spawn_actor(&blueprint, &pose) {
+                Ok(prop) => props.push(prop),
+                Err(_) => eprintln!(
+                    "Skip the prop {} at ({}, {}) for the occupied location",
+                    spec.blueprint, spec.x, spec.y
+                ),
+            }
+        }
+        eprintln!("Spawned {} props", props.len());
+
+        Ok(Self { props })
+    }
+
+    pub fn props(&self) -> &[Actor] {
+        &self.props
+    }
+}
+
+impl Drop for PropSet {
+    fn drop(&mut self) {
+        leftover::report(self.props.len(), "props");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_the_aliases() {
+        let spec: PropSpec = "cone@1,2".parse().unwrap();
+        assert_eq!(spec.blueprint, "static.prop.constructioncone");
+        let spec: PropSpec = " static.prop.bench01 @1,2".parse().unwrap();
+        assert_eq!(spec.blueprint, "static.prop.bench01");
+    }
+
+    #[test]
+    fn defaults_the_height_and_the_yaw() {
+        let spec: PropSpec = "barrel@1.5, -2".parse().unwrap();
+        assert_eq!((spec.x, spec.y, spec.z, spec.yaw), (1.5, -2.0, None, 0.0));
+
+        let spec: PropSpec = "barrel@1,2,0.3".parse().unwrap();
+        assert_eq!((spec.z, spec.yaw), (Some(0.3), 0.0));
+
+        let spec: PropSpec = "barrel@1,2,0.3,90".parse().unwrap();
+        assert_eq!((spec.z, spec.yaw), (Some(0.3), 90.0));
+    }
+
+    #[test]
+    fn rejects_a_prop_without_a_location() {
+        assert!("barrel".parse::<PropSpec>().is_err());
+    }
+
+    #[test]
+    fn rejects_the_wrong_number_of_coordinates() {
+        assert!("barrel@1".parse::<PropSpec>().is_err());
+        assert!("barrel@1,2,3,4,5".parse::<PropSpec>().is_err());
+    }
+}
@@ -0,0 +1,30 @@
+//! Rendering settings of the server.
+//!
+//! The shading quality of the server is chosen at its launch by
+//! `-quality-level=Low` or `-quality-level=Epic` and cannot be changed
+//! by a client. The quality level here sets the draw distance of the
+//! map meshes instead, which is what the client can control.
+
+use clap::ValueEnum;
+
+/// The draw distance in meters of the map meshes at the low quality.
+const LOW_DRAW_DISTANCE: f32 = 80.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QualityLevel {
+    /// Cull the meshes beyond a short distance from the camera.
+    Low,
+    /// Draw the meshes as far as the map sets them.
+    Epic,
+}
+
+impl QualityLevel {
+    /// The maximum draw distance in meters of the meshes, where zero
+    /// keeps the distances of the map.
+    pub fn max_culling_distance(self) -> f32 {
+        match self {
+            Self::Low => LOW_DRAW_DISTANCE,
+            Self::Epic => 0.0,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Stream the sensors and the controller state to a rerun viewer.
+//!
+//! CARLA uses a left-handed frame, which is mirrored along the y axis
+//! to the right-handed frame of rerun.
+
+use crate::{actuator::Command, ego::EgoState, lateral::Reference, route::lane_ahead};
+use anyhow::{Context, Result};
+use carla::{
+    client::{Sensor, Vehicle, World},
+    prelude::*,
+    sensor::data::{Image, LidarMeasurement},
+};
+use nalgebra::{Isometry3, Point3, Translation3, UnitQuaternion};
+use rerun::RecordingStream;
+
+/// The distance in meters of the logged route ahead of the vehicle.
+const ROUTE_DISTANCE: f64 = 40.0;
+
+/// The spacing in meters of the logged route.
+const ROUTE_STEP: f64 = 1.0;
+
+/// Logs a front camera, a roof lidar, the ego pose, the route and the
+/// control commands on the `frame` timeline.
+pub struct RerunLogger {
+    stream: RecordingStream,
+    camera: Sensor,
+    lidar: Sensor,
+}
+
+impl RerunLogger {
+    /// Connect to a running viewer and attach the sensors to the
+    /// vehicle.
+    pub fn new(world: &mut World, vehicle: &Vehicle, delta_seconds: f64) -> Result<Self> {
+        let stream = rerun::RecordingStreamBuilder::new("automatic-control")
+            .connect_grpc()
+            .with_context(|| "unable to connect to the rerun viewer")?;
+
+        let camera_pose =
+            Isometry3::from_parts(Translation3::new(1.5, 0.0, 2.4), UnitQuaternion::identity());
+        let camera: Sensor = world
+            .actor_builder("sensor.camera.rgb")?
+            .set_attribute("image_size_x", "640")?
+            .set_attribute("image_size_y", "360")?
+            .spawn_sensor_opt(&camera_pose, Some(vehicle), None)?;
+
+        // Complete a full revolution in every tick.
+        let lidar_pose =
+            Isometry3::from_parts(Translation3::new(0.0, 0.0, 2.4), UnitQuaternion::identity());
+        let lidar: Sensor = world
+            .actor_builder("sensor.lidar.ray_cast")?
+            .set_attribute("channels", "32")?
+            .set_attribute("range", "50")?
+            .set_attribute("points_per_second", "100000")?
+            .set_attribute("rotation_frequency", &(1.0 / delta_seconds).to_string())?
+            .spawn_sensor_opt(&lidar_pose, Some(vehicle), None)?;
+
+        // The time of the recording is thread-local, so it is set in
+        // the sensor threads as well.
+        {
+            let stream = stream.clone();
+            camera.listen(move |data| {
+                stream.set_time_sequence("frame", data.frame() as i64);
+                let image: Image = data.try_into().unwrap();
+                let pixels: Vec<u8> = image
+                    .as_slice()
+                    .iter()
+                    .flat_map(|color| [color.r, color.g, color.b])
+                    .collect();
+                let resolution = [image.width() as u32, image.height() as u32];
+                let _ = stream.log("camera", &rerun::Image::from_rgb24(pixels, resolution));
+            });
+        }
+        {
+            let stream = stream.clone();
+            lidar.listen(move |data| {
+                stream.set_time_sequence("frame", data.frame() as i64);
+                let transform = data.sensor_transform();
+                let measure: LidarMeasurement = data.try_into().unwrap();
+                let points = measure.as_slice().iter().map(|detection| {
+                    let point = &detection.point;
+                    to_rerun(&(transform * Point3::new(point.x, point.y, point.z)))
+                });
+                let _ = stream.log("world/lidar", &rerun::Points3D::new(points));
+            });
+        }
+
+        Ok(Self {
+            stream,
+            camera,
+            lidar,
+        })
+    }
+
+    /// Log the state of the vehicle and the controller in a tick.
+    pub fn log(
+        &self,
+        frame: usize,
+        ego: &EgoState,
+        reference: &Reference,
+        command: &Command,
+        target_speed: f32,
+    ) -> Result<()> {
+        let stream = &self.stream;
+        stream.set_time_sequence("frame", frame as i64);
+
+        let translation = to_rerun(&Point3::from(ego.transform.translation.vector));
+        let rotation = ego.transform.rotation;
+        stream.log(
+            "world/ego",
+            &rerun::Transform3D::from_translation_rotation(
+                translation,
+                rerun::Quaternion::from_xyzw([-rotation.i, rotation.j, -rotation.k, rotation.w]),
+            ),
+        )?;
+
+        let route = lane_ahead(&reference.nearest, ROUTE_STEP, ROUTE_DISTANCE);
+        let route: Vec<[f32; 3]> = route
+            .iter()
+            .map(|waypoint| to_rerun(&Point3::from(waypoint.transform().translation.vector)))
+            .collect();
+        stream.log("world/route", &rerun::LineStrips3D::new([route]))?;
+        let target = to_rerun(&Point3::from(
+            reference.target.transform().translation.vector,
+        ));
+        stream.log(
+            "world/target",
+            &rerun::Points3D::new([target]).with_radii([0.5]),
+        )?;
+
+        for (name, value) in [
+            ("control/steer", command.steer),
+            ("control/acceleration", command.acceleration),
+            ("control/speed", ego.speed),
+            ("control/setpoint_speed", command.speed),
+            ("control/target_speed", target_speed),
+        ] {
+            stream.log(name, &rerun::Scalars::single(value as f64))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for RerunLogger {
+    fn drop(&mut self) {
+        self.camera.stop();
+        self.lidar.stop();
+    }
+}
+
+/// Mirror a point in the CARLA frame to the rerun frame.
+fn to_rerun(point: &Point3<f32>) -> [f32; 3] {
+    [point.x, -point.y, point.z]
+}
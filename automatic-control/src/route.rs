@@ -0,0 +1,37 @@
+//! Utilities to inspect the lane ahead of the vehicle.
+
+use crate::ego::normalize_angle;
+use carla::client::Waypoint;
+
+/// Sample the waypoints on the lane ahead, spaced `step` meters
+/// apart and starting from `waypoint`, until `distance` meters is
+/// covered or the lane ends.
+pub fn lane_ahead(waypoint: &Waypoint, step: f64, distance: f64) -> Vec<Waypoint> {
+    let count = (distance / step).ceil() as usize;
+    let mut waypoints = vec![waypoint.clone()];
+
+    while waypoints.len() <= count {
+        let Some(next) = waypoints.last().unwrap().next(step).get(0) else {
+            break;
+        };
+        waypoints.push(next);
+    }
+
+    waypoints
+}
+
+/// Compute the signed curvature in 1/m between consecutive
+/// waypoints spaced `step` meters apart. Positive values turn right.
+pub fn curvatures(waypoints: &[Waypoint], step: f64) -> Vec<f32> {
+    let yaws: Vec<f32> = waypoints
+        .iter()
+        .map(|waypoint| {
+            let (_, _, yaw) = waypoint.transform().rotation.euler_angles();
+            yaw
+        })
+        .collect();
+
+    yaws.windows(2)
+        .map(|pair| normalize_angle(pair[1] - pair[0]) / step as f32)
+        .collect()
+}
@@ -0,0 +1,38 @@
+//! The episode settings changed for the run.
+
+use carla::{client::World, rpc::EpisodeSettings};
+use std::time::Duration;
+
+/// Gives the world its original settings back when dropped, so that an
+/// error during the run does not leave the server waiting for ticks in
+/// the synchronous mode.
+pub struct SettingsGuard {
+    world: World,
+    orig_settings: EpisodeSettings,
+}
+
+impl SettingsGuard {
+    /// Keep the settings of the world before they are changed.
+    pub fn new(world: &World, orig_settings: EpisodeSettings) -> Self {
+        Self {
+            world: world.clone(),
+            orig_settings,
+        }
+    }
+}
+
+impl Drop for SettingsGuard {
+    fn drop(&mut self) {
+        let orig = &self.orig_settings;
+        let settings = EpisodeSettings {
+            synchronous_mode: false,
+            no_rendering_mode: orig.no_rendering_mode,
+            fixed_delta_seconds: None,
+            max_culling_distance: orig.max_culling_distance,
+            tile_stream_distance: orig.tile_stream_distance,
+            actor_active_distance: orig.actor_active_distance,
+            ..self.world.settings()
+        };
+        self.world.apply_settings(&settings, Duration::ZERO);
+    }
+}
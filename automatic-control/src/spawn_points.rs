@@ -0,0 +1,79 @@
+//! Listing and choice of the recommended spawn points of the map.
+
+use anyhow::{bail, ensure, Result};
+use carla::client::{Actor, ActorBlueprint, Map, World};
+use nalgebra::Isometry3;
+use rand::{seq::SliceRandom, Rng};
+
+/// Print the recommended spawn points with the road and the lane of
+/// their nearest waypoints. The indices are the ones taken by
+/// `--spawn-index`.
+pub fn list(map: &Map) {
+    let spawn_points = map.recommended_spawn_points();
+    println!("{} spawn points on {}", spawn_points.len(), map.name());
+    println!(
+        "{:>5} {:>10} {:>10} {:>8} {:>8} {:>6} {:>7} {:>5}",
+        "index", "x", "y", "z", "yaw", "road", "section", "lane"
+    );
+    for (index, point) in spawn_points.iter().enumerate() {
+        let location = point.translation;
+        let (_, _, yaw) = point.rotation.euler_angles();
+        let lane = match map.waypoint(&location) {
+            Some(waypoint) => format!(
+                "{:>6} {:>7} {:>5}",
+                waypoint.road_id(),
+                waypoint.section_id(),
+                waypoint.lane_id()
+            ),
+            None => format!("{:>6} {:>7} {:>5}", "-", "-", "-"),
+        };
+        println!(
+            "{index:>5} {:>10.3} {:>10.3} {:>8.3} {:>8.2} {lane}",
+            location.x,
+            location.y,
+            location.z,
+            yaw.to_degrees()
+        );
+    }
+}
+
+/// Spawn the actor at the recommended spawn point of the index printed
+/// by [`list`], or at a random one if `index` is `None`. The other
+/// spawn points are tried in random order while the chosen one is
+/// occupied.
+pub fn spawn(
+    world: &mut World,
+    blueprint: &ActorBlueprint,
+    index: Option<usize>,
+    rng: &mut impl Rng,
+) -> Result<(Actor, Isometry3<f32>)> {
+    let map = world.map();
+    let spawn_points = map.recommended_spawn_points();
+    let count = spawn_points.len();
+    ensure!(count > 0, "{} has no spawn points", map.name());
+
+    let mut order: Vec<_> = (0..count).collect();
+    order.shuffle(rng);
+    if let Some(index) = index {
+        ensure!(
+            index < count,
+            "the spawn point {index} is out of range, {} has {count} spawn points",
+            map.name()
+        );
+        order.retain(|&other| other != index);
+        order.insert(0, index);
+    }
+
+    for index in order {
+        let point = spawn_points.get(index).unwrap();
+        // The spawn fails if the point is occupied.
+        match world.spawn_actor(blueprint, &point) {
+            Ok(actor) => {
+                eprintln!("Spawn a vehicle at the spawn point {index}: {point}");
+                return Ok((actor, point));
+            }
+            Err(_) => eprintln!("The spawn point {index} is occupied"),
+        }
+    }
+    bail!("all {count} spawn points of {} are occupied", map.name())
+}
@@ -0,0 +1,205 @@
+//! Place the spectator relative to the vehicle.
+
+use carla::client::World;
+use clap::ValueEnum;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+use std::fmt;
+
+/// The chase camera offset in the vehicle frame.
+const CHASE_OFFSET: [f32; 3] = [-10.0, 0.0, 7.0];
+
+/// The driver's eye point in the vehicle frame.
+const COCKPIT_OFFSET: [f32; 3] = [0.1, -0.4, 1.3];
+
+/// The camera point above the bonnet in the vehicle frame.
+const HOOD_OFFSET: [f32; 3] = [1.2, 0.0, 1.6];
+
+/// The height in meters of the top-down camera above the vehicle.
+const TOP_DOWN_HEIGHT: f32 = 40.0;
+
+/// The pivot above the vehicle origin that the chase camera backs off
+/// from.
+const PIVOT_HEIGHT: f32 = 1.5;
+
+/// The distance in meters kept in front of an obstruction.
+const MARGIN: f32 = 0.5;
+
+/// The shortest chase camera distance as a fraction of the offset.
+const MIN_FRACTION: f32 = 0.15;
+
+/// The fraction of the offset regained per second once the view
+/// clears, so that the camera does not jump back.
+const RECOVERY_RATE: f32 = 0.5;
+
+/// The jump in meters beyond which the smoothing snaps to the new
+/// pose, such as when the vehicle is teleported.
+const SNAP_DISTANCE: f32 = 20.0;
+
+/// The semantic tags of the moving actors, which the camera sees
+/// through. These are the pedestrians, riders and vehicles.
+const DYNAMIC_TAGS: std::ops::RangeInclusive<u8> = 12..=19;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CameraMode {
+    /// Behind and above the vehicle.
+    Chase,
+    /// At the driver's seat.
+    Cockpit,
+    /// Above the bonnet.
+    Hood,
+    /// High above the vehicle looking down, with the vehicle heading
+    /// up.
+    TopDown,
+    /// Leave the spectator to be moved in the server window.
+    Free,
+}
+
+impl CameraMode {
+    /// The modes in the order of the number keys.
+    pub const ALL: [CameraMode; 5] = [
+        CameraMode::Chase,
+        CameraMode::Cockpit,
+        CameraMode::Hood,
+        CameraMode::TopDown,
+        CameraMode::Free,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for CameraMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.to_possible_value().unwrap();
+        f.write_str(name.get_name())
+    }
+}
+
+/// Moves the spectator in the chosen mode. The chase camera moves in
+/// towards the vehicle when a building or the terrain would block the
+/// view, e.g. in tunnels and under bridges.
+///
+/// The chase and top-down poses are smoothed over the ticks so that
+/// the view does not judder with the vehicle. The cockpit and hood
+/// cameras stay rigid since a lag would put them outside the vehicle.
+pub struct SpectatorCamera {
+    mode: CameraMode,
+    avoid_obstructions: bool,
+    smoothing: f32,
+    fraction: f32,
+    pose: Option<Isometry3<f32>>,
+}
+
+impl SpectatorCamera {
+    /// Create the camera. `smoothing` is the fraction from 0 to 1 of
+    /// the distance to the target pose left behind in a tick.
+    pub fn new(mode: CameraMode, avoid_obstructions: bool, smoothing: f32) -> Self {
+        Self {
+            mode,
+            avoid_obstructions,
+            smoothing: smoothing.clamp(0.0, 0.99),
+            fraction: 1.0,
+            pose: None,
+        }
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    /// Switch the mode by a key: `c` cycles the modes and the number
+    /// keys select one. Other keys are ignored. It returns whether the
+    /// mode changed.
+    pub fn handle_key(&mut self, key: char) -> bool {
+        let mode = match key {
+            'c' | 'C' => self.mode.next(),
+            '1'..='5' => CameraMode::ALL[key as usize - '1' as usize],
+            _ => return false,
+        };
+        if mode != self.mode {
+            self.mode = mode;
+            self.fraction = 1.0;
+            self.pose = None;
+            println!("Switch the camera to {mode}");
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The spectator pose for the vehicle pose, or `None` in the free
+    /// mode.
+    pub fn update(
+        &mut self,
+        world: &World,
+        vehicle: &Isometry3<f32>,
+        dt: f32,
+    ) -> Option<Isometry3<f32>> {
+        let attached = |[x, y, z]: [f32; 3]| vehicle * Translation3::new(x, y, z);
+        let pose = match self.mode {
+            CameraMode::Chase if self.avoid_obstructions => self.chase(world, vehicle, dt),
+            CameraMode::Chase => attached(CHASE_OFFSET),
+            CameraMode::Cockpit => attached(COCKPIT_OFFSET),
+            CameraMode::Hood => attached(HOOD_OFFSET),
+            CameraMode::TopDown => {
+                let (_, _, yaw) = vehicle.rotation.euler_angles();
+                let translation =
+                    vehicle.translation.vector + Vector3::new(0.0, 0.0, TOP_DOWN_HEIGHT);
+                Isometry3::from_parts(
+                    Translation3::from(translation),
+                    UnitQuaternion::from_euler_angles(0.0, -90f32.to_radians(), yaw),
+                )
+            }
+            CameraMode::Free => return None,
+        };
+
+        let is_rigid = matches!(self.mode, CameraMode::Cockpit | CameraMode::Hood);
+        let pose = match self.pose {
+            Some(last)
+                if !is_rigid
+                    && (pose.translation.vector - last.translation.vector).norm()
+                        < SNAP_DISTANCE =>
+            {
+                let weight = 1.0 - self.smoothing;
+                let translation = last
+                    .translation
+                    .vector
+                    .lerp(&pose.translation.vector, weight);
+                let rotation = last.rotation.slerp(&pose.rotation, weight);
+                Isometry3::from_parts(Translation3::from(translation), rotation)
+            }
+            _ => pose,
+        };
+        self.pose = Some(pose);
+        Some(pose)
+    }
+
+    fn chase(&mut self, world: &World, vehicle: &Isometry3<f32>, dt: f32) -> Isometry3<f32> {
+        let [x, y, z] = CHASE_OFFSET;
+        let desired = vehicle * Translation3::new(x, y, z);
+        let pivot = vehicle.translation.vector + Vector3::new(0.0, 0.0, PIVOT_HEIGHT);
+        let ray = desired.translation.vector - pivot;
+        let length = ray.norm();
+
+        // Stop in front of the nearest static hit along the ray.
+        let hits = world.cast_ray(&Translation3::from(pivot), &desired.translation);
+        let clear = hits
+            .as_slice()
+            .iter()
+            .filter(|hit| !DYNAMIC_TAGS.contains(&(hit.label.clone() as u8)))
+            .map(|hit| {
+                let location = Vector3::new(hit.location.x, hit.location.y, hit.location.z);
+                ((location - pivot).norm() - MARGIN) / length
+            })
+            .fold(1.0, f32::min)
+            .max(MIN_FRACTION);
+
+        // Move in at once and back out slowly.
+        self.fraction = clear.min(self.fraction + RECOVERY_RATE * dt);
+
+        let translation = Translation3::from(pivot + ray * self.fraction);
+        Isometry3::from_parts(translation, desired.rotation)
+    }
+}
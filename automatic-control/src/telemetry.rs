@@ -0,0 +1,158 @@
+//! Plots of the tracking performance after a run.
+
+use anyhow::{anyhow, Result};
+use plotters::prelude::*;
+use std::path::Path;
+
+/// A line with its label and color.
+type Series<'a> = (&'a str, RGBColor, Vec<(f32, f32)>);
+
+/// The state of the controller in a tick.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// The simulation time in seconds.
+    pub time: f64,
+    /// The vehicle speed in m/s.
+    pub speed: f32,
+    /// The target speed in m/s after the behaviors.
+    pub target_speed: f32,
+    /// The front wheel angle in radians.
+    pub steer: f32,
+    /// The heading error in radians.
+    pub heading_error: f32,
+    /// The cross-track error in meters.
+    pub lateral_error: f32,
+}
+
+/// The samples recorded over a run.
+#[derive(Debug, Clone, Default)]
+pub struct Telemetry {
+    pub samples: Vec<Sample>,
+}
+
+impl Telemetry {
+    pub fn push(&mut self, sample: Sample) {
+        self.samples.push(sample);
+    }
+
+    /// Draw the speed, steering, heading error and cross-track error
+    /// over time as stacked charts in an SVG file.
+    pub fn plot(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let Some(start) = self.samples.first().map(|sample| sample.time) else {
+            eprintln!("No telemetry is recorded, skip plotting");
+            return Ok(());
+        };
+        let end = self.samples.last().unwrap().time.max(start + 1.0);
+        let duration = (end - start) as f32;
+        let series = |value: fn(&Sample) -> f32| -> Vec<(f32, f32)> {
+            self.samples
+                .iter()
+                .map(|sample| ((sample.time - start) as f32, value(sample)))
+                .collect()
+        };
+
+        let root = SVGBackend::new(path, (1000, 1200)).into_drawing_area();
+        root.fill(&WHITE).map_err(plot_error)?;
+        let panels = root.split_evenly((4, 1));
+
+        draw_panel(
+            &panels[0],
+            "speed",
+            "speed (km/h)",
+            duration,
+            &[
+                ("speed", BLUE, series(|sample| sample.speed * 3.6)),
+                (
+                    "target speed",
+                    RED,
+                    series(|sample| sample.target_speed * 3.6),
+                ),
+            ],
+        )?;
+        draw_panel(
+            &panels[1],
+            "steering",
+            "wheel angle (deg)",
+            duration,
+            &[("steer", BLUE, series(|sample| sample.steer.to_degrees()))],
+        )?;
+        draw_panel(
+            &panels[2],
+            "heading error",
+            "heading error (deg)",
+            duration,
+            &[(
+                "heading error",
+                BLUE,
+                series(|sample| sample.heading_error.to_degrees()),
+            )],
+        )?;
+        draw_panel(
+            &panels[3],
+            "cross-track error",
+            "cross-track error (m)",
+            duration,
+            &[(
+                "cross-track error",
+                BLUE,
+                series(|sample| sample.lateral_error),
+            )],
+        )?;
+
+        root.present().map_err(plot_error)?;
+        eprintln!("The telemetry is plotted to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Draw the series on a chart with a shared time axis.
+fn draw_panel<DB: DrawingBackend>(
+    area: &DrawingArea<DB, plotters::coord::Shift>,
+    caption: &str,
+    y_desc: &str,
+    duration: f32,
+    series: &[Series],
+) -> Result<()> {
+    let values = series
+        .iter()
+        .flat_map(|(_, _, points)| points.iter().map(|(_, value)| *value));
+    let min = values.clone().fold(f32::INFINITY, f32::min);
+    let max = values.fold(f32::NEG_INFINITY, f32::max);
+    let margin = ((max - min) * 0.1).max(0.1);
+
+    let mut chart = ChartBuilder::on(area)
+        .caption(caption, ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..duration, (min - margin)..(max + margin))
+        .map_err(plot_error)?;
+    chart
+        .configure_mesh()
+        .x_desc("time (s)")
+        .y_desc(y_desc)
+        .draw()
+        .map_err(plot_error)?;
+
+    for (label, color, points) in series {
+        let color = *color;
+        chart
+            .draw_series(LineSeries::new(points.iter().copied(), color))
+            .map_err(plot_error)?
+            .label(*label)
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(plot_error)?;
+
+    Ok(())
+}
+
+fn plot_error<E: std::error::Error + Send + Sync>(error: DrawingAreaErrorKind<E>) -> anyhow::Error {
+    anyhow!("unable to plot the telemetry: {error}")
+}
@@ -0,0 +1,201 @@
+//! Assemble a chase camera into an animated GIF or APNG at exit.
+//!
+//! The camera captures a frame every few ticks and the frames are
+//! kept in memory. When the run outlasts the frame limit, every other
+//! frame is dropped and the interval doubles, so that the animation
+//! still covers the whole run.
+
+use anyhow::{bail, ensure, Context, Result};
+use carla::{
+    client::{Sensor, Vehicle, World},
+    sensor::data::Image,
+};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, RgbImage, RgbaImage,
+};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// The captured frames and the subsampling of the camera images.
+#[derive(Default)]
+struct Frames {
+    images: Vec<RgbImage>,
+    /// The number of camera images received.
+    received: usize,
+    /// Keep one in `stride` camera images.
+    stride: usize,
+}
+
+/// The capture and playback of the timelapse.
+#[derive(Debug, Clone)]
+pub struct TimelapseConfig {
+    /// Capture a frame every this many ticks.
+    pub every: usize,
+    /// The most frames kept in memory.
+    pub max_frames: usize,
+    pub width: usize,
+    pub height: usize,
+    /// The playback frame rate.
+    pub fps: u16,
+}
+
+pub struct Timelapse {
+    sensor: Sensor,
+    path: PathBuf,
+    fps: u16,
+    frames: Arc<Mutex<Frames>>,
+}
+
+impl Timelapse {
+    pub fn new(
+        path: impl AsRef<Path>,
+        world: &mut World,
+        vehicle: &Vehicle,
+        config: &TimelapseConfig,
+        delta_seconds: f64,
+    ) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let TimelapseConfig {
+            every,
+            max_frames,
+            width,
+            height,
+            fps,
+        } = *config;
+        ensure!(every > 0, "the timelapse interval must be positive");
+        ensure!(max_frames >= 2, "the timelapse needs at least 2 frames");
+        ensure!(fps > 0, "the timelapse frame rate must be positive");
+        match extension(&path).as_deref() {
+            Some("gif" | "png" | "apng") => {}
+            _ => bail!(
+                "the timelapse {} must be a .gif, .png or .apng file",
+                path.display()
+            ),
+        }
+
+        // The camera renders only the captured ticks.
+        let pose = Isometry3::from_parts(
+            Translation3::new(-6.0, 0.0, 3.0),
+            UnitQuaternion::from_euler_angles(0.0, -15f32.to_radians(), 0.0),
+        );
+        let sensor: Sensor = world
+            .actor_builder("sensor.camera.rgb")?
+            .set_attribute("image_size_x", &width.to_string())?
+            .set_attribute("image_size_y", &height.to_string())?
+            .set_attribute("sensor_tick", &(every as f64 * delta_seconds).to_string())?
+            .spawn_sensor_opt(&pose, Some(vehicle), None)?;
+
+        let frames = Arc::new(Mutex::new(Frames {
+            stride: 1,
+            ..Frames::default()
+        }));
+        {
+            let frames = frames.clone();
+            sensor.listen(move |data| {
+                let image: Image = data.try_into().unwrap();
+                let mut frames = frames.lock().unwrap();
+                let index = frames.received;
+                frames.received += 1;
+                if !index.is_multiple_of(frames.stride) {
+                    return;
+                }
+
+                let pixels = image
+                    .as_slice()
+                    .iter()
+                    .flat_map(|color| [color.r, color.g, color.b])
+                    .collect();
+                let image = RgbImage::from_raw(image.width() as u32, image.height() as u32, pixels)
+                    .unwrap();
+                frames.images.push(image);
+
+                if frames.images.len() == max_frames {
+                    let mut index = 0usize;
+                    frames.images.retain(|_| {
+                        index += 1;
+                        !index.is_multiple_of(2)
+                    });
+                    frames.stride *= 2;
+                }
+            });
+        }
+
+        eprintln!("Capture a timelapse to {}", path.display());
+        Ok(Self {
+            sensor,
+            path,
+            fps,
+            frames,
+        })
+    }
+
+    /// Stop the camera and write the animation.
+    pub fn finish(&mut self) -> Result<()> {
+        self.sensor.stop();
+        let images = std::mem::take(&mut self.frames.lock().unwrap().images);
+        if images.is_empty() {
+            eprintln!("The timelapse has no frames, skip writing it");
+            return Ok(());
+        }
+
+        let file = File::create(&self.path)
+            .with_context(|| format!("unable to create {}", self.path.display()))?;
+        let writer = BufWriter::new(file);
+        let count = images.len();
+        if extension(&self.path).as_deref() == Some("gif") {
+            write_gif(writer, images, self.fps)?;
+        } else {
+            write_apng(writer, images, self.fps)?;
+        }
+        eprintln!(
+            "Saved the timelapse of {count} frames to {}",
+            self.path.display()
+        );
+        Ok(())
+    }
+}
+
+impl Drop for Timelapse {
+    fn drop(&mut self) {
+        self.sensor.stop();
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+fn write_gif(writer: BufWriter<File>, images: Vec<RgbImage>, fps: u16) -> Result<()> {
+    // Quantizing the colors dominates the time. The speed of 10 is
+    // fast and still looks good.
+    let mut encoder = GifEncoder::new_with_speed(writer, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_numer_denom_ms(1000, fps as u32);
+    encoder.encode_frames(images.into_iter().map(|image| {
+        let image: RgbaImage = image::DynamicImage::ImageRgb8(image).into_rgba8();
+        Frame::from_parts(image, 0, 0, delay)
+    }))?;
+    Ok(())
+}
+
+fn write_apng(writer: BufWriter<File>, images: Vec<RgbImage>, fps: u16) -> Result<()> {
+    let (width, height) = images[0].dimensions();
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(images.len() as u32, 0)?;
+    encoder.set_frame_delay(1, fps)?;
+    let mut writer = encoder.write_header()?;
+    for image in &images {
+        writer.write_image_data(image.as_raw())?;
+    }
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Regression runs of the controllers over several towns.
+
+use crate::{run, towns, Metrics, Opts, FIXED_DELTA_SECONDS};
+use anyhow::{ensure, Result};
+use carla::client::Client;
+use clap::Args;
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct TourOpts {
+    /// The comma-separated towns to drive in order.
+    #[clap(
+        long,
+        value_delimiter = ',',
+        default_value = "Town01,Town02,Town03,Town04,Town05"
+    )]
+    pub towns: Vec<String>,
+
+    /// The duration in seconds of the episode in each town.
+    #[clap(long, default_value = "60.0")]
+    pub episode_seconds: f64,
+}
+
+/// Reload the world, respawn the vehicle and drive an episode in each
+/// town, then print the metrics by town. The output files of the
+/// options are suffixed by the town names.
+pub fn tour(
+    client: &Client,
+    opts: &Opts,
+    tour_opts: &TourOpts,
+    stop: &Arc<AtomicBool>,
+) -> Result<()> {
+    ensure!(
+        opts.opendrive.is_none() && opts.osm.is_none(),
+        "the tour loads the towns and cannot be combined with --opendrive or --osm"
+    );
+    // The metrics server keeps its port until the program exits.
+    ensure!(
+        opts.prometheus.is_none(),
+        "--prometheus cannot serve more than one town of the tour"
+    );
+    ensure!(
+        tour_opts.episode_seconds > 0.0,
+        "the episode duration must be positive"
+    );
+    for town in &tour_opts.towns {
+        towns::validate(client, town)?;
+    }
+    let max_ticks = (tour_opts.episode_seconds / FIXED_DELTA_SECONDS).round() as usize;
+
+    let mut results = vec![];
+    for town in &tour_opts.towns {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        eprintln!("Tour {town} for {} s", tour_opts.episode_seconds);
+
+        let mut town_opts = opts.clone();
+        town_opts.world = Some(town.clone());
+        town_opts.action = None;
+        for path in [
+            &mut town_opts.plot,
+            &mut town_opts.mcap,
+            &mut town_opts.log_csv,
+            &mut town_opts.log_events,
+            &mut town_opts.log_parquet,
+            &mut town_opts.record_video,
+            &mut town_opts.timelapse,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = suffixed(path, town);
+        }
+
+        if let Some(metrics) = run(client, &town_opts, stop, Some(max_ticks))? {
+            results.push((town.as_str(), metrics));
+        }
+    }
+
+    print_table(&results);
+    Ok(())
+}
+
+/// Insert the town name before the extension of the path, e.g.
+/// `log.csv` becomes `log-Town01.csv`.
+fn suffixed(path: &Path, town: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}-{town}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{town}"),
+    };
+    path.with_file_name(name)
+}
+
+fn print_table(results: &[(&str, Metrics)]) {
+    let row = |name: &str, metrics: &Metrics| {
+        let ticks = metrics.ticks.max(1) as f32;
+        println!(
+            "{name:<12} {:>7} {:>12.3} {:>12.3} {:>7} {:>10}",
+            metrics.ticks,
+            metrics.lateral_error / ticks,
+            metrics.speed_error / ticks,
+            metrics.resets,
+            metrics.collisions
+        );
+    };
+
+    println!(
+        "{:<12} {:>7} {:>12} {:>12} {:>7} {:>10}",
+        "town", "ticks", "cross-track", "speed error", "resets", "collisions"
+    );
+    let mut total = Metrics::default();
+    for (town, metrics) in results {
+        row(town, metrics);
+        total.ticks += metrics.ticks;
+        total.lateral_error += metrics.lateral_error;
+        total.speed_error += metrics.speed_error;
+        total.resets += metrics.resets;
+        total.collisions += metrics.collisions;
+    }
+    row("total", &total);
+}
@@ -0,0 +1,100 @@
+//! Listing and validation of the maps available on the server.
+
+use anyhow::{bail, Result};
+use carla::client::Client;
+
+/// Print the names of the available maps.
+pub fn list(client: &Client) {
+    let mut maps = client.avaiable_maps();
+    maps.sort();
+    println!("{} maps are available", maps.len());
+    for path in &maps {
+        println!("{:<16} {path}", town_name(path));
+    }
+}
+
+/// Check that the map given by a name such as `Town03` or by a full
+/// path is available, and suggest the closest name if it is not.
+pub fn validate(client: &Client, world: &str) -> Result<()> {
+    let maps = client.avaiable_maps();
+    if maps
+        .iter()
+        .any(|path| path == world || town_name(path) == world)
+    {
+        return Ok(());
+    }
+
+    let closest = maps
+        .iter()
+        .map(|path| town_name(path))
+        .min_by_key(|name| edit_distance(&name.to_lowercase(), &world.to_lowercase()));
+    match closest {
+        Some(name) => bail!(
+            "the map '{world}' is not available, did you mean '{name}'? \
+             Run the towns subcommand to list the maps."
+        ),
+        None => bail!("the map '{world}' is not available, the server has no maps"),
+    }
+}
+
+/// The last component of the map path, e.g. `Town03` of
+/// `/Game/Carla/Maps/Town03`.
+fn town_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// The Levenshtein distance between the strings.
+fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let rhs: Vec<char> = rhs.chars().collect();
+    let mut row: Vec<usize> = (0..=rhs.len()).collect();
+    for (i, lhs_char) in lhs.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &rhs_char) in rhs.iter().enumerate() {
+            let substitution = diagonal + usize::from(lhs_char != rhs_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[rhs.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_edits() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("town03", "town03"), 0);
+        assert_eq!(edit_distance("", "town"), 4);
+        assert_eq!(edit_distance("town", ""), 4);
+        assert_eq!(edit_distance("town3", "town03"), 1);
+        assert_eq!(edit_distance("town04", "town03"), 1);
+        assert_eq!(edit_distance("twon03", "town03"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn is_symmetric() {
+        for (lhs, rhs) in [
+            ("town10hd", "town10hd_opt"),
+            ("kitten", "sitting"),
+            ("", "a"),
+        ] {
+            assert_eq!(edit_distance(lhs, rhs), edit_distance(rhs, lhs));
+        }
+    }
+
+    #[test]
+    fn counts_characters_rather_than_bytes() {
+        assert_eq!(edit_distance("straße", "strasse"), 2);
+        assert_eq!(edit_distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn takes_the_last_path_component() {
+        assert_eq!(town_name("/Game/Carla/Maps/Town03"), "Town03");
+        assert_eq!(town_name("Town03"), "Town03");
+    }
+}
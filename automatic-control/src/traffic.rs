@@ -0,0 +1,54 @@
+//! Configuration of the Traffic Manager driving the autopilot
+//! vehicles.
+
+use carla::{
+    client::{Client, Vehicle},
+    traffic_manager::TrafficManager,
+};
+
+/// The Traffic Manager settings given on the command line. The unset
+/// values are left at the defaults of the Traffic Manager.
+#[derive(Debug, Clone)]
+pub struct TrafficConfig {
+    pub port: u16,
+    /// Run in lockstep with the synchronous ticks of the client.
+    pub synchronous: bool,
+    /// The percentage below the speed limit driven by the vehicles.
+    /// Negative values exceed the limit.
+    pub speed_difference: Option<f32>,
+    /// The distance in meters kept to the leading vehicle.
+    pub min_distance: Option<f32>,
+    /// The percentage of the red lights run by each vehicle.
+    pub ignore_lights: f32,
+    /// Simulate the physics only within the radius in meters around
+    /// the ego vehicle.
+    pub hybrid_physics_radius: Option<f32>,
+}
+
+impl TrafficConfig {
+    /// Connect to the Traffic Manager on the port and apply the global
+    /// settings. It is shut down when the handle is dropped.
+    pub fn connect(&self, client: &Client) -> TrafficManager {
+        let mut traffic_manager = client.instance_tm(self.port);
+        traffic_manager.set_synchronous_mode(self.synchronous);
+        if let Some(percentage) = self.speed_difference {
+            traffic_manager.set_global_percentage_speed_difference(percentage);
+        }
+        if let Some(distance) = self.min_distance {
+            traffic_manager.set_global_distance_to_leading_vehicle(distance);
+        }
+        if let Some(radius) = self.hybrid_physics_radius {
+            traffic_manager.set_hybrid_physics_mode(true);
+            traffic_manager.set_hybrid_physics_radius(radius);
+        }
+        eprintln!("Connected to the Traffic Manager on port {}", self.port);
+        traffic_manager
+    }
+
+    /// Hand the vehicle to the Traffic Manager with the per-vehicle
+    /// settings.
+    pub fn drive(&self, traffic_manager: &mut TrafficManager, vehicle: &Vehicle) {
+        vehicle.set_autopilot_opt(true, self.port);
+        traffic_manager.set_percentage_running_light(vehicle, self.ignore_lights);
+    }
+}
@@ -0,0 +1,322 @@
+//! A terminal view of the run.
+//!
+//! The view takes over the terminal while driving. The messages
+//! printed to stdout and stderr meanwhile are captured and shown in a
+//! pane below the tables. The terminal is in the raw mode to read the
+//! keys, so Ctrl-C is read as a key instead of a signal.
+
+use crate::{
+    actuator::Command, behavior::light_state_name, counter::FrameCount, ego::EgoState,
+    spectator::CameraMode,
+};
+use anyhow::{Context, Result};
+use carla::{
+    client::{ActorBase, TrafficLight, World},
+    rpc::TrafficLightState,
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        cursor::{Hide, Show},
+        event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Color, Style},
+    widgets::{Block, Cell, Paragraph, Row, Table},
+    Terminal,
+};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// The number of traffic lights listed.
+const TRAFFIC_LIGHTS: usize = 5;
+
+/// The number of captured messages kept.
+const MESSAGES: usize = 100;
+
+/// The state of the run in a tick.
+pub struct TuiFrame<'a> {
+    pub frame: usize,
+    pub ego: &'a EgoState,
+    pub target_speed: f32,
+    pub command: &'a Command,
+    pub camera: CameraMode,
+    /// The measurements received by each sensor.
+    pub sensors: Vec<(&'static str, FrameCount)>,
+    pub collisions: usize,
+    pub resets: usize,
+    /// The total and solid lane marking crossings.
+    pub lane_invasions: Option<(usize, usize)>,
+}
+
+/// Draws the tables on the terminal and restores it when dropped.
+pub struct Tui {
+    terminal: Terminal<CrosstermBackend<File>>,
+    messages: Arc<Mutex<VecDeque<String>>>,
+    capture: Option<JoinHandle<()>>,
+    saved_stdout: OwnedFd,
+    saved_stderr: OwnedFd,
+    stop: Arc<AtomicBool>,
+}
+
+impl Tui {
+    /// Create the view. Ctrl-C and `q` set `stop`.
+    pub fn new(stop: Arc<AtomicBool>) -> Result<Self> {
+        let saved_stdout = dup(1)?;
+        let saved_stderr = dup(2)?;
+        let mut output = File::from(dup(1)?);
+        execute!(output, EnterAlternateScreen, Hide)?;
+        enable_raw_mode()?;
+        let terminal = Terminal::new(CrosstermBackend::new(output))?;
+
+        // Send stdout and stderr to a pipe read by a thread, which
+        // exits once both are restored.
+        let (reader, writer) = io::pipe()?;
+        dup2(writer.as_raw_fd(), 1).with_context(|| "unable to capture stdout")?;
+        dup2(writer.as_raw_fd(), 2).with_context(|| "unable to capture stderr")?;
+        drop(writer);
+
+        let messages = Arc::new(Mutex::new(VecDeque::new()));
+        let capture = {
+            let messages = messages.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(reader).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    let mut messages = messages.lock().unwrap();
+                    if messages.len() == MESSAGES {
+                        messages.pop_front();
+                    }
+                    messages.push_back(line);
+                }
+            })
+        };
+
+        Ok(Self {
+            terminal,
+            messages,
+            capture: Some(capture),
+            saved_stdout,
+            saved_stderr,
+            stop,
+        })
+    }
+
+    /// The keys typed since the last call.
+    pub fn keys(&mut self) -> Result<Vec<char>> {
+        let mut keys = vec![];
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.stop.store(true, Ordering::SeqCst);
+                }
+                KeyCode::Char('q') => self.stop.store(true, Ordering::SeqCst),
+                KeyCode::Char(key) => keys.push(key),
+                _ => {}
+            }
+        }
+        Ok(keys)
+    }
+
+    pub fn draw(&mut self, world: &World, state: &TuiFrame) -> Result<()> {
+        let ego = state.ego;
+        let command = state.command;
+
+        let rows = [
+            ("frame", state.frame.to_string()),
+            ("speed", format!("{:.1} km/h", ego.speed * 3.6)),
+            (
+                "target speed",
+                format!("{:.1} km/h", state.target_speed * 3.6),
+            ),
+            ("acceleration", format!("{:+.2} m/s²", command.acceleration)),
+            ("steer", format!("{:+.1}°", command.steer.to_degrees())),
+            ("yaw", format!("{:.1}°", ego.yaw.to_degrees())),
+            ("pitch", format!("{:+.1}°", ego.pitch.to_degrees())),
+            (
+                "location",
+                format!(
+                    "({:.1}, {:.1}, {:.1})",
+                    ego.transform.translation.x,
+                    ego.transform.translation.y,
+                    ego.transform.translation.z
+                ),
+            ),
+            ("hand brake", command.hand_brake.to_string()),
+            ("camera", state.camera.to_string()),
+        ];
+        let ego_table = key_value_table("Ego", rows.iter().map(|(key, value)| (*key, value)));
+
+        // The traffic lights are listed by the distance to the vehicle.
+        let location = ego.transform.translation.vector;
+        let mut lights: Vec<_> = world
+            .actors()
+            .filter("traffic.traffic_light")
+            .iter()
+            .filter_map(|actor| TrafficLight::try_from(actor).ok())
+            .map(|light| {
+                let distance = (light.location().vector - location).norm();
+                (light, distance)
+            })
+            .collect();
+        lights.sort_by(|(_, lhs), (_, rhs)| lhs.total_cmp(rhs));
+        let light_rows = lights.iter().take(TRAFFIC_LIGHTS).map(|(light, distance)| {
+            let state = light.state();
+            let color = match state {
+                TrafficLightState::Red => Color::Red,
+                TrafficLightState::Yellow => Color::Yellow,
+                TrafficLightState::Green => Color::Green,
+                _ => Color::Gray,
+            };
+            Row::new([
+                Cell::from(light.id().to_string()),
+                Cell::from(light_state_name(state)).style(Style::default().fg(color)),
+                Cell::from(format!("{distance:.1} m")),
+            ])
+        });
+        let light_table = Table::new(
+            light_rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["id", "state", "distance"]))
+        .block(Block::bordered().title("Traffic lights"));
+
+        let sensor_rows = state.sensors.iter().map(|(name, frames)| {
+            Row::new([
+                name.to_string(),
+                frames.count.to_string(),
+                frames
+                    .last_frame
+                    .map_or_else(|| "-".to_string(), |frame| frame.to_string()),
+            ])
+        });
+        let sensor_table = Table::new(
+            sensor_rows,
+            [
+                Constraint::Length(16),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["sensor", "count", "last frame"]))
+        .block(Block::bordered().title("Sensors"));
+
+        let mut infractions = vec![
+            ("collisions", state.collisions.to_string()),
+            ("off-road resets", state.resets.to_string()),
+        ];
+        if let Some((total, solid)) = state.lane_invasions {
+            infractions.push(("lane invasions", total.to_string()));
+            infractions.push(("solid crossings", solid.to_string()));
+        }
+        let infraction_table = key_value_table(
+            "Infractions",
+            infractions.iter().map(|(key, value)| (*key, value)),
+        );
+
+        let messages = self.messages.lock().unwrap();
+        self.terminal.draw(|frame| {
+            let [top, middle, bottom] = Layout::vertical([
+                Constraint::Length(rows.len() as u16 + 2),
+                Constraint::Length(TRAFFIC_LIGHTS as u16 + 3),
+                Constraint::Fill(1),
+            ])
+            .areas(frame.area());
+            let [ego_area, infraction_area] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(top);
+            let [light_area, sensor_area] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(middle);
+
+            // Show the latest messages that fit in the pane.
+            let visible = bottom.height.saturating_sub(2) as usize;
+            let text: Vec<_> = messages
+                .iter()
+                .skip(messages.len().saturating_sub(visible))
+                .map(String::as_str)
+                .collect();
+
+            frame.render_widget(ego_table, ego_area);
+            frame.render_widget(infraction_table, infraction_area);
+            frame.render_widget(light_table, light_area);
+            frame.render_widget(sensor_table, sensor_area);
+            frame.render_widget(
+                Paragraph::new(text.join("\n")).block(
+                    Block::bordered().title("Messages (c: camera, 1-5: camera mode, q: quit)"),
+                ),
+                bottom,
+            );
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Tui {
+    /// Restore the terminal and print the captured messages again
+    /// since the alternate screen is gone.
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        let _ = dup2(self.saved_stdout.as_raw_fd(), 1);
+        let _ = dup2(self.saved_stderr.as_raw_fd(), 2);
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen, Show);
+
+        if let Some(capture) = self.capture.take() {
+            let _ = capture.join();
+        }
+        for message in self.messages.lock().unwrap().drain(..) {
+            eprintln!("{message}");
+        }
+    }
+}
+
+fn key_value_table<'a>(
+    title: &'a str,
+    rows: impl IntoIterator<Item = (&'a str, &'a String)>,
+) -> Table<'a> {
+    let rows = rows
+        .into_iter()
+        .map(|(key, value)| Row::new([key, value.as_str()]));
+    Table::new(rows, [Constraint::Length(16), Constraint::Fill(1)])
+        .block(Block::bordered().title(title))
+}
+
+fn dup(fd: RawFd) -> io::Result<OwnedFd> {
+    // SAFETY: The new descriptor is owned by nothing else.
+    let copy = unsafe { libc::dup(fd) };
+    if copy < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(copy) })
+}
+
+/// Make `dst` refer to the same file as `src`.
+fn dup2(src: RawFd, dst: RawFd) -> io::Result<()> {
+    if unsafe { libc::dup2(src, dst) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Automatic tuning of the controller gains.
+
+use crate::{events::Event, lateral::ControllerKind, Metrics, Opts, Session, FIXED_DELTA_SECONDS};
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use std::sync::atomic::Ordering;
+
+/// The cost added for every time the vehicle leaves the road or
+/// crashes.
+const RESET_PENALTY: f32 = 100.0;
+
+#[derive(Debug, Clone, Args)]
+pub struct TuneOpts {
+    /// The search method over the gains.
+    #[clap(long, value_enum, default_value = "twiddle")]
+    pub method: TuneMethod,
+
+    /// The duration in seconds of each episode.
+    #[clap(long, default_value = "30.0")]
+    pub episode_seconds: f64,
+
+    /// The maximum number of twiddle iterations.
+    #[clap(long, default_value = "10")]
+    pub iterations: usize,
+
+    /// The twiddle search stops when the sum of the step sizes falls
+    /// below this value.
+    #[clap(long, default_value = "0.01")]
+    pub tolerance: f32,
+
+    /// The number of values per gain in the grid search.
+    #[clap(long, default_value = "3")]
+    pub grid_steps: usize,
+
+    /// The relative range around the initial gains covered by the
+    /// grid search.
+    #[clap(long, default_value = "0.5")]
+    pub grid_range: f32,
+
+    /// The weight of the speed error relative to the cross-track
+    /// error in the cost.
+    #[clap(long, default_value = "0.5")]
+    pub speed_weight: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TuneMethod {
+    /// The coordinate descent that grows the step sizes on success
+    /// and shrinks them on failure.
+    Twiddle,
+    /// Evaluate every combination of gains on a grid.
+    Grid,
+}
+
+/// A tunable gain of the command line options.
+struct Gain {
+    flag: &'static str,
+    get: fn(&Opts) -> f32,
+    set: fn(&mut Opts, f32),
+}
+
+/// Search the gains of the speed controller and the selected lateral
+/// controller and print the best parameter set.
+pub fn tune(session: &mut Session, opts: &Opts, tune_opts: &TuneOpts) -> Result<()> {
+    let gains = gains(opts.controller);
+    let mut tuner = Tuner {
+        session,
+        opts: opts.clone(),
+        tune_opts,
+        gains: &gains,
+        best: None,
+    };
+    let initial: Vec<f32> = gains.iter().map(|gain| (gain.get)(opts)).collect();
+
+    match tune_opts.method {
+        TuneMethod::Twiddle => tuner.twiddle(initial)?,
+        TuneMethod::Grid => tuner.grid(initial)?,
+    }
+
+    match tuner.best {
+        Some((cost, values)) => {
+            println!("best cost={cost:.4}: {}", format_flags(&gains, &values));
+        }
+        None => eprintln!("No episode was completed"),
+    }
+
+    Ok(())
+}
+
+struct Tuner<'a> {
+    session: &'a mut Session,
+    opts: Opts,
+    tune_opts: &'a TuneOpts,
+    gains: &'a [Gain],
+    best: Option<(f32, Vec<f32>)>,
+}
+
+impl Tuner<'_> {
+    fn twiddle(&mut self, mut values: Vec<f32>) -> Result<()> {
+        let mut steps: Vec<f32> = values.iter().map(|value| 0.5 * value.max(0.1)).collect();
+        let Some(mut best) = self.evaluate(&values)? else {
+            return Ok(());
+        };
+
+        for iteration in 0..self.tune_opts.iterations {
+            if steps.iter().sum::<f32>() < self.tune_opts.tolerance {
+                break;
+            }
+            eprintln!("Twiddle iteration {iteration}");
+
+            for index in 0..values.len() {
+                let orig = values[index];
+
+                values[index] = orig + steps[index];
+                let Some(cost) = self.evaluate(&values)? else {
+                    return Ok(());
+                };
+                if cost < best {
+                    best = cost;
+                    steps[index] *= 1.1;
+                    continue;
+                }
+
+                values[index] = (orig - steps[index]).max(0.0);
+                let Some(cost) = self.evaluate(&values)? else {
+                    return Ok(());
+                };
+                if cost < best {
+                    best = cost;
+                    steps[index] *= 1.1;
+                    continue;
+                }
+
+                values[index] = orig;
+                steps[index] *= 0.9;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn grid(&mut self, initial: Vec<f32>) -> Result<()> {
+        let steps = self.tune_opts.grid_steps.max(1);
+        let range = self.tune_opts.grid_range;
+        let axes: Vec<Vec<f32>> = initial
+            .iter()
+            .map(|&value| {
+                if steps == 1 {
+                    return vec![value];
+                }
+                (0..steps)
+                    .map(|step| {
+                        let ratio = step as f32 / (steps - 1) as f32;
+                        value * (1.0 - range + 2.0 * range * ratio)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let count = steps.pow(axes.len() as u32);
+        for combination in 0..count {
+            let mut rem = combination;
+            let values: Vec<f32> = axes
+                .iter()
+                .map(|axis| {
+                    let value = axis[rem % steps];
+                    rem /= steps;
+                    value
+                })
+                .collect();
+
+            if self.evaluate(&values)?.is_none() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run an episode with the gains and return the cost. It returns
+    /// `None` if the tuning is interrupted.
+    fn evaluate(&mut self, values: &[f32]) -> Result<Option<f32>> {
+        for (gain, &value) in self.gains.iter().zip(values) {
+            (gain.set)(&mut self.opts, value);
+            if let Some(events) = &self.session.event_log {
+                events.log(&self.session.world, Event::parameter(gain.flag, value))?;
+            }
+        }
+
+        let ticks = (self.tune_opts.episode_seconds / FIXED_DELTA_SECONDS) as usize;
+        self.session.reset();
+        let metrics = self.session.drive(&self.opts, Some(ticks))?;
+        if self.session.stop.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+
+        let cost = cost(&metrics, self.tune_opts.speed_weight);
+        eprintln!("cost={cost:.4}: {}", format_flags(self.gains, values));
+
+        if self.best.as_ref().is_none_or(|(best, _)| cost < *best) {
+            self.best = Some((cost, values.to_vec()));
+        }
+
+        Ok(Some(cost))
+    }
+}
+
+/// The mean weighted tracking error with penalties on leaving the
+/// road and collisions.
+fn cost(metrics: &Metrics, speed_weight: f32) -> f32 {
+    if metrics.ticks == 0 {
+        return f32::INFINITY;
+    }
+    let errors = metrics.lateral_error + speed_weight * metrics.speed_error;
+    let restarts = metrics.resets + metrics.collisions;
+    errors / metrics.ticks as f32 + RESET_PENALTY * restarts as f32
+}
+
+fn format_flags(gains: &[Gain], values: &[f32]) -> String {
+    gains
+        .iter()
+        .zip(values)
+        .map(|(gain, value)| format!("--{} {value:.4}", gain.flag))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The gains tuned for the lateral controller together with the
+/// speed controller.
+fn gains(controller: ControllerKind) -> Vec<Gain> {
+    let mut gains = vec![
+        Gain {
+            flag: "speed-kp",
+            get: |opts| opts.speed_kp,
+            set: |opts, value| opts.speed_kp = value,
+        },
+        Gain {
+            flag: "speed-ki",
+            get: |opts| opts.speed_ki,
+            set: |opts, value| opts.speed_ki = value,
+        },
+    ];
+
+    match controller {
+        ControllerKind::Heading => gains.extend([
+            Gain {
+                flag: "steer-kp",
+                get: |opts| opts.steer_kp,
+                set: |opts, value| opts.steer_kp = value,
+            },
+            Gain {
+                flag: "steer-kd",
+                get: |opts| opts.steer_kd,
+                set: |opts, value| opts.steer_kd = value,
+            },
+        ]),
+        ControllerKind::Stanley => gains.extend([
+            Gain {
+                flag: "stanley-gain",
+                get: |opts| opts.stanley_gain,
+                set: |opts, value| opts.stanley_gain = value,
+            },
+            Gain {
+                flag: "stanley-softening",
+                get: |opts| opts.stanley_softening,
+                set: |opts, value| opts.stanley_softening = value,
+            },
+        ]),
+        ControllerKind::PurePursuit => gains.push(Gain {
+            flag: "pursuit-lookahead",
+            get: |opts| opts.pursuit_lookahead as f32,
+            set: |opts, value| opts.pursuit_lookahead = value as f64,
+        }),
+        ControllerKind::Lqr => gains.extend([
+            Gain {
+                flag: "lqr-q-lateral",
+                get: |opts| opts.lqr_q_lateral,
+                set: |opts, value| opts.lqr_q_lateral = value,
+            },
+            Gain {
+                flag: "lqr-q-heading",
+                get: |opts| opts.lqr_q_heading,
+                set: |opts, value| opts.lqr_q_heading = value,
+            },
+            Gain {
+                flag: "lqr-r",
+                get: |opts| opts.lqr_r,
+                set: |opts, value| opts.lqr_r = value,
+            },
+        ]),
+    }
+
+    gains
+}
@@ -0,0 +1,124 @@
+//! Record a chase camera to a video file by ffmpeg.
+
+use anyhow::{bail, ensure, Context, Result};
+use carla::{
+    client::{Sensor, Vehicle, World},
+    sensor::data::Image,
+};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+/// The number of frames buffered before the camera waits for ffmpeg.
+const QUEUE_SIZE: usize = 8;
+
+/// Pipes the camera images to an ffmpeg process, which encodes them
+/// at the simulation frame rate.
+pub struct VideoRecorder {
+    sensor: Sensor,
+    ffmpeg: Child,
+    sender: SyncSender<Option<Vec<u8>>>,
+    writer: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl VideoRecorder {
+    pub fn new(
+        path: impl AsRef<Path>,
+        world: &mut World,
+        vehicle: &Vehicle,
+        width: usize,
+        height: usize,
+        delta_seconds: f64,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        // The H.264 encoder requires even dimensions.
+        ensure!(
+            width.is_multiple_of(2) && height.is_multiple_of(2),
+            "the video size {width}x{height} must be even"
+        );
+
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-y"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-video_size", &format!("{width}x{height}")])
+            .args(["-framerate", &(1.0 / delta_seconds).to_string()])
+            .args(["-i", "-"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| "unable to run ffmpeg, which must be installed to record videos")?;
+        let mut stdin = ffmpeg.stdin.take().unwrap();
+
+        // Write the frames in a thread, so that a slow encoder does
+        // not stall the sensor thread until the queue is full. `None`
+        // ends the stream.
+        let (sender, receiver) = mpsc::sync_channel::<Option<Vec<u8>>>(QUEUE_SIZE);
+        let writer = thread::spawn(move || {
+            while let Ok(Some(frame)) = receiver.recv() {
+                stdin.write_all(&frame)?;
+            }
+            Ok(())
+        });
+
+        let pose = Isometry3::from_parts(
+            Translation3::new(-6.0, 0.0, 3.0),
+            UnitQuaternion::from_euler_angles(0.0, -15f32.to_radians(), 0.0),
+        );
+        let sensor: Sensor = world
+            .actor_builder("sensor.camera.rgb")?
+            .set_attribute("image_size_x", &width.to_string())?
+            .set_attribute("image_size_y", &height.to_string())?
+            .spawn_sensor_opt(&pose, Some(vehicle), None)?;
+        {
+            let sender = sender.clone();
+            sensor.listen(move |data| {
+                let image: Image = data.try_into().unwrap();
+                let pixels = image
+                    .as_slice()
+                    .iter()
+                    .flat_map(|color| [color.r, color.g, color.b])
+                    .collect();
+                // The writer is gone only if ffmpeg failed, which is
+                // reported at exit.
+                let _ = sender.send(Some(pixels));
+            });
+        }
+
+        eprintln!("Record the video to {}", path.display());
+        Ok(Self {
+            sensor,
+            ffmpeg,
+            sender,
+            writer: Some(writer),
+        })
+    }
+
+    /// Stop the camera and wait for ffmpeg to finish the file.
+    pub fn finish(&mut self) -> Result<()> {
+        self.sensor.stop();
+        let _ = self.sender.send(None);
+        if let Some(writer) = self.writer.take() {
+            writer
+                .join()
+                .unwrap()
+                .with_context(|| "unable to send the frames to ffmpeg")?;
+        }
+        let status = self.ffmpeg.wait()?;
+        if !status.success() {
+            bail!("ffmpeg exited with {status}");
+        }
+        Ok(())
+    }
+}
+
+impl Drop for VideoRecorder {
+    fn drop(&mut self) {
+        self.sensor.stop();
+    }
+}
@@ -0,0 +1,208 @@
+//! Pedestrians walking scripted routes for repeatable scenarios.
+//!
+//! The routes are loaded from a JSON file of the form
+//!
+//! ```json
+//! [
+//!     {
+//!         "blueprint": "walker.pedestrian.0001",
+//!         "speed": 1.4,
+//!         "loop": false,
+//!         "targets": [
+//!             { "x": 70.0, "y": 20.0, "dwell": 2.0 },
+//!             { "x": 70.0, "y": 5.0 }
+//!         ]
+//!     }
+//! ]
+//! ```
+//!
+//! Each pedestrian spawns at the first target and walks straight to
+//! the following ones at the speed in m/s, waiting for the dwell time
+//! in seconds at each of them. The blueprint, the speed, the loop flag,
+//! the target height and the dwell time are optional. Like the crowd,
+//! the pedestrians are moved by the client since the AI walker
+//! controller is not available to it.
+
+use crate::{leftover, walkers::walk_towards};
+use anyhow::{bail, ensure, Context, Result};
+use carla::{
+    client::{Actor, ActorBase, World},
+    road::LaneType,
+};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+/// The height in meters of the pedestrian center above the ground.
+const LIFT: f32 = 1.0;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WalkerRoute {
+    /// The pedestrian blueprint, or the first one of the library by id
+    /// if it is not given.
+    #[serde(default)]
+    pub blueprint: Option<String>,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    /// Walk to the first target again after the last one.
+    #[serde(default, rename = "loop")]
+    pub repeat: bool,
+    pub targets: Vec<RouteTarget>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteTarget {
+    pub x: f32,
+    pub y: f32,
+    /// The ground height, or the height of the nearest lane if it is
+    /// not given.
+    #[serde(default)]
+    pub z: Option<f32>,
+    #[serde(default)]
+    pub dwell: f32,
+}
+
+fn default_speed() -> f32 {
+    1.4
+}
+
+impl WalkerRoute {
+    pub fn load(path: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        let routes: Vec<Self> = serde_json::from_str(&text)
+            .with_context(|| format!("invalid walker routes in {}", path.display()))?;
+        for (index, route) in routes.iter().enumerate() {
+            ensure!(
+                !route.targets.is_empty(),
+                "the walker route {index} has no targets"
+            );
+            ensure!(
+                route.speed > 0.0,
+                "the speed of the walker route {index} must be positive"
+            );
+        }
+        Ok(routes)
+    }
+}
+
+struct ScriptedWalker {
+    actor: Actor,
+    route: WalkerRoute,
+    /// The locations of the targets including the ground height.
+    locations: Vec<Vector3<f32>>,
+    next: usize,
+    dwell: f32,
+}
+
+/// The pedestrians walking the routes.
+pub struct ScriptedWalkers {
+    walkers: Vec<ScriptedWalker>,
+    actors: Vec<Actor>,
+}
+
+impl ScriptedWalkers {
+    /// Spawn one pedestrian at the first target of every route.
+    pub fn spawn(world: &mut World, routes: &[WalkerRoute]) -> Result<Self> {
+        let library = world.blueprint_library();
+        let mut default_ids: Vec<_> = library
+            .filter("walker.pedestrian.*")
+            .iter()
+            .map(|blueprint| blueprint.id())
+            .collect();
+        default_ids.sort();
+        let map = world.map();
+
+        let mut walkers = vec![];
+        for (index, route) in routes.iter().enumerate() {
+            let id = match &route.blueprint {
+                Some(id) => id.as_str(),
+                None => match default_ids.first() {
+                    Some(id) => id.as_str(),
+                    None => bail!("no pedestrian blueprint is found"),
+                },
+            };
+            let Some(mut blueprint) = library.find(id) else {
+                bail!("no pedestrian blueprint is named '{id}'");
+            };
+            let _ = blueprint.set_attribute("is_invincible", "false");
+
+            let locations: Vec<_> = route
+                .targets
+                .iter()
+                .map(|target| {
+                    let z = target.z.unwrap_or_else(|| {
+                        map.waypoint_opt(
+                            &Translation3::new(target.x, target.y, 0.0),
+                            true,
+                            LaneType::Any,
+                        )
+                        .map_or(0.0, |waypoint| waypoint.transform().translation.z)
+                    });
+                    Vector3::new(target.x, target.y, z + LIFT)
+                })
+                .collect();
+
+            // Face the second target from the start.
+            let start = locations[0];
+            let heading = locations.get(1).map_or(0.0, |next| {
+                let offset = next - start;
+                offset.y.atan2(offset.x)
+            });
+            let pose = Isometry3::from_parts(
+                Translation3::from(start),
+                UnitQuaternion::from_euler_angles(0.0, 0.0, heading),
+            );
+            let actor = world
+                .spawn_actor(&blueprint, &pose)
+                .with_context(|| format!("unable to spawn the walker of the route {index}"))?;
+            walkers.push(ScriptedWalker {
+                actor: actor.clone(),
+                dwell: route.targets[0].dwell,
+                route: route.clone(),
+                locations,
+                next: 1,
+            });
+        }
+        eprintln!("Spawned {} pedestrians on scripted routes", walkers.len());
+
+        let actors = walkers.iter().map(|walker| walker.actor.clone()).collect();
+        Ok(Self { walkers, actors })
+    }
+
+    pub fn walkers(&self) -> &[Actor] {
+        &self.actors
+    }
+
+    /// Move the pedestrians along their routes by the time step in
+    /// seconds.
+    pub fn update(&mut self, dt: f32) {
+        for walker in &mut self.walkers {
+            if walker.actor.is_dormant() {
+                continue;
+            }
+            if walker.dwell > 0.0 {
+                walker.dwell -= dt;
+                continue;
+            }
+            if walker.next == walker.locations.len() {
+                if !walker.route.repeat {
+                    continue;
+                }
+                walker.next = 0;
+            }
+            let target = &walker.locations[walker.next];
+            if walk_towards(&walker.actor, target, walker.route.speed * dt) {
+                walker.dwell = walker.route.targets[walker.next].dwell;
+                walker.next += 1;
+            }
+        }
+    }
+}
+
+impl Drop for ScriptedWalkers {
+    fn drop(&mut self) {
+        leftover::report(self.walkers.len(), "scripted pedestrians");
+    }
+}
@@ -0,0 +1,323 @@
+//! Pedestrians walking on the sidewalks.
+//!
+//! The client library has no binding of the AI walker controller, so
+//! the pedestrians are moved by the client instead. They follow the
+//! sidewalk lanes of the map and cross the road to the opposite
+//! sidewalk when their sidewalk ends. The walking animation is not
+//! played since the walkers are placed rather than controlled.
+
+use crate::leftover;
+use anyhow::{bail, ensure, Error, Result};
+use carla::{
+    client::{Actor, ActorBase, Map, Waypoint, World},
+    road::LaneType,
+};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+use rand::{
+    rngs::StdRng,
+    seq::{IteratorRandom, SliceRandom},
+    Rng, SeedableRng,
+};
+use rand_distr::{Distribution, Normal};
+use std::{f32::consts::PI, str::FromStr};
+
+/// The number of spawn attempts per pedestrian before giving up.
+const ATTEMPTS_PER_WALKER: usize = 5;
+
+/// The distance in meters from the ego start point within which no
+/// pedestrian is spawned.
+const EGO_CLEARANCE: f32 = 10.0;
+
+/// The height in meters of the pedestrian center above the sidewalk.
+const LIFT: f32 = 1.0;
+
+/// The slowest walking speed in m/s drawn from the speed distribution.
+const MIN_SPEED: f32 = 0.3;
+
+/// The farthest distance in meters searched across the road for the
+/// opposite sidewalk.
+const MAX_CROSSING: usize = 40;
+
+/// The normal distribution of the walking speeds in m/s, written as
+/// `MEAN[,STDDEV]`.
+#[derive(Debug, Clone, Copy)]
+pub struct SpeedDistribution {
+    pub mean: f32,
+    pub stddev: f32,
+}
+
+impl FromStr for SpeedDistribution {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (mean, stddev) = match text.split_once(',') {
+            Some((mean, stddev)) => (mean.trim().parse()?, stddev.trim().parse()?),
+            None => (text.trim().parse()?, 0.0),
+        };
+        ensure!(mean > 0.0, "the mean walking speed must be positive");
+        ensure!(
+            stddev >= 0.0,
+            "the deviation of the walking speed must not be negative"
+        );
+        Ok(Self { mean, stddev })
+    }
+}
+
+/// The crowd settings given on the command line.
+#[derive(Debug, Clone)]
+pub struct CrowdConfig {
+    pub count: usize,
+    /// Spawn the pedestrians within the radius in meters around the
+    /// ego start point, or anywhere on the map if it is `None`.
+    pub radius: Option<f32>,
+    /// The probability that a pedestrian crosses the road at the end
+    /// of its sidewalk instead of turning around.
+    pub cross_factor: f32,
+    pub speed: SpeedDistribution,
+}
+
+impl CrowdConfig {
+    /// The number of pedestrians for the density per hectare over the
+    /// circle of the radius in meters.
+    pub fn count_for_density(density: f32, radius: f32) -> usize {
+        (density * PI * radius.powi(2) / 10_000.0).round() as usize
+    }
+}
+
+enum Motion {
+    /// Walk along the sidewalk lane, in the lane direction if
+    /// `forward` is set.
+    Sidewalk { waypoint: Waypoint, forward: bool },
+    /// Walk straight to the waypoint of the opposite sidewalk.
+    Crossing { target: Waypoint },
+}
+
+struct Walker {
+    actor: Actor,
+    speed: f32,
+    motion: Motion,
+}
+
+/// The pedestrians spawned on the sidewalks.
+pub struct WalkerCrowd {
+    walkers: Vec<Walker>,
+    actors: Vec<Actor>,
+    cross_factor: f32,
+    /// The random choices of the walks, seeded at the spawn.
+    rng: StdRng,
+}
+
+impl WalkerCrowd {
+    /// Spawn up to `config.count` pedestrians at random sidewalk
+    /// locations of the navigation mesh.
+    pub fn spawn(
+        world: &mut World,
+        config: &CrowdConfig,
+        ego_start: &Isometry3<f32>,
+        rng: &mut impl Rng,
+    ) -> Result<Self> {
+        let library = world.blueprint_library().filter("walker.pedestrian.*");
+        let blueprints: Vec<_> = library.iter().collect();
+        ensure!(!blueprints.is_empty(), "no pedestrian blueprint is found");
+        let Ok(speeds) = Normal::new(config.speed.mean, config.speed.stddev) else {
+            bail!("invalid walking speed distribution {:?}", config.speed);
+        };
+        world.set_pedestrians_cross_factor(config.cross_factor);
+
+        let map = world.map();
+        let mut rng = StdRng::seed_from_u64(rng.gen());
+        let mut walkers = vec![];
+        // The locations far from the start point are rejected, so more
+        // attempts are needed within a small radius.
+        let attempts = match config.radius {
+            Some(_) => config.count * ATTEMPTS_PER_WALKER * 10,
+            None => config.count * ATTEMPTS_PER_WALKER,
+        };
+        for _ in 0..attempts {
+            if walkers.len() == config.count {
+                break;
+            }
+            let location = world.random_location_from_navigation();
+            let distance = (location.vector - ego_start.translation.vector).norm();
+            if distance <= EGO_CLEARANCE || config.radius.is_some_and(|radius| distance > radius) {
+                continue;
+            }
+            let Some(waypoint) = map.waypoint_opt(&location, true, LaneType::Sidewalk) else {
+                continue;
+            };
+
+            let forward = rng.gen();
+            let mut blueprint = blueprints.choose(&mut rng).unwrap().clone();
+            let _ = blueprint.set_attribute("is_invincible", "false");
+            // The spawn fails if the location is occupied.
+            let pose = walker_pose(&waypoint.transform(), forward);
+            if let Ok(actor) = world.spawn_actor(&blueprint, &pose) {
+                walkers.push(Walker {
+                    actor,
+                    speed: speeds.sample(&mut rng).max(MIN_SPEED),
+                    motion: Motion::Sidewalk { waypoint, forward },
+                });
+            }
+        }
+        if walkers.len() < config.count {
+            eprintln!(
+                "Spawned {} of {} pedestrians for lack of free locations",
+                walkers.len(),
+                config.count
+            );
+        } else {
+            eprintln!("Spawned {} pedestrians", config.count);
+        }
+
+        let actors = walkers.iter().map(|walker| walker.actor.clone()).collect();
+        Ok(Self {
+            walkers,
+            actors,
+            cross_factor: config.cross_factor,
+            rng,
+        })
+    }
+
+    pub fn walkers(&self) -> &[Actor] {
+        &self.actors
+    }
+
+    /// Move the pedestrians by the time step in seconds.
+    pub fn update(&mut self, map: &Map, dt: f32) {
+        let rng = &mut self.rng;
+        for walker in &mut self.walkers {
+            // The dormant pedestrians far from the vehicle on the large
+            // maps cannot be moved.
+            if !walker.actor.is_alive() || walker.actor.is_dormant() {
+                continue;
+            }
+            let step = walker.speed * dt;
+
+            match &mut walker.motion {
+                Motion::Sidewalk { waypoint, forward } => {
+                    let candidates = if *forward {
+                        waypoint.next(step as f64)
+                    } else {
+                        waypoint.previous(step as f64)
+                    };
+                    if let Some(next) = candidates.iter().choose(rng) {
+                        walker
+                            .actor
+                            .set_transform(&walker_pose(&next.transform(), *forward));
+                        *waypoint = next;
+                        continue;
+                    }
+
+                    // The sidewalk ends at the corner of the block.
+                    let target = (rng.gen::<f32>() < self.cross_factor)
+                        .then(|| opposite_sidewalk(map, waypoint))
+                        .flatten();
+                    match target {
+                        Some(target) => walker.motion = Motion::Crossing { target },
+                        None => *forward = !*forward,
+                    }
+                }
+                Motion::Crossing { target } => {
+                    let target_location = target.transform().translation.vector;
+                    if walk_towards(&walker.actor, &target_location, step) {
+                        walker.motion = Motion::Sidewalk {
+                            waypoint: target.clone(),
+                            forward: rng.gen(),
+                        };
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for WalkerCrowd {
+    fn drop(&mut self) {
+        leftover::report(self.walkers.len(), "pedestrians");
+    }
+}
+
+/// The pose of the pedestrian standing on the sidewalk waypoint and
+/// facing the walking direction.
+fn walker_pose(waypoint: &Isometry3<f32>, forward: bool) -> Isometry3<f32> {
+    let (_, _, yaw) = waypoint.rotation.euler_angles();
+    let yaw = if forward { yaw } else { yaw + PI };
+    let location = waypoint.translation.vector + Vector3::new(0.0, 0.0, LIFT);
+    Isometry3::from_parts(
+        Translation3::from(location),
+        UnitQuaternion::from_euler_angles(0.0, 0.0, yaw),
+    )
+}
+
+/// Move the pedestrian by the step in meters straight towards the
+/// target, keeping its height. It returns `true` once the target is
+/// reached.
+pub fn walk_towards(actor: &Actor, target: &Vector3<f32>, step: f32) -> bool {
+    let location = actor.location().vector;
+    let mut offset = target - location;
+    offset.z = 0.0;
+    let distance = offset.norm();
+    if distance <= step {
+        return true;
+    }
+    let location = location + offset * (step / distance);
+    let yaw = offset.y.atan2(offset.x);
+    actor.set_transform(&Isometry3::from_parts(
+        Translation3::from(location),
+        UnitQuaternion::from_euler_angles(0.0, 0.0, yaw),
+    ));
+    false
+}
+
+/// Search both sides of the sidewalk for the first sidewalk beyond the
+/// driving lanes.
+fn opposite_sidewalk(map: &Map, waypoint: &Waypoint) -> Option<Waypoint> {
+    let pose = waypoint.transform();
+    let origin = pose.translation.vector;
+    let side = pose.rotation * Vector3::y();
+
+    [1.0, -1.0].into_iter().find_map(|sign: f32| {
+        let mut crossed_road = false;
+        (1..=MAX_CROSSING).find_map(|distance| {
+            let point = Translation3::from(origin + side * sign * distance as f32);
+            if map.waypoint_opt(&point, false, LaneType::Driving).is_some() {
+                crossed_road = true;
+                return None;
+            }
+            if !crossed_road {
+                return None;
+            }
+            map.waypoint_opt(&point, false, LaneType::Sidewalk)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_mean_and_the_deviation() {
+        let speed: SpeedDistribution = "1.4, 0.2".parse().unwrap();
+        assert_eq!(speed.mean, 1.4);
+        assert_eq!(speed.stddev, 0.2);
+    }
+
+    #[test]
+    fn deviation_defaults_to_zero() {
+        let speed: SpeedDistribution = " 1.2 ".parse().unwrap();
+        assert_eq!(speed.mean, 1.2);
+        assert_eq!(speed.stddev, 0.0);
+    }
+
+    #[test]
+    fn rejects_a_non_positive_mean() {
+        assert!("0".parse::<SpeedDistribution>().is_err());
+        assert!("-1.0,0.1".parse::<SpeedDistribution>().is_err());
+    }
+
+    #[test]
+    fn rejects_a_negative_deviation() {
+        assert!("1.4,-0.1".parse::<SpeedDistribution>().is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Weather presets and overrides given on the command line.
+
+use anyhow::{bail, Error, Result};
+use carla::{client::World, rpc::WeatherParameters};
+use std::{f32::consts::TAU, str::FromStr};
+
+/// The presets of the simulator. The values are the cloudiness, the
+/// precipitation, the precipitation deposits, the wind intensity, the
+/// sun azimuth and altitude angles, the fog density, distance and
+/// falloff, the wetness, the scattering intensity, the Mie and Rayleigh
+/// scattering scales and the dust storm.
+#[rustfmt::skip]
+const PRESETS: &[(&str, [f32; 14])] = &[
+    ("ClearNoon", [5.0, 0.0, 0.0, 10.0, -1.0, 45.0, 2.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("CloudyNoon", [60.0, 0.0, 0.0, 10.0, -1.0, 45.0, 3.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("WetNoon", [5.0, 0.0, 50.0, 10.0, -1.0, 45.0, 3.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("WetCloudyNoon", [60.0, 0.0, 50.0, 10.0, -1.0, 45.0, 3.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("MidRainyNoon", [60.0, 60.0, 60.0, 60.0, -1.0, 45.0, 3.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("HardRainNoon", [100.0, 100.0, 90.0, 100.0, -1.0, 45.0, 7.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("SoftRainNoon", [20.0, 30.0, 50.0, 30.0, -1.0, 45.0, 3.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("ClearSunset", [5.0, 0.0, 0.0, 10.0, -1.0, 15.0, 2.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("CloudySunset", [60.0, 0.0, 0.0, 10.0, -1.0, 15.0, 3.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("WetSunset", [5.0, 0.0, 50.0, 10.0, -1.0, 15.0, 2.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("WetCloudySunset", [60.0, 0.0, 50.0, 10.0, -1.0, 15.0, 2.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("MidRainSunset", [60.0, 60.0, 60.0, 60.0, -1.0, 15.0, 3.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("HardRainSunset", [100.0, 100.0, 90.0, 100.0, -1.0, 15.0, 7.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("SoftRainSunset", [20.0, 30.0, 50.0, 30.0, -1.0, 15.0, 2.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("ClearNight", [5.0, 0.0, 0.0, 10.0, -1.0, -90.0, 60.0, 75.0, 1.0, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("CloudyNight", [60.0, 0.0, 0.0, 10.0, -1.0, -90.0, 60.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("WetNight", [5.0, 0.0, 50.0, 10.0, -1.0, -90.0, 60.0, 75.0, 1.0, 60.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("WetCloudyNight", [60.0, 0.0, 50.0, 10.0, -1.0, -90.0, 60.0, 0.75, 0.1, 60.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("SoftRainNight", [60.0, 30.0, 50.0, 30.0, -1.0, -90.0, 60.0, 0.75, 0.1, 60.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("MidRainyNight", [80.0, 60.0, 60.0, 60.0, -1.0, -90.0, 60.0, 0.75, 0.1, 80.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("HardRainNight", [100.0, 100.0, 90.0, 100.0, -1.0, -90.0, 100.0, 0.75, 0.1, 100.0, 1.0, 0.03, 0.0331, 0.0]),
+    ("DustStorm", [100.0, 0.0, 0.0, 100.0, -1.0, 45.0, 2.0, 0.75, 0.1, 0.0, 1.0, 0.03, 0.0331, 100.0]),
+];
+
+/// The keys of the overrides in the order of the preset values.
+const KEYS: [&str; 14] = [
+    "cloudiness",
+    "precipitation",
+    "precipitation_deposits",
+    "wind_intensity",
+    "sun_azimuth_angle",
+    "sun_altitude_angle",
+    "fog_density",
+    "fog_distance",
+    "fog_falloff",
+    "wetness",
+    "scattering_intensity",
+    "mie_scattering_scale",
+    "rayleigh_scattering_scale",
+    "dust_storm",
+];
+
+/// A weather preset followed by `key=value` overrides, separated by
+/// commas, e.g. `WetCloudySunset,fog_density=20`. The overrides apply
+/// to the current weather if no preset is given.
+#[derive(Debug, Clone)]
+pub struct WeatherSpec {
+    preset: Option<[f32; 14]>,
+    overrides: Vec<(usize, f32)>,
+}
+
+impl WeatherSpec {
+    /// Set the weather of the world.
+    pub fn apply(&self, world: &mut World) {
+        let mut values = self.preset.unwrap_or_else(|| to_values(&world.weather()));
+        for &(index, value) in &self.overrides {
+            values[index] = value;
+        }
+        world.set_weather(&from_values(values));
+    }
+}
+
+impl FromStr for WeatherSpec {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut preset = None;
+        let mut overrides = vec![];
+
+        for (position, item) in text.split(',').map(str::trim).enumerate() {
+            match item.split_once('=') {
+                Some((key, value)) => {
+                    let Some(index) = KEYS.iter().position(|&name| name == key.trim()) else {
+                        bail!(
+                            "unknown weather parameter '{key}', expect one of {}",
+                            KEYS.join(", ")
+                        );
+                    };
+                    let value: f32 = value.trim().parse()?;
+                    overrides.push((index, value));
+                }
+                None if position == 0 => {
+                    let Some((_, values)) = PRESETS
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(item))
+                    else {
+                        let names: Vec<_> = PRESETS.iter().map(|(name, _)| *name).collect();
+                        bail!(
+                            "unknown weather preset '{item}', expect one of {}",
+                            names.join(", ")
+                        );
+                    };
+                    preset = Some(*values);
+                }
+                None => bail!("expect key=value after the weather preset, but get '{item}'"),
+            }
+        }
+
+        Ok(Self { preset, overrides })
+    }
+}
+
+fn to_values(weather: &WeatherParameters) -> [f32; 14] {
+    [
+        weather.cloudiness,
+        weather.precipitation,
+        weather.precipitation_deposits,
+        weather.wind_intensity,
+        weather.sun_azimuth_angle,
+        weather.sun_altitude_angle,
+        weather.fog_density,
+        weather.fog_distance,
+        weather.fog_falloff,
+        weather.wetness,
+        weather.scattering_intensity,
+        weather.mie_scattering_scale,
+        weather.rayleigh_scattering_scale,
+        weather.dust_storm,
+    ]
+}
+
+fn from_values(values: [f32; 14]) -> WeatherParameters {
+    WeatherParameters {
+        cloudiness: values[0],
+        precipitation: values[1],
+        precipitation_deposits: values[2],
+        wind_intensity: values[3],
+        sun_azimuth_angle: values[4],
+        sun_altitude_angle: values[5],
+        fog_density: values[6],
+        fog_distance: values[7],
+        fog_falloff: values[8],
+        wetness: values[9],
+        scattering_intensity: values[10],
+        mie_scattering_scale: values[11],
+        rayleigh_scattering_scale: values[12],
+        dust_storm: values[13],
+    }
+}
+
+/// The interval in simulation seconds between the weather updates at
+/// the normal speed.
+const UPDATE_INTERVAL: f32 = 0.1;
+
+/// Evolves the sun and a cycle of storms over time, as the
+/// `dynamic_weather.py` example of CARLA does.
+#[derive(Debug, Clone)]
+pub struct DynamicWeather {
+    values: [f32; 14],
+    speed_factor: f32,
+    elapsed: f32,
+    sun_phase: f32,
+    storm: f32,
+    increasing: bool,
+}
+
+impl DynamicWeather {
+    /// Start from the current weather of the world. `speed_factor`
+    /// scales the rate of the changes.
+    pub fn new(world: &World, speed_factor: f32) -> Self {
+        let values = to_values(&world.weather());
+        let precipitation = values[1];
+        Self {
+            values,
+            speed_factor,
+            elapsed: 0.0,
+            sun_phase: 0.0,
+            storm: if precipitation > 0.0 {
+                precipitation
+            } else {
+                -50.0
+            },
+            increasing: true,
+        }
+    }
+
+    /// Advance the weather by the time step in seconds.
+    pub fn update(&mut self, world: &mut World, dt: f32) {
+        self.elapsed += dt;
+        if self.elapsed <= UPDATE_INTERVAL / self.speed_factor {
+            return;
+        }
+        let dt = self.speed_factor * self.elapsed;
+        self.elapsed = 0.0;
+
+        // The sun circles the sky and rises and sets.
+        self.sun_phase = (self.sun_phase + 0.008 * dt) % TAU;
+        let azimuth = (self.values[4] + 0.25 * dt) % 360.0;
+        let altitude = 70.0 * self.sun_phase.sin() - 20.0;
+
+        // The storm builds up to the full rain and clears for a while.
+        let rate = if self.increasing { 1.3 } else { -1.3 };
+        self.storm = (self.storm + rate * dt).clamp(-250.0, 100.0);
+        let clouds = (self.storm + 40.0).clamp(0.0, 90.0);
+        let delay = if self.increasing { -10.0 } else { 90.0 };
+        let wind = if clouds <= 20.0 {
+            5.0
+        } else if clouds >= 70.0 {
+            90.0
+        } else {
+            40.0
+        };
+        if self.storm == -250.0 {
+            self.increasing = true;
+        }
+        if self.storm == 100.0 {
+            self.increasing = false;
+        }
+
+        self.values[0] = clouds;
+        self.values[1] = self.storm.clamp(0.0, 80.0);
+        self.values[2] = (self.storm + delay).clamp(0.0, 85.0);
+        self.values[3] = wind;
+        self.values[4] = azimuth;
+        self.values[5] = altitude;
+        self.values[6] = (self.storm - 10.0).clamp(0.0, 30.0);
+        self.values[9] = (self.storm * 5.0).clamp(0.0, 100.0);
+        world.set_weather(&from_values(self.values));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_preset_in_any_case() {
+        let spec: WeatherSpec = "hardrainnight".parse().unwrap();
+        let (_, values) = PRESETS
+            .iter()
+            .find(|(name, _)| *name == "HardRainNight")
+            .unwrap();
+        assert_eq!(spec.preset, Some(*values));
+        assert!(spec.overrides.is_empty());
+    }
+
+    #[test]
+    fn parses_the_overrides_after_a_preset() {
+        let spec: WeatherSpec = "ClearNoon, fog_density = 40, wetness=80.5".parse().unwrap();
+        assert!(spec.preset.is_some());
+        assert_eq!(spec.overrides, [(6, 40.0), (9, 80.5)]);
+    }
+
+    #[test]
+    fn parses_the_overrides_without_a_preset() {
+        let spec: WeatherSpec = "sun_altitude_angle=-10".parse().unwrap();
+        assert_eq!(spec.preset, None);
+        assert_eq!(spec.overrides, [(5, -10.0)]);
+    }
+
+    #[test]
+    fn rejects_a_preset_after_the_overrides() {
+        assert!("cloudiness=50,ClearNoon".parse::<WeatherSpec>().is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert!("Snowstorm".parse::<WeatherSpec>().is_err());
+        assert!("ClearNoon,snow=10".parse::<WeatherSpec>().is_err());
+    }
+
+    #[test]
+    fn rejects_a_bad_value() {
+        assert!("ClearNoon,cloudiness=lots".parse::<WeatherSpec>().is_err());
+    }
+}
@@ -0,0 +1,266 @@
+mod mpc;
+#[path = "../../automatic-control/src/ego/spec.rs"]
+mod spec;
+
+use crate::{
+    mpc::{Mpc, MpcInput, MpcWeights},
+    spec::VehicleSpec,
+};
+use anyhow::{Context, Result};
+use carla::{
+    client::{ActorBase, Client, Vehicle, Waypoint},
+    rpc::{EpisodeSettings, VehicleAckermannControl},
+};
+use clap::Parser;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector2};
+use std::{
+    f64::consts::PI,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The simulation time step in seconds.
+const FIXED_DELTA_SECONDS: f64 = 0.05;
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+
+    // Connect to the client and retrieve the world object
+    let client = Client::connect(&opts.addr, opts.port, None);
+
+    // Set the world
+    let mut world = match &opts.world {
+        Some(world) => client.load_world(world),
+        None => client.world(),
+    };
+
+    // Set synchronous mode
+    world.apply_settings(
+        &EpisodeSettings {
+            synchronous_mode: true,
+            fixed_delta_seconds: Some(FIXED_DELTA_SECONDS),
+            ..world.settings()
+        },
+        Duration::ZERO,
+    );
+
+    let map = world.map();
+
+    // spawn the vehicle in ...
+    let start_point = Isometry3 {
+        translation: Translation3::new(83.075226, 13.414804, 0.600000),
+        rotation: UnitQuaternion::from_euler_angles(0.0, 0.0, -179.840_79_f32.to_radians()),
+    };
+    eprintln!("Spawn a vehicle at {start_point}");
+
+    // Register a Ctrl-C handler
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let stop = stop.clone();
+
+        ctrlc::set_handler(move || {
+            stop.store(true, Ordering::SeqCst);
+        })
+        .with_context(|| "Error setting Ctrl-C handler")?;
+    }
+
+    // Spawn vehicles
+    let vblu = world
+        .blueprint_library()
+        .find("vehicle.tesla.model3")
+        .unwrap();
+    let vehicle: Vehicle = world.spawn_actor(&vblu, &start_point)?.try_into().unwrap();
+    vehicle.set_autopilot(false);
+
+    let spectator = world.spectator();
+    let target_speed = opts.target_speed as f64 * 10.0 / 36.0;
+
+    let mpc = {
+        let spec = VehicleSpec::from_vehicle(&vehicle);
+        Mpc {
+            horizon: opts.horizon.get(),
+            dt: opts.step_seconds,
+            wheelbase: spec.wheelbase as f64,
+            max_steer: spec.max_steer_angle as f64,
+            max_acceleration: opts.max_acceleration,
+            max_deceleration: opts.max_deceleration,
+            weights: MpcWeights {
+                lateral: opts.q_lateral,
+                heading: opts.q_heading,
+                speed: opts.q_speed,
+                steer: opts.r_steer,
+                acceleration: opts.r_acceleration,
+                steer_rate: opts.r_steer_rate,
+            },
+        }
+    };
+    let mut prev_steer = 0.0;
+
+    while !stop.load(Ordering::SeqCst) {
+        let vehicle_transform = vehicle.transform();
+        let (_, _, vehicle_yaw) = vehicle_transform.rotation.euler_angles();
+        let vehicle_speed = vehicle.velocity().norm() as f64;
+
+        // Get the current waypoint.
+        let Some(curr_waypoint) = map.waypoint(&vehicle_transform.translation) else {
+            vehicle.set_transform(&start_point);
+            prev_steer = 0.0;
+            world.tick();
+            continue;
+        };
+
+        // Set the spectator viewpoint
+        let s_point = vehicle_transform * Translation3::new(-10.0, 0.0, 7.0);
+        spectator.set_transform(&s_point);
+
+        // Sample the lane ahead at the distance travelled in each
+        // prediction step.
+        let step_distance = vehicle_speed.max(1.0) * mpc.dt;
+        let curvatures = lane_curvatures(&curr_waypoint, step_distance, mpc.horizon);
+
+        // Compute the errors with respect to the lane center.
+        let path_transform = curr_waypoint.transform();
+        let (_, _, path_yaw) = path_transform.rotation.euler_angles();
+        let offset = vehicle_transform.translation.vector - path_transform.translation.vector;
+        let right = Vector2::new(-path_yaw.sin(), path_yaw.cos());
+        let lateral_error = Vector2::new(offset.x, offset.y).dot(&right) as f64;
+        let heading_error = normalize_angle((vehicle_yaw - path_yaw) as f64);
+
+        let input = MpcInput {
+            lateral_error,
+            heading_error,
+            speed: vehicle_speed,
+            target_speed,
+            curvatures: &curvatures,
+            prev_steer,
+        };
+
+        let control = match mpc.solve(&input) {
+            Ok(output) => {
+                prev_steer = output.steer;
+                VehicleAckermannControl {
+                    steer: output.steer as f32,
+                    steer_speed: 0.0,
+                    speed: (vehicle_speed + output.acceleration * mpc.dt).max(0.0) as f32,
+                    acceleration: output.acceleration.abs() as f32,
+                    jerk: 0.0,
+                }
+            }
+            Err(err) => {
+                // Stop the vehicle if no feasible plan is found.
+                eprintln!("{err}");
+                VehicleAckermannControl {
+                    steer: prev_steer as f32,
+                    steer_speed: 0.0,
+                    speed: 0.0,
+                    acceleration: opts.max_deceleration as f32,
+                    jerk: 0.0,
+                }
+            }
+        };
+        vehicle.apply_ackermann_control(&control);
+
+        world.tick();
+    }
+
+    // Restore the world settings
+    world.apply_settings(
+        &EpisodeSettings {
+            synchronous_mode: false,
+            fixed_delta_seconds: None,
+            ..world.settings()
+        },
+        Duration::ZERO,
+    );
+
+    Ok(())
+}
+
+/// Compute the curvature of the lane at `count` samples spaced
+/// `step_distance` meters apart, starting from `waypoint`.
+fn lane_curvatures(waypoint: &Waypoint, step_distance: f64, count: usize) -> Vec<f64> {
+    let mut yaws = vec![];
+    let mut curr = waypoint.clone();
+
+    loop {
+        let (_, _, yaw) = curr.transform().rotation.euler_angles();
+        yaws.push(yaw as f64);
+
+        if yaws.len() > count {
+            break;
+        }
+        let Some(next) = curr.next(step_distance).get(0) else {
+            break;
+        };
+        curr = next;
+    }
+
+    yaws.windows(2)
+        .map(|pair| normalize_angle(pair[1] - pair[0]) / step_distance)
+        .collect()
+}
+
+/// Wrap an angle in radians to the range [-π, π).
+fn normalize_angle(angle: f64) -> f64 {
+    (angle + PI).rem_euclid(PI * 2.0) - PI
+}
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(long, default_value = "localhost")]
+    pub addr: String,
+
+    #[clap(long, default_value = "2000")]
+    pub port: u16,
+
+    #[clap(long)]
+    pub world: Option<String>,
+
+    /// The target speed in km/h.
+    #[clap(long, default_value = "20.0")]
+    pub target_speed: f32,
+
+    /// The number of prediction steps.
+    #[clap(long, default_value = "20")]
+    pub horizon: NonZeroUsize,
+
+    /// The duration of each prediction step in seconds.
+    #[clap(long, default_value = "0.1")]
+    pub step_seconds: f64,
+
+    /// The maximum acceleration in m/s².
+    #[clap(long, default_value = "3.0")]
+    pub max_acceleration: f64,
+
+    /// The maximum deceleration in m/s².
+    #[clap(long, default_value = "5.0")]
+    pub max_deceleration: f64,
+
+    /// The weight on the lateral offset.
+    #[clap(long, default_value = "1.0")]
+    pub q_lateral: f64,
+
+    /// The weight on the heading error.
+    #[clap(long, default_value = "2.0")]
+    pub q_heading: f64,
+
+    /// The weight on the speed tracking error.
+    #[clap(long, default_value = "0.5")]
+    pub q_speed: f64,
+
+    /// The weight on the steering angle.
+    #[clap(long, default_value = "0.1")]
+    pub r_steer: f64,
+
+    /// The weight on the acceleration.
+    #[clap(long, default_value = "0.1")]
+    pub r_acceleration: f64,
+
+    /// The weight on the steering change between steps.
+    #[clap(long, default_value = "5.0")]
+    pub r_steer_rate: f64,
+}
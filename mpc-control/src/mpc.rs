@@ -0,0 +1,319 @@
+//! A linear time-varying MPC on the kinematic bicycle model.
+//!
+//! The state is `[e_y, e_ψ, v]`, the lateral offset to the right of
+//! the lane center, the heading error and the speed. The input is
+//! `[δ, a]`, the front wheel angle and the acceleration. The
+//! dynamics are linearized around the lane center with the
+//! curvatures of the upcoming waypoints.
+//!
+//! ```text
+//! e_y[k+1] = e_y[k] + v₀ dt e_ψ[k]
+//! e_ψ[k+1] = e_ψ[k] + v₀ dt / L δ[k] - v₀ dt κ[k]
+//! v[k+1]   = v[k] + dt a[k]
+//! ```
+
+use anyhow::{bail, Result};
+use clarabel::{
+    algebra::CscMatrix,
+    solver::{DefaultSettings, DefaultSolver, IPSolver, SolverStatus, SupportedConeT::*},
+};
+
+const NX: usize = 3;
+const NU: usize = 2;
+
+/// The minimum speed in m/s used to linearize the model, below which
+/// the steering has no effect on the heading.
+const MIN_LINEARIZATION_SPEED: f64 = 1.0;
+
+/// The cost weights of the MPC objective.
+#[derive(Debug, Clone)]
+pub struct MpcWeights {
+    pub lateral: f64,
+    pub heading: f64,
+    pub speed: f64,
+    pub steer: f64,
+    pub acceleration: f64,
+    pub steer_rate: f64,
+}
+
+/// The fixed parameters of the MPC problem.
+#[derive(Debug, Clone)]
+pub struct Mpc {
+    /// The number of prediction steps.
+    pub horizon: usize,
+    /// The duration of a prediction step in seconds.
+    pub dt: f64,
+    /// The vehicle wheelbase in meters.
+    pub wheelbase: f64,
+    /// The maximum front wheel angle in radians.
+    pub max_steer: f64,
+    /// The maximum acceleration in m/s².
+    pub max_acceleration: f64,
+    /// The maximum deceleration in m/s², given as a positive
+    /// number.
+    pub max_deceleration: f64,
+    pub weights: MpcWeights,
+}
+
+/// The per-tick inputs of the MPC problem.
+#[derive(Debug, Clone)]
+pub struct MpcInput<'a> {
+    /// The current lateral offset in meters. Positive values are to
+    /// the right of the lane center.
+    pub lateral_error: f64,
+    /// The current heading error in radians.
+    pub heading_error: f64,
+    /// The current speed in m/s.
+    pub speed: f64,
+    /// The target speed in m/s.
+    pub target_speed: f64,
+    /// The lane curvature in 1/m at each prediction step. Missing
+    /// steps are treated as straight.
+    pub curvatures: &'a [f64],
+    /// The steering angle applied at the previous tick.
+    pub prev_steer: f64,
+}
+
+/// The first control input of the optimal sequence.
+#[derive(Debug, Clone)]
+pub struct MpcOutput {
+    /// The front wheel angle in radians.
+    pub steer: f64,
+    /// The acceleration in m/s².
+    pub acceleration: f64,
+}
+
+impl Mpc {
+    /// Build and solve the QP for the given inputs.
+    pub fn solve(&self, input: &MpcInput) -> Result<MpcOutput> {
+        let Self {
+            horizon: n,
+            dt,
+            wheelbase,
+            max_steer,
+            max_acceleration,
+            max_deceleration,
+            ref weights,
+        } = *self;
+
+        let x_index = |k: usize, i: usize| k * NX + i;
+        let u_index = |k: usize, j: usize| (n + 1) * NX + k * NU + j;
+        let n_vars = (n + 1) * NX + n * NU;
+
+        // Assemble the cost 1/2 zᵀPz + qᵀz. Only the upper triangle
+        // of P is filled.
+        let mut p_mat = Triplets::default();
+        let mut q = vec![0.0; n_vars];
+
+        for k in 1..=n {
+            p_mat.push(x_index(k, 0), x_index(k, 0), 2.0 * weights.lateral);
+            p_mat.push(x_index(k, 1), x_index(k, 1), 2.0 * weights.heading);
+            p_mat.push(x_index(k, 2), x_index(k, 2), 2.0 * weights.speed);
+            q[x_index(k, 2)] -= 2.0 * weights.speed * input.target_speed;
+        }
+
+        for k in 0..n {
+            let steer = u_index(k, 0);
+            p_mat.push(steer, steer, 2.0 * (weights.steer + weights.steer_rate));
+            p_mat.push(u_index(k, 1), u_index(k, 1), 2.0 * weights.acceleration);
+
+            // Penalize the change of steering between steps.
+            if k == 0 {
+                q[steer] -= 2.0 * weights.steer_rate * input.prev_steer;
+            } else {
+                let prev = u_index(k - 1, 0);
+                p_mat.push(prev, prev, 2.0 * weights.steer_rate);
+                p_mat.push(prev, steer, -2.0 * weights.steer_rate);
+            }
+        }
+
+        // Assemble the constraints Az + s = b. The equality rows come
+        // first, followed by the input bounds.
+        let mut a_mat = Triplets::default();
+        let mut b = vec![];
+
+        let initial = [input.lateral_error, input.heading_error, input.speed];
+        for (i, &value) in initial.iter().enumerate() {
+            a_mat.push(b.len(), x_index(0, i), 1.0);
+            b.push(value);
+        }
+
+        let v0 = input.speed.max(MIN_LINEARIZATION_SPEED);
+        for k in 0..n {
+            let curvature = input.curvatures.get(k).copied().unwrap_or(0.0);
+
+            // e_y
+            let row = b.len();
+            a_mat.push(row, x_index(k + 1, 0), 1.0);
+            a_mat.push(row, x_index(k, 0), -1.0);
+            a_mat.push(row, x_index(k, 1), -v0 * dt);
+            b.push(0.0);
+
+            // e_ψ
+            let row = b.len();
+            a_mat.push(row, x_index(k + 1, 1), 1.0);
+            a_mat.push(row, x_index(k, 1), -1.0);
+            a_mat.push(row, u_index(k, 0), -v0 * dt / wheelbase);
+            b.push(-v0 * dt * curvature);
+
+            // v
+            let row = b.len();
+            a_mat.push(row, x_index(k + 1, 2), 1.0);
+            a_mat.push(row, x_index(k, 2), -1.0);
+            a_mat.push(row, u_index(k, 1), -dt);
+            b.push(0.0);
+        }
+        let n_equalities = b.len();
+
+        for k in 0..n {
+            let bounds = [
+                (u_index(k, 0), max_steer, max_steer),
+                (u_index(k, 1), max_acceleration, max_deceleration),
+            ];
+            for (index, upper, lower) in bounds {
+                a_mat.push(b.len(), index, 1.0);
+                b.push(upper);
+                a_mat.push(b.len(), index, -1.0);
+                b.push(lower);
+            }
+        }
+        let n_inequalities = b.len() - n_equalities;
+
+        let p_mat = p_mat.build(n_vars, n_vars);
+        let a_mat = a_mat.build(b.len(), n_vars);
+        let cones = [ZeroConeT(n_equalities), NonnegativeConeT(n_inequalities)];
+        let settings = DefaultSettings {
+            verbose: false,
+            ..Default::default()
+        };
+
+        let mut solver = DefaultSolver::new(&p_mat, &q, &a_mat, &b, &cones, settings)?;
+        solver.solve();
+
+        let solution = &solver.solution;
+        match solution.status {
+            SolverStatus::Solved | SolverStatus::AlmostSolved => {}
+            status => bail!("the MPC solver failed with status {status:?}"),
+        }
+
+        Ok(MpcOutput {
+            steer: solution.x[u_index(0, 0)],
+            acceleration: solution.x[u_index(0, 1)],
+        })
+    }
+}
+
+/// Sparse matrix entries in triplet form. Repeated entries are
+/// summed.
+#[derive(Default)]
+struct Triplets {
+    rows: Vec<usize>,
+    cols: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl Triplets {
+    fn push(&mut self, row: usize, col: usize, value: f64) {
+        self.rows.push(row);
+        self.cols.push(col);
+        self.values.push(value);
+    }
+
+    fn build(self, n_rows: usize, n_cols: usize) -> CscMatrix<f64> {
+        CscMatrix::new_from_triplets(n_rows, n_cols, self.rows, self.cols, self.values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mpc() -> Mpc {
+        Mpc {
+            horizon: 20,
+            dt: 0.1,
+            wheelbase: 2.8,
+            max_steer: 0.5,
+            max_acceleration: 3.0,
+            max_deceleration: 5.0,
+            weights: MpcWeights {
+                lateral: 1.0,
+                heading: 2.0,
+                speed: 0.5,
+                steer: 0.1,
+                acceleration: 0.1,
+                steer_rate: 5.0,
+            },
+        }
+    }
+
+    fn input(curvatures: &[f64]) -> MpcInput<'_> {
+        MpcInput {
+            lateral_error: 0.0,
+            heading_error: 0.0,
+            speed: 5.0,
+            target_speed: 5.0,
+            curvatures,
+            prev_steer: 0.0,
+        }
+    }
+
+    #[test]
+    fn keeps_still_on_target() {
+        let output = mpc().solve(&input(&[])).unwrap();
+        assert!(output.steer.abs() < 1e-4);
+        assert!(output.acceleration.abs() < 1e-4);
+    }
+
+    #[test]
+    fn steers_back_to_the_lane_center() {
+        let output = mpc()
+            .solve(&MpcInput {
+                lateral_error: 1.0,
+                ..input(&[])
+            })
+            .unwrap();
+        assert!(output.steer < 0.0);
+
+        let output = mpc()
+            .solve(&MpcInput {
+                lateral_error: -1.0,
+                ..input(&[])
+            })
+            .unwrap();
+        assert!(output.steer > 0.0);
+    }
+
+    #[test]
+    fn steers_into_the_curve() {
+        let curvatures = [0.05; 20];
+        let output = mpc().solve(&input(&curvatures)).unwrap();
+        assert!(output.steer > 0.0);
+    }
+
+    #[test]
+    fn respects_the_input_bounds() {
+        let mpc = mpc();
+        let output = mpc
+            .solve(&MpcInput {
+                lateral_error: 50.0,
+                speed: 0.0,
+                target_speed: 30.0,
+                ..input(&[])
+            })
+            .unwrap();
+        assert!(output.steer >= -mpc.max_steer - 1e-6);
+        assert!(output.acceleration > 0.0);
+        assert!(output.acceleration <= mpc.max_acceleration + 1e-6);
+
+        let output = mpc
+            .solve(&MpcInput {
+                speed: 30.0,
+                target_speed: 0.0,
+                ..input(&[])
+            })
+            .unwrap();
+        assert!(output.acceleration < 0.0);
+        assert!(output.acceleration >= -mpc.max_deceleration - 1e-6);
+    }
+}
@@ -0,0 +1,375 @@
+use anyhow::{Context, Result};
+use carla::{client::ActorBase, geom::BoundingBox, prelude::*, sensor::data::Image};
+use clap::{Parser, ValueEnum};
+use nalgebra::{Isometry3, Point2, Point3};
+use serde_json::json;
+use show::{
+    camera::{decode_depth, save_calibration, to_rgb_image, CameraConfig},
+    image_format::ImageOpts,
+    noise::PostProcess,
+    pose,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{fs, path::PathBuf};
+
+/// The annotated classes with their semantic tags.
+const CLASSES: &[(&str, u8)] = &[
+    ("pedestrian", 12),
+    ("car", 14),
+    ("truck", 15),
+    ("bus", 16),
+    ("motorcycle", 18),
+    ("bicycle", 19),
+];
+
+/// The number of samples along each side of a box when checking the
+/// occlusion.
+const OCCLUSION_SAMPLES: usize = 8;
+
+/// The tolerance in meters of the depth test.
+const DEPTH_TOLERANCE: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// A single COCO annotation file.
+    Coco,
+    /// A YOLO label file per image.
+    Yolo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Occlusion {
+    /// Compare the box depth with a depth camera.
+    Depth,
+    /// Check the class of the pixels with a semantic camera.
+    Semantic,
+}
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    #[clap(flatten)]
+    pub postprocess: PostProcess,
+
+    #[clap(flatten)]
+    pub image: ImageOpts,
+
+    /// The directory to write the images and the labels.
+    #[clap(short = 'o', long, default_value = "bbox_2d")]
+    pub output_dir: PathBuf,
+
+    /// The format of the labels.
+    #[clap(long, value_enum, default_value = "coco")]
+    pub format: Format,
+
+    /// The camera used to filter out occluded objects.
+    #[clap(long, value_enum, default_value = "depth")]
+    pub occlusion: Occlusion,
+
+    /// The minimum visible fraction of a box to be labeled.
+    #[clap(long, default_value = "0.2")]
+    pub min_visibility: f32,
+
+    /// The maximum distance in meters of the labeled objects.
+    #[clap(long, default_value = "50.0")]
+    pub max_distance: f32,
+
+    /// The minimum width and height in pixels of a box.
+    #[clap(long, default_value = "4.0")]
+    pub min_size: f32,
+
+    /// The image width in pixels.
+    #[clap(long, default_value = "800")]
+    pub width: u32,
+
+    /// The image height in pixels.
+    #[clap(long, default_value = "600")]
+    pub height: u32,
+
+    /// The horizontal field of view in degrees.
+    #[clap(long, default_value = "90.0")]
+    pub fov: f32,
+}
+
+/// A box projected onto the image.
+struct Projection {
+    min: Point2<f32>,
+    max: Point2<f32>,
+    /// The depth in meters of the nearest corner.
+    near: f32,
+    /// The depth in meters of the farthest corner.
+    far: f32,
+}
+
+/// A labeled box in pixels.
+struct Label {
+    class: usize,
+    min: Point2<f32>,
+    max: Point2<f32>,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    opts.image.validate()?;
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+    let ego = sim.ego.clone();
+
+    let config = CameraConfig {
+        name: "front".to_string(),
+        blueprint: "sensor.camera.rgb".to_string(),
+        location: [1.5, 0.0, 2.4],
+        rotation: [0.0; 3],
+        width: opts.width,
+        height: opts.height,
+        fov: opts.fov,
+        postprocess: opts.postprocess.clone(),
+    };
+    let aux_config = CameraConfig {
+        name: "occlusion".to_string(),
+        blueprint: match opts.occlusion {
+            Occlusion::Depth => "sensor.camera.depth",
+            Occlusion::Semantic => "sensor.camera.semantic_segmentation",
+        }
+        .to_string(),
+        postprocess: PostProcess::default(),
+        ..config.clone()
+    };
+    let camera = config.spawn(&mut sim.world, &ego)?;
+    let aux_camera = aux_config.spawn(&mut sim.world, &ego)?;
+    save_calibration(
+        opts.output_dir.join("calibration.json"),
+        std::slice::from_ref(&config),
+    )?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let images = sync.register("camera", &camera, |data| {
+        let pose = pose::from_carla(&data.sensor_transform());
+        let image: Image = data.try_into().unwrap();
+        (pose, image)
+    });
+    let aux_images = sync.register("occlusion camera", &aux_camera, |data| {
+        Image::try_from(data).unwrap()
+    });
+
+    let mut coco_images = vec![];
+    let mut coco_annotations = vec![];
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let (pose, image) = synced.take(&images);
+        let aux = synced.take(&aux_images);
+
+        let ego_location = sim.ego.transform().translation;
+        let depth = (opts.occlusion == Occlusion::Depth).then(|| decode_depth(&aux));
+        let mut labels = vec![];
+
+        for (class, &(_, tag)) in CLASSES.iter().enumerate() {
+            for bbox in sim.world.level_bounding_boxes(tag).iter() {
+                // Skip the box of the ego vehicle.
+                if bbox.contains(&ego_location, &Isometry3::identity()) {
+                    continue;
+                }
+                let Some(Projection {
+                    min,
+                    max,
+                    near,
+                    far,
+                }) = project_box(&config, &pose, &bbox, opts.max_distance)
+                else {
+                    continue;
+                };
+                if max.x - min.x < opts.min_size || max.y - min.y < opts.min_size {
+                    continue;
+                }
+
+                let visibility = match &depth {
+                    Some(depth) => visible_fraction(&config, &min, &max, |index| {
+                        (near - DEPTH_TOLERANCE..=far + DEPTH_TOLERANCE).contains(&depth[index])
+                    }),
+                    None => {
+                        let pixels = aux.as_slice();
+                        visible_fraction(&config, &min, &max, |index| pixels[index].r == tag)
+                    }
+                };
+                if visibility >= opts.min_visibility {
+                    labels.push(Label { class, min, max });
+                }
+            }
+        }
+
+        let image_name = opts.image.file_name(&format!("{frame:06}"));
+        let path = opts.output_dir.join(&image_name);
+        let image_opts = opts.image.clone();
+        writer.submit(move || {
+            image_opts
+                .save(&to_rgb_image(&image), &path)
+                .with_context(|| format!("unable to write {}", path.display()))
+        })?;
+
+        match opts.format {
+            Format::Yolo => {
+                let (width, height) = (opts.width as f32, opts.height as f32);
+                let lines: Vec<String> = labels
+                    .iter()
+                    .map(|Label { class, min, max }| {
+                        let center = Point2::from((min.coords + max.coords) / 2.0);
+                        format!(
+                            "{class} {:.6} {:.6} {:.6} {:.6}\n",
+                            center.x / width,
+                            center.y / height,
+                            (max.x - min.x) / width,
+                            (max.y - min.y) / height
+                        )
+                    })
+                    .collect();
+                let path = opts.output_dir.join(format!("{frame:06}.txt"));
+                writer.submit(move || {
+                    fs::write(&path, lines.concat())
+                        .with_context(|| format!("unable to write {}", path.display()))
+                })?;
+            }
+            Format::Coco => {
+                coco_images.push(json!({
+                    "id": frame,
+                    "file_name": image_name,
+                    "width": opts.width,
+                    "height": opts.height,
+                }));
+                for Label { class, min, max } in &labels {
+                    let (width, height) = (max.x - min.x, max.y - min.y);
+                    coco_annotations.push(json!({
+                        "id": coco_annotations.len() + 1,
+                        "image_id": frame,
+                        "category_id": class + 1,
+                        "bbox": [min.x, min.y, width, height],
+                        "area": width * height,
+                        "iscrowd": 0,
+                    }));
+                }
+            }
+        }
+
+        println!("frame {frame}: {} boxes", labels.len());
+    }
+
+    camera.stop();
+    aux_camera.stop();
+    writer.finish()?;
+
+    match opts.format {
+        Format::Yolo => {
+            let names: Vec<&str> = CLASSES.iter().map(|(name, _)| *name).collect();
+            let path = opts.output_dir.join("classes.txt");
+            fs::write(&path, names.join("\n") + "\n")
+                .with_context(|| format!("unable to write {}", path.display()))?;
+        }
+        Format::Coco => {
+            let categories: Vec<_> = CLASSES
+                .iter()
+                .enumerate()
+                .map(|(class, (name, _))| json!({ "id": class + 1, "name": name }))
+                .collect();
+            let coco = json!({
+                "images": coco_images,
+                "annotations": coco_annotations,
+                "categories": categories,
+            });
+            let path = opts.output_dir.join("annotations.json");
+            fs::write(&path, serde_json::to_string_pretty(&coco)?)
+                .with_context(|| format!("unable to write {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Project the box in the world frame onto the image and clip it. The
+/// level bounding boxes include the spawned actors. It returns
+/// `None` if the box is too far, behind the camera or out of the
+/// image.
+fn project_box(
+    config: &CameraConfig,
+    camera_pose: &Isometry3<f32>,
+    bbox: &BoundingBox<f32>,
+    max_distance: f32,
+) -> Option<Projection> {
+    let to_camera = camera_pose.inverse();
+    let center = to_camera * Point3::from(bbox.transform.translation.vector);
+    if center.coords.norm() > max_distance {
+        return None;
+    }
+
+    let corners: Vec<Point3<f32>> = bbox
+        .local_vertices()
+        .iter()
+        .map(|vertex| to_camera * Point3::from(vertex.vector))
+        .collect();
+    let pixels: Vec<Point2<f32>> = corners
+        .iter()
+        .map(|corner| config.project(corner))
+        .collect::<Option<_>>()?;
+
+    let mut min = Point2::new(f32::INFINITY, f32::INFINITY);
+    let mut max = Point2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for pixel in &pixels {
+        min = min.inf(pixel);
+        max = max.sup(pixel);
+    }
+
+    // Clip the box to the image
+    let min = min.sup(&Point2::origin());
+    let max = max.inf(&Point2::new(config.width as f32, config.height as f32));
+    if min.x >= max.x || min.y >= max.y {
+        return None;
+    }
+
+    let depths = corners.iter().map(|corner| corner.x);
+    Some(Projection {
+        min,
+        max,
+        near: depths.clone().fold(f32::INFINITY, f32::min),
+        far: depths.fold(f32::NEG_INFINITY, f32::max),
+    })
+}
+
+/// The fraction of the sampled pixels within the box that belong to
+/// the object.
+fn visible_fraction(
+    config: &CameraConfig,
+    min: &Point2<f32>,
+    max: &Point2<f32>,
+    is_object: impl Fn(usize) -> bool,
+) -> f32 {
+    let samples = OCCLUSION_SAMPLES;
+    let mut visible = 0;
+    for row in 0..samples {
+        for col in 0..samples {
+            let x = min.x + (max.x - min.x) * (col as f32 + 0.5) / samples as f32;
+            let y = min.y + (max.y - min.y) * (row as f32 + 0.5) / samples as f32;
+            let x = (x as u32).min(config.width - 1);
+            let y = (y as u32).min(config.height - 1);
+            if is_object((y * config.width + x) as usize) {
+                visible += 1;
+            }
+        }
+    }
+    visible as f32 / (samples * samples) as f32
+}
@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use carla::{client::ActorBase, rpc::ActorId};
+use clap::Parser;
+use nalgebra::Isometry3;
+use serde::Serialize;
+use show::{
+    pose, semantic,
+    sim::{Sim, SimOpts},
+};
+use std::{fs, path::PathBuf};
+
+/// The classes of the dynamic objects.
+const CLASSES: &[&str] = &[
+    "pedestrian",
+    "rider",
+    "car",
+    "truck",
+    "bus",
+    "train",
+    "motorcycle",
+    "bicycle",
+];
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    /// The directory to write the boxes.
+    #[clap(short = 'o', long, default_value = "bbox_3d")]
+    pub output_dir: PathBuf,
+
+    /// The maximum distance in meters of the exported objects from the
+    /// ego vehicle.
+    #[clap(long, default_value = "50.0")]
+    pub radius: f32,
+
+    /// Also export the parked vehicles and other objects placed in the
+    /// map, which are not actors.
+    #[clap(long)]
+    pub include_static: bool,
+}
+
+#[derive(Serialize)]
+struct FrameBoxes {
+    frame: usize,
+    timestamp: f64,
+    /// The pose of the ego vehicle in the world frame, which converts
+    /// the boxes back to the world frame.
+    ego_pose: Pose,
+    objects: Vec<Object>,
+}
+
+/// An oriented box in the geometric ego frame.
+#[derive(Serialize)]
+struct Object {
+    class: &'static str,
+    /// The actor inside the box, or `None` for the objects placed in
+    /// the map.
+    actor_id: Option<ActorId>,
+    type_id: Option<String>,
+    pose: Pose,
+    /// The half lengths in meters along the box axes.
+    extent: [f32; 3],
+}
+
+#[derive(Serialize)]
+struct Pose {
+    /// The location in meters.
+    location: [f32; 3],
+    /// The CARLA roll, pitch and yaw in degrees.
+    rotation: [f32; 3],
+}
+
+impl From<&Isometry3<f32>> for Pose {
+    /// Describe a geometric pose.
+    fn from(pose: &Isometry3<f32>) -> Self {
+        let location = pose.translation.vector;
+        Self {
+            location: [location.x, location.y, location.z],
+            rotation: pose::carla_angles(&pose.rotation),
+        }
+    }
+}
+
+/// The geometric pose in the ego frame of a world pose, where both
+/// poses are encoded by carla-rust.
+fn ego_frame(ego_pose: &Isometry3<f32>, world_pose: &Isometry3<f32>) -> Isometry3<f32> {
+    pose::from_carla(ego_pose).inverse() * pose::from_carla(world_pose)
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    while sim.is_running() {
+        let frame = sim.tick();
+        let timestamp = sim.world.snapshot().timestamp().elapsed_seconds;
+        let ego_pose = sim.ego.transform();
+
+        // The boxes do not carry the actor ids, which are recovered
+        // from the vehicles and walkers inside.
+        let actors: Vec<_> = sim
+            .world
+            .actors()
+            .iter()
+            .filter(|actor| {
+                let type_id = actor.type_id();
+                type_id.starts_with("vehicle.") || type_id.starts_with("walker.")
+            })
+            .collect();
+
+        let mut objects = vec![];
+        for &class in CLASSES {
+            let tag = semantic::tag(class).unwrap();
+            for bbox in sim.world.level_bounding_boxes(tag).iter() {
+                let pose = ego_frame(&ego_pose, &bbox.transform);
+                if pose.translation.vector.norm() > opts.radius {
+                    continue;
+                }
+                let actor = actors
+                    .iter()
+                    .find(|actor| bbox.contains(&actor.location(), &Isometry3::identity()));
+                match actor {
+                    Some(actor) if actor.id() == sim.ego.id() => continue,
+                    None if !opts.include_static => continue,
+                    _ => {}
+                }
+
+                objects.push(Object {
+                    class,
+                    actor_id: actor.map(|actor| actor.id()),
+                    type_id: actor.map(|actor| actor.type_id()),
+                    pose: Pose::from(&pose),
+                    extent: bbox.extent.into(),
+                });
+            }
+        }
+
+        let count = objects.len();
+        let boxes = FrameBoxes {
+            frame,
+            timestamp,
+            ego_pose: Pose::from(&pose::from_carla(&ego_pose)),
+            objects,
+        };
+        let path = opts.output_dir.join(format!("{frame:06}.json"));
+        fs::write(&path, serde_json::to_string_pretty(&boxes)?)
+            .with_context(|| format!("unable to write {}", path.display()))?;
+
+        println!("frame {frame}: {count} objects");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{Translation3, UnitQuaternion, Vector3};
+
+    /// A pose as carla-rust encodes the CARLA location and angles.
+    fn carla_pose(location: [f32; 3], [roll, pitch, yaw]: [f32; 3]) -> Isometry3<f32> {
+        Isometry3::from_parts(
+            Translation3::from(Vector3::from(location)),
+            UnitQuaternion::from_euler_angles(
+                roll.to_radians(),
+                pitch.to_radians(),
+                yaw.to_radians(),
+            ),
+        )
+    }
+
+    #[test]
+    fn box_ahead_of_pitched_ego_is_on_its_axis() {
+        // The ego climbs a slope of 10°, and the box sits on the slope
+        // 10 m ahead.
+        let pitch = 10_f32.to_radians();
+        let ego_pose = carla_pose([0.0, 0.0, 0.0], [0.0, 10.0, 0.0]);
+        let box_pose = carla_pose(
+            [10.0 * pitch.cos(), 0.0, 10.0 * pitch.sin()],
+            [0.0, 10.0, 0.0],
+        );
+
+        let pose = Pose::from(&ego_frame(&ego_pose, &box_pose));
+        let expected = [10.0, 0.0, 0.0];
+        for (lhs, rhs) in pose.location.iter().zip(&expected) {
+            assert!((lhs - rhs).abs() < 1e-4);
+        }
+        for angle in pose.rotation {
+            assert!(angle.abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn level_box_is_pitched_down_from_climbing_ego() {
+        let ego_pose = carla_pose([0.0, 0.0, 0.0], [0.0, 10.0, 0.0]);
+        let box_pose = carla_pose([10.0, 0.0, 0.0], [0.0, 0.0, 0.0]);
+
+        let pose = Pose::from(&ego_frame(&ego_pose, &box_pose));
+        // The box lies below the nose of the ego.
+        assert!(pose.location[2] < 0.0);
+        assert!((pose.rotation[1] + 10.0).abs() < 1e-3);
+    }
+}
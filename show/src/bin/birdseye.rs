@@ -0,0 +1,158 @@
+//! Draw the map, the traffic lights and the actors in a window from
+//! the client side, like `no_rendering_mode.py` of the CARLA Python
+//! examples. The server renders nothing while the window is open.
+
+use anyhow::{ensure, Context, Result};
+use carla::{
+    client::{ActorBase, TrafficLight},
+    rpc::{EpisodeSettings, TrafficLightState},
+};
+use clap::Parser;
+use image::Rgb;
+use minifb::{Key, Window, WindowOptions};
+use nalgebra::{Point2, Point3};
+use show::{
+    sim::{Sim, SimOpts},
+    topdown::{Canvas, RoadMap},
+};
+use std::time::Duration;
+
+/// The half length and half width in meters of the vehicle markers.
+const VEHICLE_EXTENT: [f32; 2] = [2.4, 1.0];
+
+/// The radius in meters of the walker markers.
+const WALKER_RADIUS: f32 = 0.5;
+
+/// The radius in meters of the traffic light markers.
+const LIGHT_RADIUS: f32 = 1.5;
+
+const EGO_COLOR: Rgb<u8> = Rgb([220, 30, 30]);
+const VEHICLE_COLOR: Rgb<u8> = Rgb([30, 30, 220]);
+const WALKER_COLOR: Rgb<u8> = Rgb([230, 130, 0]);
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    /// The width and height in pixels of the window.
+    #[clap(long, default_value = "800")]
+    pub size: usize,
+
+    /// Center the view on the ego vehicle instead of showing the whole
+    /// map.
+    #[clap(long)]
+    pub follow: bool,
+
+    /// The distance in meters from the ego vehicle to the window
+    /// borders when following.
+    #[clap(long, default_value = "50.0")]
+    pub view_range: f32,
+
+    /// The distance in meters between the sampled waypoints.
+    #[clap(long, default_value = "2.0")]
+    pub waypoint_distance: f64,
+
+    /// The space in meters around the roads.
+    #[clap(long, default_value = "10.0")]
+    pub margin: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    ensure!(opts.size > 0, "--size must be positive");
+    ensure!(opts.view_range > 0.0, "--view-range must be positive");
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    // The original settings are restored by the simulation on drop.
+    let settings = sim.world.settings();
+    sim.world.apply_settings(
+        &EpisodeSettings {
+            no_rendering_mode: true,
+            ..settings
+        },
+        Duration::ZERO,
+    );
+
+    // Draw the roads once and copy them to the view on each frame.
+    let road_map = RoadMap::new(&sim.world.map(), opts.waypoint_distance)?;
+    let (min, max) = road_map.bounds();
+    let size = opts.size;
+    let scale = if opts.follow {
+        size as f32 / (2.0 * opts.view_range)
+    } else {
+        let extent = (max - min).add_scalar(2.0 * opts.margin);
+        size as f32 / extent.max()
+    };
+    let mut background = Canvas::fit(min, max, opts.margin, scale)?;
+    road_map.draw(&mut background);
+    let map_center = nalgebra::center(&min, &max);
+
+    let mut window = Window::new("birdseye", size, size, WindowOptions::default())
+        .with_context(|| "unable to open the window")?;
+    let mut buffer = vec![0u32; size * size];
+    let ego_id = sim.ego.id();
+
+    while sim.is_running() && window.is_open() && !window.is_key_down(Key::Escape) {
+        let frame = sim.tick();
+
+        let center = if opts.follow {
+            let location = sim.ego.location().vector;
+            Point2::new(location.x, location.y)
+        } else {
+            map_center
+        };
+        let mut view = Canvas::centered(center, size as u32, size as u32, scale);
+        view.overlay(&background);
+
+        let (mut vehicles, mut walkers) = (0, 0);
+        for actor in sim.world.actors().iter() {
+            let type_id = actor.type_id();
+            let transform = actor.transform();
+            let location = Point2::new(transform.translation.x, transform.translation.y);
+
+            if type_id.starts_with("vehicle.") {
+                let [half_length, half_width] = VEHICLE_EXTENT;
+                let corners: Vec<_> = [(1.0, 1.0), (1.0, -1.0), (-1.0, -1.0), (-1.0, 1.0)]
+                    .into_iter()
+                    .map(|(x, y)| {
+                        let corner = transform * Point3::new(x * half_length, y * half_width, 0.0);
+                        Point2::new(corner.x, corner.y)
+                    })
+                    .collect();
+                let color = if actor.id() == ego_id {
+                    EGO_COLOR
+                } else {
+                    VEHICLE_COLOR
+                };
+                view.polygon(&corners, color);
+                vehicles += 1;
+            } else if type_id.starts_with("walker.") {
+                view.disc(&location, (WALKER_RADIUS * scale).max(2.0), WALKER_COLOR);
+                walkers += 1;
+            } else if let Ok(light) = TrafficLight::try_from(actor) {
+                let color = match light.state() {
+                    TrafficLightState::Red => Rgb([230, 0, 0]),
+                    TrafficLightState::Yellow => Rgb([230, 200, 0]),
+                    TrafficLightState::Green => Rgb([0, 180, 0]),
+                    _ => Rgb([100, 100, 100]),
+                };
+                view.disc(&location, (LIGHT_RADIUS * scale).max(3.0), color);
+            }
+        }
+
+        for (pixel, color) in buffer.iter_mut().zip(view.image.pixels()) {
+            let [r, g, b] = color.0;
+            *pixel = u32::from_be_bytes([0, r, g, b]);
+        }
+        window
+            .update_with_buffer(&buffer, size, size)
+            .with_context(|| "unable to update the window")?;
+        window.set_title(&format!(
+            "birdseye - frame {frame}: {vehicles} vehicles, {walkers} walkers"
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use carla::sensor::data::Image;
+use clap::Parser;
+use show::{
+    camera::{save_calibration, to_rgb_image, CameraConfig},
+    image_format::ImageOpts,
+    noise::PostProcess,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{fs, path::PathBuf};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    #[clap(flatten)]
+    pub postprocess: PostProcess,
+
+    #[clap(flatten)]
+    pub image: ImageOpts,
+
+    /// The directory to write the images.
+    #[clap(short = 'o', long, default_value = "camera")]
+    pub output_dir: PathBuf,
+
+    /// The image width in pixels.
+    #[clap(long, default_value = "800")]
+    pub width: u32,
+
+    /// The image height in pixels.
+    #[clap(long, default_value = "600")]
+    pub height: u32,
+
+    /// The horizontal field of view in degrees.
+    #[clap(long, default_value = "90.0")]
+    pub fov: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    opts.image.validate()?;
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    // Mount the camera above the windshield
+    let config = CameraConfig {
+        name: "front".to_string(),
+        blueprint: "sensor.camera.rgb".to_string(),
+        location: [1.5, 0.0, 2.4],
+        rotation: [0.0; 3],
+        width: opts.width,
+        height: opts.height,
+        fov: opts.fov,
+        postprocess: opts.postprocess.clone(),
+    };
+    let ego = sim.ego.clone();
+    let camera = config.spawn(&mut sim.world, &ego)?;
+    save_calibration(opts.output_dir.join("calibration.json"), &[config])?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let camera_data = sync.register("camera", &camera, |data| Image::try_from(data).unwrap());
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+
+        // Wait for the image of the current frame
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let image = synced.take(&camera_data);
+
+        let path = opts.image.path(&opts.output_dir, &format!("{frame:06}"));
+        let image_opts = opts.image.clone();
+        writer.submit(move || {
+            image_opts
+                .save(&to_rgb_image(&image), &path)
+                .with_context(|| format!("unable to write {}", path.display()))?;
+            println!("saved {}", path.display());
+            Ok(())
+        })?;
+    }
+
+    camera.stop();
+    writer.finish()?;
+    Ok(())
+}
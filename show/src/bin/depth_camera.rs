@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use carla::sensor::data::Image;
+use clap::Parser;
+use image::GrayImage;
+use show::{
+    camera::{decode_depth, save_calibration, CameraConfig},
+    noise::PostProcess,
+    npy,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{fs, path::PathBuf};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    /// The directory to write the depth images and buffers.
+    #[clap(short = 'o', long, default_value = "depth")]
+    pub output_dir: PathBuf,
+
+    /// The image width in pixels.
+    #[clap(long, default_value = "800")]
+    pub width: u32,
+
+    /// The image height in pixels.
+    #[clap(long, default_value = "600")]
+    pub height: u32,
+
+    /// The horizontal field of view in degrees.
+    #[clap(long, default_value = "90.0")]
+    pub fov: f32,
+
+    /// The depth in meters mapped to white in the visualization.
+    #[clap(long, default_value = "100.0")]
+    pub max_depth: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let config = CameraConfig {
+        name: "front".to_string(),
+        blueprint: "sensor.camera.depth".to_string(),
+        location: [1.5, 0.0, 2.4],
+        rotation: [0.0; 3],
+        width: opts.width,
+        height: opts.height,
+        fov: opts.fov,
+        postprocess: PostProcess::default(),
+    };
+    let ego = sim.ego.clone();
+    let camera = config.spawn(&mut sim.world, &ego)?;
+    save_calibration(opts.output_dir.join("calibration.json"), &[config])?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let camera_data = sync.register("camera", &camera, |data| Image::try_from(data).unwrap());
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let image = synced.take(&camera_data);
+
+        let (width, height) = (image.width(), image.height());
+        let depth = decode_depth(&image);
+
+        let center = depth[height / 2 * width + width / 2];
+        let output_dir = opts.output_dir.clone();
+        let max_depth = opts.max_depth;
+        writer.submit(move || {
+            // Write the raw depth in meters
+            let npy_path = output_dir.join(format!("{frame:06}.npy"));
+            npy::write_f32(&npy_path, &[height, width], &depth)?;
+
+            // Write the visualization with the near objects in black
+            let pixels = depth
+                .iter()
+                .map(|&meters| ((meters / max_depth).min(1.0) * 255.0) as u8)
+                .collect();
+            let png_path = output_dir.join(format!("{frame:06}.png"));
+            GrayImage::from_raw(width as u32, height as u32, pixels)
+                .unwrap()
+                .save(&png_path)
+                .with_context(|| format!("unable to write {}", png_path.display()))?;
+
+            println!("frame {frame}: center depth {center:.2} m");
+            Ok(())
+        })?;
+    }
+
+    camera.stop();
+    writer.finish()?;
+    Ok(())
+}
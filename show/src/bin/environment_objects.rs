@@ -0,0 +1,121 @@
+//! Query the environment objects of the map by semantic class, print
+//! their bounding boxes, and disable or enable selected objects.
+//!
+//! The environment objects are the buildings, poles, signs and other
+//! meshes placed in the map, which are not actors. The environment
+//! object list of the carla crate exposes neither the ids nor the
+//! names of the objects, and its `get` rejects every index, so only
+//! its length is used. The boxes come from the level bounding boxes of
+//! the same classes instead. The ids taken by `--disable` and
+//! `--enable` are the ones reported by `World.get_environment_objects`
+//! of the Python API.
+
+use anyhow::{ensure, Context, Result};
+use carla::{client::Client, prelude::*};
+use clap::Parser;
+use show::semantic;
+use std::time::Duration;
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(long, default_value = "localhost")]
+    pub addr: String,
+
+    #[clap(long, default_value = "2000")]
+    pub port: u16,
+
+    /// Load this map first instead of querying the current one.
+    #[clap(long)]
+    pub world: Option<String>,
+
+    /// The comma-separated semantic classes to query, e.g. `building`,
+    /// `pole` or `traffic_sign`.
+    #[clap(
+        long,
+        value_delimiter = ',',
+        default_value = "building,pole,traffic_sign"
+    )]
+    pub classes: Vec<String>,
+
+    /// Only print the boxes within this distance in meters from the
+    /// spectator.
+    #[clap(long)]
+    pub radius: Option<f32>,
+
+    /// The comma-separated ids of the environment objects to hide.
+    #[clap(long, value_delimiter = ',')]
+    pub disable: Vec<u64>,
+
+    /// The comma-separated ids of the environment objects to show
+    /// again.
+    #[clap(long, value_delimiter = ',')]
+    pub enable: Vec<u64>,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let tags: Vec<(&str, u8)> = opts
+        .classes
+        .iter()
+        .map(|name| {
+            semantic::tag(name)
+                .map(|tag| (name.as_str(), tag))
+                .with_context(|| format!("unknown semantic class '{name}'"))
+        })
+        .collect::<Result<_>>()?;
+    if let Some(radius) = opts.radius {
+        ensure!(radius > 0.0, "the radius must be positive");
+    }
+
+    let mut client = Client::connect(&opts.addr, opts.port, None);
+    client.set_timeout(Duration::from_secs(10));
+    let world = match &opts.world {
+        Some(world) => client.load_world(world),
+        None => client.world(),
+    };
+    let center = world.spectator().location().vector;
+
+    for (name, tag) in tags {
+        let objects = world.environment_objects(tag);
+        let boxes = world.level_bounding_boxes(tag);
+        println!(
+            "{name}: {} environment objects, {} bounding boxes",
+            objects.len(),
+            boxes.len()
+        );
+
+        for (index, bbox) in boxes.iter().enumerate() {
+            let location = bbox.transform.translation.vector;
+            let distance = (location - center).norm();
+            if opts.radius.is_some_and(|radius| distance > radius) {
+                continue;
+            }
+            let (_, _, yaw) = bbox.transform.rotation.euler_angles();
+            let extent = bbox.extent;
+            println!(
+                "  {index:>5}: location ({:.1}, {:.1}, {:.1}) m, \
+                 extent ({:.1}, {:.1}, {:.1}) m, yaw {:.0}°, {distance:.0} m away",
+                location.x,
+                location.y,
+                location.z,
+                extent.x,
+                extent.y,
+                extent.z,
+                yaw.to_degrees()
+            );
+        }
+    }
+
+    // The objects stay hidden until they are enabled or the map is
+    // reloaded.
+    if !opts.disable.is_empty() {
+        world.enable_environment_objects(&opts.disable, false);
+        println!("disabled {} environment objects", opts.disable.len());
+    }
+    if !opts.enable.is_empty() {
+        world.enable_environment_objects(&opts.enable, true);
+        println!("enabled {} environment objects", opts.enable.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use carla::{client::Sensor, prelude::*, sensor::data::GnssMeasurement};
+use clap::Parser;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use show::{
+    noise::GnssNoise,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub gnss_noise: GnssNoise,
+
+    /// The path prefix of the output files. The track is written to
+    /// PREFIX.gpx and PREFIX.csv.
+    #[clap(short = 'o', long, default_value = "track")]
+    pub output: PathBuf,
+}
+
+/// A GNSS fix at a simulation step.
+struct Fix {
+    timestamp: f64,
+    latitude: f64,
+    longitude: f64,
+    altitude: f64,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let gpx_path = opts.output.with_extension("gpx");
+    let csv_path = opts.output.with_extension("csv");
+    let mut gpx = GpxWriter::create(&gpx_path)?;
+    let mut csv = create(&csv_path)?;
+    writeln!(csv, "frame,timestamp,latitude,longitude,altitude")?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let pose = Isometry3::from_parts(Translation3::new(0.0, 0.0, 1.5), UnitQuaternion::identity());
+    let ego = sim.ego.clone();
+    let builder = sim.world.actor_builder("sensor.other.gnss")?;
+    let gnss: Sensor = opts
+        .gnss_noise
+        .apply(builder)?
+        .spawn_sensor_opt(&pose, Some(&ego), None)?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let gnss_data = sync.register("gnss", &gnss, move |data| {
+        let timestamp = data.timestamp();
+        let measure: GnssMeasurement = data.try_into().unwrap();
+        Fix {
+            timestamp,
+            latitude: measure.latitude(),
+            longitude: measure.longitude(),
+            altitude: measure.attitude(),
+        }
+    });
+
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let fix = synced.take(&gnss_data);
+        let Fix {
+            timestamp,
+            latitude,
+            longitude,
+            altitude,
+        } = fix;
+
+        writeln!(
+            csv,
+            "{frame},{timestamp:.3},{latitude:.9},{longitude:.9},{altitude:.3}"
+        )?;
+        gpx.point(&fix)?;
+        println!("frame {frame}: lat={latitude:.7} lon={longitude:.7} alt={altitude:.2}");
+    }
+
+    gnss.stop();
+    gpx.finish()?;
+    csv.flush()?;
+    eprintln!(
+        "The track is written to {} and {}",
+        gpx_path.display(),
+        csv_path.display()
+    );
+
+    Ok(())
+}
+
+fn create(path: &Path) -> Result<BufWriter<File>> {
+    let file =
+        File::create(path).with_context(|| format!("unable to create {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+/// A GPX 1.1 writer of a single track segment. The points carry no
+/// `<time>` since the simulation has no calendar time. The simulation
+/// timestamps are kept in the CSV file.
+struct GpxWriter {
+    writer: BufWriter<File>,
+}
+
+impl GpxWriter {
+    fn create(path: &Path) -> Result<Self> {
+        let mut writer = create(path)?;
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<gpx version="1.1" creator="carla-rust-example" xmlns="http://www.topografix.com/GPX/1/1">"#
+        )?;
+        writeln!(writer, "  <trk>")?;
+        writeln!(writer, "    <name>CARLA ego vehicle</name>")?;
+        writeln!(writer, "    <trkseg>")?;
+        Ok(Self { writer })
+    }
+
+    fn point(&mut self, fix: &Fix) -> Result<()> {
+        let Fix {
+            latitude,
+            longitude,
+            altitude,
+            ..
+        } = fix;
+        writeln!(
+            self.writer,
+            r#"      <trkpt lat="{latitude:.9}" lon="{longitude:.9}"><ele>{altitude:.3}</ele></trkpt>"#
+        )?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        writeln!(self.writer, "    </trkseg>")?;
+        writeln!(self.writer, "  </trk>")?;
+        writeln!(self.writer, "</gpx>")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use carla::{
+    client::{ActorBase, Sensor},
+    prelude::*,
+    sensor::data::ImuMeasurement,
+};
+use clap::Parser;
+use nalgebra::{Isometry3, UnitQuaternion, Vector3};
+use show::{
+    noise::ImuNoise,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+/// The gravity in m/s², which the accelerometer includes.
+const GRAVITY: f32 = 9.81;
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub imu_noise: ImuNoise,
+
+    /// The CSV file to write the readings and the estimated and
+    /// ground truth poses.
+    #[clap(short = 'o', long, default_value = "imu.csv")]
+    pub output: PathBuf,
+}
+
+/// An IMU reading at a simulation step.
+struct Reading {
+    timestamp: f64,
+    accelerometer: Vector3<f32>,
+    gyroscope: Vector3<f32>,
+    compass: f32,
+}
+
+/// The strapdown integration of the IMU readings.
+struct DeadReckoning {
+    pose: Isometry3<f32>,
+    velocity: Vector3<f32>,
+    timestamp: f64,
+}
+
+impl DeadReckoning {
+    fn step(&mut self, reading: &Reading) {
+        let dt = (reading.timestamp - self.timestamp) as f32;
+        self.timestamp = reading.timestamp;
+
+        // Integrate the angular velocity in the body frame.
+        let rotation =
+            self.pose.rotation * UnitQuaternion::from_scaled_axis(reading.gyroscope * dt);
+
+        // Remove the gravity from the specific force in the world
+        // frame and integrate twice.
+        let acceleration = rotation * reading.accelerometer - Vector3::z() * GRAVITY;
+        let translation =
+            self.pose.translation.vector + self.velocity * dt + 0.5 * acceleration * dt.powi(2);
+        self.velocity += acceleration * dt;
+
+        self.pose = Isometry3::from_parts(translation.into(), rotation);
+    }
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let file = File::create(&opts.output)
+        .with_context(|| format!("unable to create {}", opts.output.display()))?;
+    let mut csv = BufWriter::new(file);
+    writeln!(
+        csv,
+        "frame,timestamp,accel_x,accel_y,accel_z,gyro_x,gyro_y,gyro_z,compass,\
+         est_x,est_y,est_z,est_yaw,true_x,true_y,true_z,true_yaw,position_error,yaw_error"
+    )?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    // Place the sensor at the origin of the vehicle so that the
+    // integrated pose is comparable to the vehicle transform.
+    let ego = sim.ego.clone();
+    let builder = sim.world.actor_builder("sensor.other.imu")?;
+    let imu: Sensor = opts.imu_noise.apply(builder)?.spawn_sensor_opt(
+        &Isometry3::identity(),
+        Some(&ego),
+        None,
+    )?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let imu_data = sync.register("imu", &imu, move |data| {
+        let timestamp = data.timestamp();
+        let measure: ImuMeasurement = data.try_into().unwrap();
+        Reading {
+            timestamp,
+            accelerometer: measure.accelerometer(),
+            gyroscope: measure.gyroscope(),
+            compass: measure.compass(),
+        }
+    });
+
+    let mut estimate: Option<DeadReckoning> = None;
+
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let reading = synced.take(&imu_data);
+        let truth = sim.ego.transform();
+
+        // Start the integration from the ground truth.
+        let estimate = match &mut estimate {
+            Some(estimate) => {
+                estimate.step(&reading);
+                estimate
+            }
+            None => estimate.insert(DeadReckoning {
+                pose: truth,
+                velocity: sim.ego.velocity(),
+                timestamp: reading.timestamp,
+            }),
+        };
+
+        let est = estimate.pose.translation.vector;
+        let gt = truth.translation.vector;
+        let (_, _, est_yaw) = estimate.pose.rotation.euler_angles();
+        let (_, _, true_yaw) = truth.rotation.euler_angles();
+        let position_error = (est - gt).norm();
+        let yaw_error = {
+            let diff = est_yaw - true_yaw;
+            diff.sin().atan2(diff.cos()).to_degrees()
+        };
+
+        let Reading {
+            timestamp,
+            accelerometer: accel,
+            gyroscope: gyro,
+            compass,
+        } = reading;
+        writeln!(
+            csv,
+            "{frame},{timestamp:.3},{},{},{},{},{},{},{compass},{},{},{},{est_yaw},{},{},{},{true_yaw},\
+             {position_error},{yaw_error}",
+            accel.x, accel.y, accel.z, gyro.x, gyro.y, gyro.z, est.x, est.y, est.z, gt.x, gt.y, gt.z
+        )?;
+        println!(
+            "frame {frame}: compass={:.1} deg drift={position_error:.2} m yaw_error={yaw_error:.2} deg",
+            compass.to_degrees()
+        );
+    }
+
+    imu.stop();
+    csv.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use carla::{prelude::*, rpc::ActorId, sensor::data::Image};
+use clap::Parser;
+use image::{ImageBuffer, Luma};
+use serde::Serialize;
+use show::{
+    camera::{save_calibration, CameraConfig},
+    noise::PostProcess,
+    semantic,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    /// The directory to write the instance images and indexes.
+    #[clap(short = 'o', long, default_value = "instance")]
+    pub output_dir: PathBuf,
+
+    /// The image width in pixels.
+    #[clap(long, default_value = "800")]
+    pub width: u32,
+
+    /// The image height in pixels.
+    #[clap(long, default_value = "600")]
+    pub height: u32,
+
+    /// The horizontal field of view in degrees.
+    #[clap(long, default_value = "90.0")]
+    pub fov: f32,
+}
+
+#[derive(Serialize)]
+struct InstanceDesc {
+    pub instance_id: u16,
+    pub semantic_tag: u8,
+    pub class: &'static str,
+    pub pixels: usize,
+    /// The actor that owns the instance. It is absent for the map
+    /// objects.
+    pub actor_id: Option<ActorId>,
+    pub type_id: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let config = CameraConfig {
+        name: "front".to_string(),
+        blueprint: "sensor.camera.instance_segmentation".to_string(),
+        location: [1.5, 0.0, 2.4],
+        rotation: [0.0; 3],
+        width: opts.width,
+        height: opts.height,
+        fov: opts.fov,
+        postprocess: PostProcess::default(),
+    };
+    let ego = sim.ego.clone();
+    let camera = config.spawn(&mut sim.world, &ego)?;
+    save_calibration(opts.output_dir.join("calibration.json"), &[config])?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let camera_data = sync.register("camera", &camera, |data| Image::try_from(data).unwrap());
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let image = synced.take(&camera_data);
+
+        // The red channel stores the semantic tag, and the green and
+        // blue channels store the instance ID, which is the lower 16
+        // bits of the actor ID for actors.
+        let mut ids = Vec::with_capacity(image.len());
+        let mut counts: BTreeMap<u16, (u8, usize)> = BTreeMap::new();
+        for color in image.as_slice() {
+            let id = color.g as u16 | (color.b as u16) << 8;
+            ids.push(id);
+            counts.entry(id).or_insert((color.r, 0)).1 += 1;
+        }
+
+        let path = opts.output_dir.join(format!("{frame:06}.png"));
+        let (width, height) = (image.width() as u32, image.height() as u32);
+        writer.submit(move || {
+            ImageBuffer::<Luma<u16>, _>::from_raw(width, height, ids)
+                .unwrap()
+                .save(&path)
+                .with_context(|| format!("unable to write {}", path.display()))
+        })?;
+
+        // Map the instances to the actors
+        let actors: HashMap<u16, (ActorId, String)> = sim
+            .world
+            .actors()
+            .iter()
+            .map(|actor| (actor.id() as u16, (actor.id(), actor.type_id())))
+            .collect();
+        let index: Vec<_> = counts
+            .into_iter()
+            .map(|(instance_id, (semantic_tag, pixels))| {
+                let actor = actors.get(&instance_id).cloned();
+                InstanceDesc {
+                    instance_id,
+                    semantic_tag,
+                    class: semantic::CLASSES
+                        .get(semantic_tag as usize)
+                        .map_or("unknown", |(name, _)| name),
+                    pixels,
+                    actor_id: actor.as_ref().map(|(id, _)| *id),
+                    type_id: actor.map(|(_, type_id)| type_id),
+                }
+            })
+            .collect();
+
+        let path = opts.output_dir.join(format!("{frame:06}.json"));
+        writer.submit(move || {
+            fs::write(&path, serde_json::to_string_pretty(&index)?)
+                .with_context(|| format!("unable to write {}", path.display()))?;
+            println!("frame {frame}: {} instances", index.len());
+            Ok(())
+        })?;
+    }
+
+    camera.stop();
+    writer.finish()?;
+    Ok(())
+}
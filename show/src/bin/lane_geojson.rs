@@ -0,0 +1,115 @@
+//! Export the lane center lines and borders of the map to GeoJSON.
+//!
+//! The coordinates are the longitude and latitude computed from the
+//! geoReference of the map, the same as the GNSS sensor, so the lanes
+//! can be laid under the tracks of `gnss_logger` in QGIS. With
+//! `--local`, the map coordinates in meters are written instead, with
+//! the y axis flipped so that the north is up.
+
+use anyhow::{Context, Result};
+use carla::client::Client;
+use clap::Parser;
+use nalgebra::Point2;
+use serde_json::{json, Value};
+use show::{
+    geo::GeoReference,
+    topdown::{self, RoadMap},
+};
+use std::{fs, path::PathBuf, time::Duration};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(long, default_value = "localhost")]
+    pub addr: String,
+
+    #[clap(long, default_value = "2000")]
+    pub port: u16,
+
+    /// Load the map before exporting. The current map is used if not
+    /// set.
+    #[clap(long)]
+    pub world: Option<String>,
+
+    /// The output GeoJSON file.
+    #[clap(short = 'o', long, default_value = "lanes.geojson")]
+    pub output: PathBuf,
+
+    /// The distance in meters between the sampled waypoints.
+    #[clap(long, default_value = "2.0")]
+    pub waypoint_distance: f64,
+
+    /// Write the map coordinates in meters instead of the longitude
+    /// and latitude.
+    #[clap(long)]
+    pub local: bool,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+
+    let mut client = Client::connect(&opts.addr, opts.port, None);
+    client.set_timeout(Duration::from_secs(10));
+    let world = match &opts.world {
+        Some(world) => client.load_world(world),
+        None => client.world(),
+    };
+    let map = world.map();
+    let road_map = RoadMap::new(&map, opts.waypoint_distance)?;
+    let reference = GeoReference::from_open_drive(&map.to_open_drive());
+
+    let position = |point: &Point2<f32>| -> Value {
+        let (x, y) = (point.x as f64, point.y as f64);
+        if opts.local {
+            json!([x, -y])
+        } else {
+            let (latitude, longitude) = reference.to_geolocation(x, y);
+            json!([longitude, latitude])
+        }
+    };
+    // Each run between the section gaps becomes a line string.
+    let geometry = |points: &[Point2<f32>]| -> Value {
+        let lines: Vec<Vec<Value>> = topdown::pieces(points, road_map.max_gap())
+            .into_iter()
+            .filter(|piece| piece.len() >= 2)
+            .map(|piece| piece.iter().map(position).collect())
+            .collect();
+        json!({ "type": "MultiLineString", "coordinates": lines })
+    };
+
+    let mut features = vec![];
+    for lane in &road_map.lanes {
+        let (left, right) = lane.boundaries();
+        for (kind, points) in [
+            ("center", &lane.points),
+            ("left_border", &left),
+            ("right_border", &right),
+        ] {
+            features.push(json!({
+                "type": "Feature",
+                "geometry": geometry(points),
+                "properties": {
+                    "kind": kind,
+                    "road_id": lane.road_id,
+                    "section_id": lane.section_id,
+                    "lane_id": lane.lane_id,
+                    "is_junction": lane.is_junction,
+                    "lane_width": lane.width,
+                },
+            }));
+        }
+    }
+
+    let collection = json!({
+        "type": "FeatureCollection",
+        "name": map.name(),
+        "features": features,
+    });
+    fs::write(&opts.output, serde_json::to_string(&collection)?)
+        .with_context(|| format!("unable to write {}", opts.output.display()))?;
+    println!(
+        "saved {} with {} lanes",
+        opts.output.display(),
+        road_map.lanes.len()
+    );
+    Ok(())
+}
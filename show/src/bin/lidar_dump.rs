@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use carla::{client::Sensor, prelude::*, sensor::data::LidarMeasurement};
+use clap::Parser;
+use nalgebra::{Isometry3, Point3};
+use show::{
+    lidar::LidarConfig,
+    noise::LidarNoise,
+    pointcloud::{PointCloud, PointCloudFormat},
+    pose,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{fs, path::PathBuf};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    #[clap(flatten)]
+    pub lidar: LidarConfig,
+
+    #[clap(flatten)]
+    pub lidar_noise: LidarNoise,
+
+    /// The directory to write the point clouds.
+    #[clap(short = 'o', long, default_value = "lidar")]
+    pub output_dir: PathBuf,
+
+    /// The file format of the point clouds.
+    #[clap(long, value_enum, default_value = "pcd")]
+    pub format: PointCloudFormat,
+
+    /// Keep the points in the sensor frame instead of the world
+    /// frame.
+    #[clap(long)]
+    pub sensor_frame: bool,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let lidar_config = opts.lidar.load()?;
+    lidar_config.validate(opts.sim.delta_seconds)?;
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let pose = lidar_config.pose();
+    let ego = sim.ego.clone();
+    let builder = lidar_config.builder(
+        &mut sim.world,
+        "sensor.lidar.ray_cast",
+        opts.sim.delta_seconds,
+    )?;
+    let lidar: Sensor =
+        opts.lidar_noise
+            .apply(builder)?
+            .spawn_sensor_opt(&pose, Some(&ego), None)?;
+
+    // Transform the points with the sensor pose at the time of
+    // capture, which is only available on the raw sensor data.
+    let sensor_frame = opts.sensor_frame;
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let lidar_data = sync.register("lidar", &lidar, move |data| {
+        let transform = if sensor_frame {
+            Isometry3::identity()
+        } else {
+            pose::from_carla(&data.sensor_transform())
+        };
+        let measure: LidarMeasurement = data.try_into().unwrap();
+
+        let mut cloud = PointCloud::new(&["x", "y", "z", "intensity"]);
+        for detection in measure.as_slice() {
+            let point = &detection.point;
+            let point = transform * Point3::new(point.x, point.y, point.z);
+            cloud.push(&[point.x, point.y, point.z, detection.intensity]);
+        }
+        cloud
+    });
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let cloud = synced.take(&lidar_data);
+
+        let path = opts
+            .output_dir
+            .join(format!("{frame:06}.{}", opts.format.extension()));
+        let format = opts.format;
+        writer.submit(move || {
+            cloud.save(&path, format)?;
+            println!("frame {frame}: {} points", cloud.len());
+            Ok(())
+        })?;
+    }
+
+    lidar.stop();
+    writer.finish()?;
+    Ok(())
+}
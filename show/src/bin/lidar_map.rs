@@ -0,0 +1,109 @@
+use anyhow::{ensure, Result};
+use carla::{client::Sensor, prelude::*, sensor::data::LidarMeasurement};
+use clap::Parser;
+use nalgebra::Point3;
+use show::{
+    lidar::LidarConfig,
+    noise::LidarNoise,
+    pointcloud::{PointCloud, PointCloudFormat, VoxelGrid},
+    pose,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub lidar: LidarConfig,
+
+    #[clap(flatten)]
+    pub lidar_noise: LidarNoise,
+
+    /// The path of the map without the extension, which is given by
+    /// the format.
+    #[clap(short = 'o', long, default_value = "map")]
+    pub output: PathBuf,
+
+    /// The file format of the map.
+    #[clap(long, value_enum, default_value = "pcd")]
+    pub format: PointCloudFormat,
+
+    /// The voxel size in meters of the downsampling.
+    #[clap(long, default_value = "0.1")]
+    pub voxel_size: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let lidar_config = opts.lidar.load()?;
+    lidar_config.validate(opts.sim.delta_seconds)?;
+    ensure!(opts.voxel_size > 0.0, "--voxel-size must be positive");
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let pose = lidar_config.pose();
+    let ego = sim.ego.clone();
+    let builder = lidar_config.builder(
+        &mut sim.world,
+        "sensor.lidar.ray_cast",
+        opts.sim.delta_seconds,
+    )?;
+    let lidar: Sensor =
+        opts.lidar_noise
+            .apply(builder)?
+            .spawn_sensor_opt(&pose, Some(&ego), None)?;
+
+    // Register the sweeps with the ground-truth sensor pose.
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let lidar_data = sync.register("lidar", &lidar, move |data| {
+        let transform = pose::from_carla(&data.sensor_transform());
+        let measure: LidarMeasurement = data.try_into().unwrap();
+
+        let mut cloud = PointCloud::new(&["x", "y", "z", "intensity"]);
+        for detection in measure.as_slice() {
+            let point = &detection.point;
+            let point = transform * Point3::new(point.x, point.y, point.z);
+            cloud.push(&[point.x, point.y, point.z, detection.intensity]);
+        }
+        cloud
+    });
+
+    let mut map = VoxelGrid::new(&["x", "y", "z", "intensity"], opts.voxel_size);
+    let mut total = 0;
+
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let cloud = synced.take(&lidar_data);
+
+        total += cloud.len();
+        map.extend(&cloud);
+        println!(
+            "frame {frame}: {} points, {} voxels in the map",
+            cloud.len(),
+            map.len()
+        );
+    }
+
+    lidar.stop();
+
+    let path = opts.output.with_extension(opts.format.extension());
+    map.to_point_cloud().save(&path, opts.format)?;
+    println!(
+        "saved {} voxels from {total} points to {}",
+        map.len(),
+        path.display()
+    );
+
+    Ok(())
+}
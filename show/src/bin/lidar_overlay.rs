@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use carla::{
+    client::Sensor,
+    sensor::data::{Image, LidarMeasurement},
+};
+use clap::Parser;
+use image::{Rgb, RgbImage};
+use nalgebra::Point3;
+use show::{
+    camera::{load_calibration, save_calibration, to_rgb_image, Calibration, CameraConfig},
+    colormap,
+    image_format::ImageOpts,
+    lidar::LidarConfig,
+    noise::{LidarNoise, PostProcess},
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{fs, path::PathBuf};
+
+// Use a denser lidar than the other examples so that the overlay is
+// not too sparse.
+#[derive(Parser)]
+#[command(
+    mut_arg("channels", |arg| arg.default_value("64")),
+    mut_arg("points_per_second", |arg| arg.default_value("500000"))
+)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    #[clap(flatten)]
+    pub lidar: LidarConfig,
+
+    #[clap(flatten)]
+    pub lidar_noise: LidarNoise,
+
+    #[clap(flatten)]
+    pub postprocess: PostProcess,
+
+    #[clap(flatten)]
+    pub image: ImageOpts,
+
+    /// The directory to write the overlay images.
+    #[clap(short = 'o', long, default_value = "overlay")]
+    pub output_dir: PathBuf,
+
+    /// The calibration file exported by the camera examples. The
+    /// camera is placed as calibrated. A front camera is used if not
+    /// set.
+    #[clap(long)]
+    pub calibration: Option<PathBuf>,
+
+    /// The name of the calibrated camera. It defaults to the first one
+    /// in the calibration file.
+    #[clap(long)]
+    pub camera: Option<String>,
+
+    /// The image width in pixels of the default camera.
+    #[clap(long, default_value = "800")]
+    pub width: u32,
+
+    /// The image height in pixels of the default camera.
+    #[clap(long, default_value = "600")]
+    pub height: u32,
+
+    /// The horizontal field of view in degrees of the default camera.
+    #[clap(long, default_value = "90.0")]
+    pub fov: f32,
+
+    /// The depth in meters mapped to the far end of the color scale.
+    #[clap(long, default_value = "30.0")]
+    pub max_depth: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    opts.image.validate()?;
+    let lidar_config = opts.lidar.load()?;
+    lidar_config.validate(opts.sim.delta_seconds)?;
+    let calibration = match &opts.calibration {
+        Some(path) => {
+            let calibrations = load_calibration(path)?;
+            match &opts.camera {
+                Some(name) => calibrations
+                    .into_iter()
+                    .find(|calib| &calib.name == name)
+                    .with_context(|| format!("no camera '{name}' in {}", path.display()))?,
+                None => calibrations
+                    .into_iter()
+                    .next()
+                    .with_context(|| format!("no camera in {}", path.display()))?,
+            }
+        }
+        None => Calibration::from(&CameraConfig {
+            name: "front".to_string(),
+            blueprint: "sensor.camera.rgb".to_string(),
+            location: [1.5, 0.0, 2.4],
+            rotation: [0.0; 3],
+            width: opts.width,
+            height: opts.height,
+            fov: opts.fov,
+            postprocess: PostProcess::default(),
+        }),
+    };
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+    let ego = sim.ego.clone();
+
+    let config = CameraConfig {
+        postprocess: opts.postprocess.clone(),
+        ..calibration.to_config("sensor.camera.rgb")
+    };
+    let camera = config.spawn(&mut sim.world, &ego)?;
+    save_calibration(opts.output_dir.join("calibration.json"), &[config])?;
+
+    let lidar_pose = lidar_config.pose();
+    let builder = lidar_config.builder(
+        &mut sim.world,
+        "sensor.lidar.ray_cast",
+        opts.sim.delta_seconds,
+    )?;
+    let lidar: Sensor =
+        opts.lidar_noise
+            .apply(builder)?
+            .spawn_sensor_opt(&lidar_pose, Some(&ego), None)?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let images = sync.register("camera", &camera, |data| Image::try_from(data).unwrap());
+
+    // Express the points in the vehicle frame, where the camera
+    // extrinsics are defined.
+    let sweeps = sync.register("lidar", &lidar, move |data| {
+        let measure: LidarMeasurement = data.try_into().unwrap();
+        measure
+            .as_slice()
+            .iter()
+            .map(|detection| {
+                let point = &detection.point;
+                lidar_pose * Point3::new(point.x, point.y, point.z)
+            })
+            .collect::<Vec<Point3<f32>>>()
+    });
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let image = synced.take(&images);
+        let points = synced.take(&sweeps);
+
+        let mut overlay = to_rgb_image(&image);
+        let mut count = 0;
+        for point in &points {
+            let Some((pixel, depth)) = calibration.project(point) else {
+                continue;
+            };
+            if draw_dot(
+                &mut overlay,
+                pixel.x,
+                pixel.y,
+                colormap::rainbow(depth / opts.max_depth),
+            ) {
+                count += 1;
+            }
+        }
+
+        let path = opts.image.path(&opts.output_dir, &format!("{frame:06}"));
+        let image_opts = opts.image.clone();
+        let total = points.len();
+        writer.submit(move || {
+            image_opts
+                .save(&overlay, &path)
+                .with_context(|| format!("unable to write {}", path.display()))?;
+            println!("frame {frame}: projected {count} of {total} points");
+            Ok(())
+        })?;
+    }
+
+    camera.stop();
+    lidar.stop();
+    writer.finish()?;
+    Ok(())
+}
+
+/// Draw a 2x2 dot and return whether it is within the image.
+fn draw_dot(image: &mut RgbImage, x: f32, y: f32, color: [u8; 3]) -> bool {
+    let (width, height) = image.dimensions();
+    if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+        return false;
+    }
+    let (x, y) = (x as u32, y as u32);
+    for py in y..(y + 2).min(height) {
+        for px in x..(x + 2).min(width) {
+            image.put_pixel(px, py, Rgb(color));
+        }
+    }
+    true
+}
@@ -0,0 +1,158 @@
+use anyhow::{ensure, Context, Result};
+use carla::{client::Sensor, sensor::data::LidarMeasurement};
+use clap::Parser;
+use minifb::{Key, Window, WindowOptions};
+use nalgebra::Point3;
+use show::{
+    colormap,
+    lidar::LidarConfig,
+    noise::LidarNoise,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+};
+
+/// The half length and half width in meters of the ego marker.
+const EGO_EXTENT: [f32; 2] = [2.4, 1.0];
+
+/// The color of the ego marker.
+const EGO_COLOR: u32 = 0xffffff;
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub lidar: LidarConfig,
+
+    #[clap(flatten)]
+    pub lidar_noise: LidarNoise,
+
+    /// The width and height in pixels of the window.
+    #[clap(long, default_value = "800")]
+    pub size: usize,
+
+    /// The distance in meters from the ego vehicle to the window
+    /// borders.
+    #[clap(long, default_value = "50.0")]
+    pub view_range: f32,
+
+    /// The height in meters above the vehicle origin mapped to the
+    /// red end of the color scale.
+    #[clap(long, default_value = "-0.5")]
+    pub min_height: f32,
+
+    /// The height in meters mapped to the blue end of the color scale.
+    #[clap(long, default_value = "3.0")]
+    pub max_height: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let lidar_config = opts.lidar.load()?;
+    lidar_config.validate(opts.sim.delta_seconds)?;
+    ensure!(
+        opts.min_height < opts.max_height,
+        "--min-height must be less than --max-height"
+    );
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let pose = lidar_config.pose();
+    let ego = sim.ego.clone();
+    let builder = lidar_config.builder(
+        &mut sim.world,
+        "sensor.lidar.ray_cast",
+        opts.sim.delta_seconds,
+    )?;
+    let lidar: Sensor =
+        opts.lidar_noise
+            .apply(builder)?
+            .spawn_sensor_opt(&pose, Some(&ego), None)?;
+
+    // Express the points in the vehicle frame so that the view
+    // follows the vehicle.
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let lidar_data = sync.register("lidar", &lidar, move |data| {
+        let measure: LidarMeasurement = data.try_into().unwrap();
+        measure
+            .as_slice()
+            .iter()
+            .map(|detection| {
+                let point = &detection.point;
+                pose * Point3::new(point.x, point.y, point.z)
+            })
+            .collect::<Vec<Point3<f32>>>()
+    });
+
+    let size = opts.size;
+    let mut window = Window::new("lidar", size, size, WindowOptions::default())
+        .with_context(|| "unable to open the window")?;
+    let mut buffer = vec![0u32; size * size];
+    let scale = size as f32 / (2.0 * opts.view_range);
+
+    // The x axis points up and the y axis to the right.
+    let to_pixel = |x: f32, y: f32| -> Option<usize> {
+        let row = size as f32 / 2.0 - x * scale;
+        let col = size as f32 / 2.0 + y * scale;
+        let range = 0.0..size as f32;
+        (range.contains(&row) && range.contains(&col)).then(|| row as usize * size + col as usize)
+    };
+
+    while sim.is_running() && window.is_open() && !window.is_key_down(Key::Escape) {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                window.update();
+                continue;
+            }
+        };
+        let points = synced.take(&lidar_data);
+
+        buffer.fill(0);
+        let mut visible = 0;
+        for point in &points {
+            let Some(index) = to_pixel(point.x, point.y) else {
+                continue;
+            };
+            let height = (point.z - opts.min_height) / (opts.max_height - opts.min_height);
+            let [r, g, b] = colormap::rainbow(height);
+            buffer[index] = u32::from_be_bytes([0, r, g, b]);
+            visible += 1;
+        }
+
+        // Draw the outline of the ego vehicle.
+        let [half_length, half_width] = EGO_EXTENT;
+        let steps = (2.0 * half_length * scale).ceil() as usize;
+        for step in 0..=steps {
+            let x = -half_length + 2.0 * half_length * step as f32 / steps as f32;
+            for y in [-half_width, half_width] {
+                if let Some(index) = to_pixel(x, y) {
+                    buffer[index] = EGO_COLOR;
+                }
+            }
+        }
+        let steps = (2.0 * half_width * scale).ceil() as usize;
+        for step in 0..=steps {
+            let y = -half_width + 2.0 * half_width * step as f32 / steps as f32;
+            for x in [-half_length, half_length] {
+                if let Some(index) = to_pixel(x, y) {
+                    buffer[index] = EGO_COLOR;
+                }
+            }
+        }
+
+        window
+            .update_with_buffer(&buffer, size, size)
+            .with_context(|| "unable to update the window")?;
+        window.set_title(&format!(
+            "lidar - frame {frame}: {visible} of {} points",
+            points.len()
+        ));
+    }
+
+    lidar.stop();
+    Ok(())
+}
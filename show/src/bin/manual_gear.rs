@@ -0,0 +1,147 @@
+use anyhow::Result;
+use carla::{
+    client::{ActorBase, Client, Vehicle},
+    rpc::{EpisodeSettings, VehicleControl},
+};
+use clap::Parser;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use std::{f32::consts::PI, time::Duration};
+
+/// The simulation time step in seconds.
+const FIXED_DELTA_SECONDS: f64 = 0.05;
+
+#[derive(Parser)]
+struct Opts {
+    /// The duration in seconds of each phase of the gear schedule.
+    #[clap(long, default_value = "4.0")]
+    pub phase_seconds: f64,
+}
+
+/// A step in the scripted gear schedule.
+struct Phase {
+    name: &'static str,
+    gear: i32,
+    throttle: f32,
+    brake: f32,
+}
+
+const SCHEDULE: &[Phase] = &[
+    Phase {
+        name: "first gear",
+        gear: 1,
+        throttle: 0.6,
+        brake: 0.0,
+    },
+    Phase {
+        name: "second gear",
+        gear: 2,
+        throttle: 0.6,
+        brake: 0.0,
+    },
+    Phase {
+        name: "third gear",
+        gear: 3,
+        throttle: 0.6,
+        brake: 0.0,
+    },
+    Phase {
+        name: "brake",
+        gear: 1,
+        throttle: 0.0,
+        brake: 1.0,
+    },
+    Phase {
+        name: "reverse gear",
+        gear: -1,
+        throttle: 0.4,
+        brake: 0.0,
+    },
+    Phase {
+        name: "brake",
+        gear: -1,
+        throttle: 0.0,
+        brake: 1.0,
+    },
+];
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+
+    let mut client = Client::default();
+    client.set_timeout(Duration::from_secs(5));
+
+    let mut world = client.load_world("Town07");
+    let orig_settings = world.settings();
+    world.apply_settings(
+        &EpisodeSettings {
+            synchronous_mode: true,
+            fixed_delta_seconds: Some(FIXED_DELTA_SECONDS),
+            ..orig_settings.clone()
+        },
+        Duration::ZERO,
+    );
+
+    let vehicle_pose = Isometry3 {
+        translation: Translation3::new(72.31, -7.55, 1.0),
+        rotation: UnitQuaternion::from_euler_angles(
+            0.01_f32.to_radians(),
+            1.13_f32.to_radians(),
+            -62.79_f32.to_radians(),
+        ),
+    };
+    let vehicle: Vehicle = world
+        .actor_builder("vehicle.tesla.model3")?
+        .spawn_vehicle(&vehicle_pose)?;
+
+    let ticks = (opts.phase_seconds / FIXED_DELTA_SECONDS) as usize;
+
+    for phase in SCHEDULE {
+        eprintln!("Phase: {}", phase.name);
+
+        for _ in 0..ticks {
+            // Shift gears explicitly. The reverse flag must agree with
+            // the negative gear.
+            vehicle.apply_control(&VehicleControl {
+                throttle: phase.throttle,
+                steer: 0.0,
+                brake: phase.brake,
+                hand_brake: false,
+                reverse: phase.gear < 0,
+                manual_gear_shift: true,
+                gear: phase.gear,
+            });
+            world.tick();
+
+            let control = vehicle.control();
+            let speed = vehicle.velocity().norm();
+            println!(
+                "commanded_gear={} gear={} speed={speed:.2} m/s rpm={:.0}",
+                phase.gear,
+                control.gear,
+                engine_rpm(&vehicle, control.gear, speed)
+            );
+        }
+    }
+
+    world.apply_settings(&orig_settings, Duration::ZERO);
+
+    Ok(())
+}
+
+/// Estimate the engine RPM from the wheel speed and the gear ratios.
+/// CARLA 0.9.14 does not report the engine telemetry.
+fn engine_rpm(vehicle: &Vehicle, gear: i32, speed: f32) -> f32 {
+    let physics = vehicle.physics_control();
+
+    // Reverse uses the first gear ratio.
+    let index = gear.unsigned_abs().max(1) as usize - 1;
+    let Some(ratio) = physics.forward_gears.get(index).map(|gear| gear.ratio) else {
+        return 0.0;
+    };
+    let Some(radius) = physics.wheels.first().map(|wheel| wheel.radius / 100.0) else {
+        return 0.0;
+    };
+
+    let wheel_rpm = speed / radius * 60.0 / (2.0 * PI);
+    (wheel_rpm * ratio * physics.final_ratio).min(physics.max_rpm)
+}
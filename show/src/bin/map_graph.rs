@@ -0,0 +1,146 @@
+//! Export the lane connectivity of the map as a Graphviz DOT graph.
+//!
+//! The carla crate has no binding to the map topology, so it is
+//! rebuilt from the waypoints. A node is a lane of a road section, and
+//! an edge leads to the lanes that a vehicle can drive into. The lanes
+//! inside a junction are grouped in a cluster.
+//!
+//! Render the graph with `dot -Tsvg map.dot -o map.svg`.
+
+use anyhow::{Context, Result};
+use carla::{
+    client::{Client, Waypoint},
+    road::{JuncId, LaneId, RoadId, SectionId},
+};
+use clap::Parser;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    fs,
+    path::PathBuf,
+    time::Duration,
+};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(long, default_value = "localhost")]
+    pub addr: String,
+
+    #[clap(long, default_value = "2000")]
+    pub port: u16,
+
+    /// Load the map before exporting. The current map is used if not
+    /// set.
+    #[clap(long)]
+    pub world: Option<String>,
+
+    /// The output DOT file. Print to stdout if not set.
+    #[clap(short = 'o', long)]
+    pub output: Option<PathBuf>,
+
+    /// The distance in meters between the sampled waypoints.
+    #[clap(long, default_value = "2.0")]
+    pub waypoint_distance: f64,
+}
+
+type LaneKey = (RoadId, SectionId, LaneId);
+
+/// A lane of a road section.
+struct Node {
+    junction: Option<JuncId>,
+    min_s: f64,
+    max_s: f64,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+
+    let mut client = Client::connect(&opts.addr, opts.port, None);
+    client.set_timeout(Duration::from_secs(10));
+    let world = match &opts.world {
+        Some(world) => client.load_world(world),
+        None => client.world(),
+    };
+    let map = world.map();
+
+    // Follow each waypoint ahead to find the lanes entered from it.
+    let mut nodes: BTreeMap<LaneKey, Node> = BTreeMap::new();
+    let mut edges: BTreeSet<(LaneKey, LaneKey)> = BTreeSet::new();
+    for waypoint in map.generate_waypoints(opts.waypoint_distance).iter() {
+        let from = key(&waypoint);
+        let s = waypoint.distance();
+        let node = nodes.entry(from).or_insert_with(|| Node {
+            junction: waypoint.is_junction().then(|| waypoint.junction_id()),
+            min_s: s,
+            max_s: s,
+        });
+        node.min_s = node.min_s.min(s);
+        node.max_s = node.max_s.max(s);
+
+        for next in waypoint.next(opts.waypoint_distance).iter() {
+            let to = key(&next);
+            if to != from {
+                edges.insert((from, to));
+            }
+        }
+    }
+
+    // Group the lanes in the junctions into clusters.
+    let mut junctions: BTreeMap<JuncId, Vec<LaneKey>> = BTreeMap::new();
+    let mut dot = String::new();
+    writeln!(dot, "digraph \"{}\" {{", map.name())?;
+    writeln!(dot, "    node [shape=box];")?;
+    for (key, node) in &nodes {
+        match node.junction {
+            Some(junction) => junctions.entry(junction).or_default().push(*key),
+            None => writeln!(dot, "    {}", node_line(key, node))?,
+        }
+    }
+    for (junction, keys) in &junctions {
+        writeln!(dot, "    subgraph cluster_junction_{junction} {{")?;
+        writeln!(dot, "        label=\"junction {junction}\";")?;
+        for key in keys {
+            writeln!(dot, "        {}", node_line(key, &nodes[key]))?;
+        }
+        writeln!(dot, "    }}")?;
+    }
+    for (from, to) in &edges {
+        writeln!(dot, "    {} -> {};", node_id(from), node_id(to))?;
+    }
+    writeln!(dot, "}}")?;
+
+    match &opts.output {
+        Some(path) => {
+            fs::write(path, &dot).with_context(|| format!("unable to write {}", path.display()))?;
+            eprintln!(
+                "saved {} with {} lanes and {} connections",
+                path.display(),
+                nodes.len(),
+                edges.len()
+            );
+        }
+        None => print!("{dot}"),
+    }
+    Ok(())
+}
+
+fn key(waypoint: &Waypoint) -> LaneKey {
+    (
+        waypoint.road_id(),
+        waypoint.section_id(),
+        waypoint.lane_id(),
+    )
+}
+
+fn node_id((road, section, lane): &LaneKey) -> String {
+    format!("\"r{road}_s{section}_l{lane}\"")
+}
+
+fn node_line(key: &LaneKey, node: &Node) -> String {
+    let (road, section, lane) = key;
+    format!(
+        "{} [label=\"road {road} lane {lane}\\nsection {section}, {:.0} m\"];",
+        node_id(key),
+        node.max_s - node.min_s
+    )
+}
@@ -0,0 +1,155 @@
+//! Render the road network and a route into a top-down image.
+//!
+//! The route is read from a CSV file with a header, such as the
+//! ground truth columns `true_x` and `true_y` written by
+//! `imu_odometry`.
+
+use anyhow::{ensure, Context, Result};
+use carla::client::Client;
+use clap::Parser;
+use image::Rgb;
+use nalgebra::Point2;
+use show::topdown::{self, Canvas, RoadMap};
+use std::{fs, path::PathBuf, time::Duration};
+
+const ROUTE_COLOR: Rgb<u8> = Rgb([220, 30, 30]);
+const START_COLOR: Rgb<u8> = Rgb([30, 160, 30]);
+const END_COLOR: Rgb<u8> = Rgb([30, 30, 220]);
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(long, default_value = "localhost")]
+    pub addr: String,
+
+    #[clap(long, default_value = "2000")]
+    pub port: u16,
+
+    /// Load the map before rendering. The current map is used if not
+    /// set.
+    #[clap(long)]
+    pub world: Option<String>,
+
+    /// The output PNG file.
+    #[clap(short = 'o', long, default_value = "minimap.png")]
+    pub output: PathBuf,
+
+    /// The CSV file of the driven or planned route.
+    #[clap(long)]
+    pub route: Option<PathBuf>,
+
+    /// The column of the x coordinates in meters in the route file.
+    #[clap(long, default_value = "x")]
+    pub x_column: String,
+
+    /// The column of the y coordinates in meters in the route file.
+    #[clap(long, default_value = "y")]
+    pub y_column: String,
+
+    /// The scale of the image.
+    #[clap(long, default_value = "2.0")]
+    pub pixels_per_meter: f32,
+
+    /// The distance in meters between the sampled waypoints.
+    #[clap(long, default_value = "2.0")]
+    pub waypoint_distance: f64,
+
+    /// The space in meters around the roads.
+    #[clap(long, default_value = "10.0")]
+    pub margin: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    ensure!(
+        opts.pixels_per_meter > 0.0,
+        "--pixels-per-meter must be positive"
+    );
+    let route = opts
+        .route
+        .as_ref()
+        .map(|path| load_route(path, &opts.x_column, &opts.y_column))
+        .transpose()?;
+
+    let mut client = Client::connect(&opts.addr, opts.port, None);
+    client.set_timeout(Duration::from_secs(10));
+    let world = match &opts.world {
+        Some(world) => client.load_world(world),
+        None => client.world(),
+    };
+
+    let road_map = RoadMap::new(&world.map(), opts.waypoint_distance)?;
+
+    // Fit the roads and the route in the image.
+    let (min, max) = road_map.bounds();
+    let (min, max) = match &route {
+        Some(route) => topdown::bounds(route.iter().chain([&min, &max])),
+        None => (min, max),
+    };
+    let mut canvas = Canvas::fit(min, max, opts.margin, opts.pixels_per_meter)?;
+    road_map.draw(&mut canvas);
+
+    if let Some(route) = &route {
+        canvas.polyline(route, 1.5, f32::INFINITY, ROUTE_COLOR);
+        if let (Some(start), Some(end)) = (route.first(), route.last()) {
+            canvas.disc(start, 4.0, START_COLOR);
+            canvas.disc(end, 4.0, END_COLOR);
+        }
+    }
+
+    canvas
+        .image
+        .save(&opts.output)
+        .with_context(|| format!("unable to write {}", opts.output.display()))?;
+    println!(
+        "saved {} with {} lanes{}",
+        opts.output.display(),
+        road_map.lanes.len(),
+        route.map_or(String::new(), |route| format!(
+            " and a route of {} points",
+            route.len()
+        ))
+    );
+    Ok(())
+}
+
+/// Read the route points from the named columns of a CSV file.
+fn load_route(path: &PathBuf, x_column: &str, y_column: &str) -> Result<Vec<Point2<f32>>> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("unable to read {}", path.display()))?;
+    let mut lines = text.lines();
+    let header: Vec<&str> = lines
+        .next()
+        .with_context(|| format!("{} is empty", path.display()))?
+        .split(',')
+        .map(str::trim)
+        .collect();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| *column == name)
+            .with_context(|| format!("{} has no column '{name}'", path.display()))
+    };
+    let (x_index, y_index) = (column(x_column)?, column(y_column)?);
+
+    let mut route = vec![];
+    for (index, line) in lines.enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let parse = |field: usize| -> Result<f32> {
+            let value = fields.get(field).with_context(|| {
+                format!("line {} of {} is too short", index + 2, path.display())
+            })?;
+            value.parse().with_context(|| {
+                format!(
+                    "unable to parse '{value}' at line {} of {}",
+                    index + 2,
+                    path.display()
+                )
+            })
+        };
+        route.push(Point2::new(parse(x_index)?, parse(y_index)?));
+    }
+    Ok(route)
+}
@@ -0,0 +1,115 @@
+use anyhow::{ensure, Context, Result};
+use carla::{client::Sensor, sensor::data::Image};
+use clap::Parser;
+use show::{
+    camera::{save_calibration, to_rgb_image, Rig},
+    image_format::ImageOpts,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::{SensorHandle, SensorSync},
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{collections::HashSet, fs, path::PathBuf};
+
+/// The rig used when no file is given.
+const DEFAULT_RIG: &str = include_str!("../../rig.toml");
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    #[clap(flatten)]
+    pub image: ImageOpts,
+
+    /// The directory to write the images, with a subdirectory per
+    /// camera.
+    #[clap(short = 'o', long, default_value = "multi_camera")]
+    pub output_dir: PathBuf,
+
+    /// The TOML file of the camera rig. It defaults to the front, left,
+    /// right and rear cameras in rig.toml.
+    #[clap(long)]
+    pub rig: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    opts.image.validate()?;
+    let rig = match &opts.rig {
+        Some(path) => Rig::load(path)?,
+        None => toml::from_str(DEFAULT_RIG)?,
+    };
+    ensure!(!rig.cameras.is_empty(), "the rig has no cameras");
+    {
+        let mut names = HashSet::new();
+        for camera in &rig.cameras {
+            ensure!(
+                names.insert(&camera.name),
+                "the camera name '{}' is used more than once",
+                camera.name
+            );
+        }
+    }
+
+    for camera in &rig.cameras {
+        let dir = opts.output_dir.join(&camera.name);
+        fs::create_dir_all(&dir).with_context(|| format!("unable to create {}", dir.display()))?;
+    }
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let ego = sim.ego.clone();
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let cameras: Vec<(Sensor, SensorHandle<Image>)> = rig
+        .cameras
+        .iter()
+        .map(|config| {
+            let camera = config.spawn(&mut sim.world, &ego)?;
+            let images =
+                sync.register(&config.name, &camera, |data| Image::try_from(data).unwrap());
+            Ok((camera, images))
+        })
+        .collect::<Result<_>>()?;
+    save_calibration(opts.output_dir.join("calibration.json"), &rig.cameras)?;
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+
+        // Wait for every camera so that the frame index is shared
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let images: Vec<Image> = cameras
+            .iter()
+            .map(|(_, images)| synced.take(images))
+            .collect();
+
+        for (config, image) in rig.cameras.iter().zip(&images) {
+            let path = opts
+                .image
+                .path(opts.output_dir.join(&config.name), &format!("{frame:06}"));
+            let image = to_rgb_image(image);
+            let image_opts = opts.image.clone();
+            writer.submit(move || {
+                image_opts
+                    .save(&image, &path)
+                    .with_context(|| format!("unable to write {}", path.display()))
+            })?;
+        }
+        println!("frame {frame}: saved {} images", images.len());
+    }
+
+    for (camera, _) in &cameras {
+        camera.stop();
+    }
+    writer.finish()?;
+    Ok(())
+}
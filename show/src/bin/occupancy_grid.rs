@@ -0,0 +1,143 @@
+use anyhow::{ensure, Context, Result};
+use carla::{client::Sensor, prelude::*, sensor::data::LidarMeasurement};
+use clap::Parser;
+use nalgebra::Point3;
+use show::{
+    lidar::LidarConfig,
+    noise::LidarNoise,
+    occupancy::{GridParams, OccupancyGrid, Sweep},
+    pose,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{collections::VecDeque, fs, path::PathBuf};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    #[clap(flatten)]
+    pub lidar: LidarConfig,
+
+    #[clap(flatten)]
+    pub lidar_noise: LidarNoise,
+
+    /// The directory to write the grid images.
+    #[clap(short = 'o', long, default_value = "occupancy")]
+    pub output_dir: PathBuf,
+
+    /// The cell size in meters.
+    #[clap(long, default_value = "0.2")]
+    pub resolution: f32,
+
+    /// The distance in meters from the ego vehicle to the grid
+    /// borders.
+    #[clap(long, default_value = "40.0")]
+    pub extent: f32,
+
+    /// The number of recent sweeps fused into each grid.
+    #[clap(long, default_value = "1")]
+    pub fuse: usize,
+
+    /// The points below this height in meters above the vehicle
+    /// origin are treated as ground.
+    #[clap(long, default_value = "0.3")]
+    pub min_height: f32,
+
+    /// The points above this height in meters are ignored.
+    #[clap(long, default_value = "2.5")]
+    pub max_height: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let lidar_config = opts.lidar.load()?;
+    lidar_config.validate(opts.sim.delta_seconds)?;
+    ensure!(opts.fuse > 0, "--fuse must be at least 1");
+    ensure!(opts.resolution > 0.0, "--resolution must be positive");
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let params = GridParams {
+        resolution: opts.resolution,
+        extent: opts.extent,
+        min_height: opts.min_height,
+        max_height: opts.max_height,
+    };
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let pose = lidar_config.pose();
+    let ego = sim.ego.clone();
+    let builder = lidar_config.builder(
+        &mut sim.world,
+        "sensor.lidar.ray_cast",
+        opts.sim.delta_seconds,
+    )?;
+    let lidar: Sensor =
+        opts.lidar_noise
+            .apply(builder)?
+            .spawn_sensor_opt(&pose, Some(&ego), None)?;
+
+    // Keep the sweeps in the world frame so that they can be fused
+    // after the vehicle moves.
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let lidar_data = sync.register("lidar", &lidar, move |data| {
+        let transform = pose::from_carla(&data.sensor_transform());
+        let measure: LidarMeasurement = data.try_into().unwrap();
+        let points = measure
+            .as_slice()
+            .iter()
+            .map(|detection| {
+                let point = &detection.point;
+                transform * Point3::new(point.x, point.y, point.z)
+            })
+            .collect();
+        Sweep {
+            origin: Point3::from(transform.translation.vector),
+            points,
+        }
+    });
+
+    let mut sweeps = VecDeque::with_capacity(opts.fuse);
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let sweep = synced.take(&lidar_data);
+        if sweeps.len() == opts.fuse {
+            sweeps.pop_front();
+        }
+        sweeps.push_back(sweep);
+
+        let grid = OccupancyGrid::from_sweeps(
+            params.clone(),
+            &pose::from_carla(&sim.ego.transform()),
+            &sweeps,
+        );
+        let path = opts.output_dir.join(format!("{frame:06}.png"));
+        writer.submit(move || {
+            grid.to_image()
+                .save(&path)
+                .with_context(|| format!("unable to write {}", path.display()))?;
+            println!("saved {}", path.display());
+            Ok(())
+        })?;
+    }
+
+    lidar.stop();
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use carla::{
+    client::Sensor,
+    prelude::*,
+    sensor::data::{RadarDetection, RadarMeasurement},
+};
+use clap::Parser;
+use nalgebra::{Isometry3, Point3, Translation3, UnitQuaternion};
+use show::{
+    pose,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+};
+
+/// The tolerance in meters and radians when checking the detections
+/// against the sensor limits.
+const TOLERANCE: f32 = 1e-3;
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    /// The horizontal field of view in degrees.
+    #[clap(long, default_value = "30.0")]
+    pub horizontal_fov: f32,
+
+    /// The vertical field of view in degrees.
+    #[clap(long, default_value = "30.0")]
+    pub vertical_fov: f32,
+
+    /// The maximum distance in meters.
+    #[clap(long, default_value = "100.0")]
+    pub range: f32,
+
+    /// The number of points per second.
+    #[clap(long, default_value = "1500")]
+    pub points_per_second: u32,
+
+    /// Also print every detection with its location in the world frame.
+    #[clap(long)]
+    pub verbose: bool,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let pose = Isometry3::from_parts(Translation3::new(2.0, 0.0, 1.0), UnitQuaternion::identity());
+    let ego = sim.ego.clone();
+    let radar: Sensor = sim
+        .world
+        .actor_builder("sensor.other.radar")?
+        .set_attribute("horizontal_fov", &opts.horizontal_fov.to_string())?
+        .set_attribute("vertical_fov", &opts.vertical_fov.to_string())?
+        .set_attribute("range", &opts.range.to_string())?
+        .set_attribute("points_per_second", &opts.points_per_second.to_string())?
+        .spawn_sensor_opt(&pose, Some(&ego), None)
+        .with_context(|| "unable to spawn the radar")?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let radar_data = sync.register("radar", &radar, move |data| {
+        let transform = pose::from_carla(&data.sensor_transform());
+        let measure: RadarMeasurement = data.try_into().unwrap();
+        (transform, measure)
+    });
+
+    let max_azimuth = (opts.horizontal_fov / 2.0).to_radians() + TOLERANCE;
+    let max_altitude = (opts.vertical_fov / 2.0).to_radians() + TOLERANCE;
+
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let (transform, measure) = synced.take(&radar_data);
+
+        let mut approaching = 0;
+        let mut receding = 0;
+        let mut violations = 0;
+
+        for detection in measure.as_slice() {
+            let &RadarDetection {
+                velocity,
+                azimuth,
+                altitude,
+                depth,
+            } = detection;
+
+            // Check the detection against the sensor configuration.
+            let valid = depth <= opts.range + TOLERANCE
+                && azimuth.abs() <= max_azimuth
+                && altitude.abs() <= max_altitude;
+            if !valid {
+                violations += 1;
+                eprintln!(
+                    "frame {frame}: detection out of range depth={depth:.2} azimuth={:.2} \
+                     altitude={:.2}",
+                    azimuth.to_degrees(),
+                    altitude.to_degrees()
+                );
+            }
+
+            // The velocity is relative to the sensor and negative
+            // towards it.
+            if velocity < 0.0 {
+                approaching += 1;
+            } else {
+                receding += 1;
+            }
+
+            if opts.verbose {
+                let location = transform * detection_point(detection);
+                println!(
+                    "frame {frame}: depth={depth:.2} m azimuth={:.2} deg altitude={:.2} deg \
+                     velocity={velocity:.2} m/s location=({:.2}, {:.2}, {:.2})",
+                    azimuth.to_degrees(),
+                    altitude.to_degrees(),
+                    location.x,
+                    location.y,
+                    location.z
+                );
+            }
+        }
+
+        println!(
+            "frame {frame}: {} detections, {approaching} approaching, {receding} receding, \
+             {violations} out of range",
+            measure.len()
+        );
+    }
+
+    radar.stop();
+    Ok(())
+}
+
+/// The location of a detection in the sensor frame.
+fn detection_point(detection: &RadarDetection) -> Point3<f32> {
+    let RadarDetection {
+        azimuth,
+        altitude,
+        depth,
+        ..
+    } = *detection;
+    Point3::new(
+        depth * altitude.cos() * azimuth.cos(),
+        depth * altitude.cos() * azimuth.sin(),
+        depth * altitude.sin(),
+    )
+}
@@ -0,0 +1,95 @@
+//! Capture one high-resolution image at the spectator pose.
+//!
+//! The camera is spawned at the current spectator pose, so the view
+//! is framed in the server window first. The camera is stopped after
+//! the frame and stays idle in the world.
+//!
+//! The tool never ticks the world. In the synchronous mode it waits
+//! for the client that drives the simulation to tick.
+
+use anyhow::{ensure, Context, Result};
+use carla::{client::Client, prelude::*, sensor::data::Image};
+use clap::Parser;
+use show::{camera::to_rgb_image, image_format::ImageOpts, sim::SENSOR_TIMEOUT, sync::SensorSync};
+use std::{fs, path::PathBuf, time::Duration};
+
+/// The number of frames to wait for the image.
+const FRAMES: usize = 3;
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(long, default_value = "localhost")]
+    pub addr: String,
+
+    #[clap(long, default_value = "2000")]
+    pub port: u16,
+
+    #[clap(flatten)]
+    pub image: ImageOpts,
+
+    /// The directory to write the image.
+    #[clap(short = 'o', long, default_value = ".")]
+    pub output_dir: PathBuf,
+
+    /// The image width in pixels.
+    #[clap(long, default_value = "3840")]
+    pub width: u32,
+
+    /// The image height in pixels.
+    #[clap(long, default_value = "2160")]
+    pub height: u32,
+
+    /// The horizontal field of view in degrees.
+    #[clap(long, default_value = "90.0")]
+    pub fov: f32,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    opts.image.validate()?;
+    ensure!(
+        opts.width > 0 && opts.height > 0,
+        "the image size must be positive"
+    );
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut client = Client::connect(&opts.addr, opts.port, None);
+    client.set_timeout(Duration::from_secs(10));
+    let mut world = client.world();
+    if world.settings().synchronous_mode {
+        eprintln!("The server is in the synchronous mode, waiting for its client to tick");
+    }
+
+    let pose = world.spectator().transform();
+    let camera = world
+        .actor_builder("sensor.camera.rgb")?
+        .set_attribute("image_size_x", &opts.width.to_string())?
+        .set_attribute("image_size_y", &opts.height.to_string())?
+        .set_attribute("fov", &opts.fov.to_string())?
+        .spawn_sensor(&pose)?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let camera_data = sync.register("camera", &camera, |data| Image::try_from(data).unwrap());
+
+    // The first frame after spawning may miss the camera.
+    let mut image = None;
+    for _ in 0..FRAMES {
+        let frame = world.wait_for_tick().frame();
+        if let Ok(mut synced) = sync.wait(frame) {
+            image = Some((frame, synced.take(&camera_data)));
+            break;
+        }
+    }
+    camera.stop();
+    let (frame, image) = image.with_context(|| "the camera did not send an image")?;
+
+    let path = opts
+        .image
+        .path(&opts.output_dir, &format!("screenshot_{frame:06}"));
+    opts.image
+        .save(&to_rgb_image(&image), &path)
+        .with_context(|| format!("unable to write {}", path.display()))?;
+    println!("saved {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use carla::sensor::data::Image;
+use clap::Parser;
+use image::{GrayImage, RgbImage};
+use show::{
+    camera::{save_calibration, CameraConfig},
+    noise::PostProcess,
+    semantic,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{fs, path::PathBuf};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    /// The directory to write the images.
+    #[clap(short = 'o', long, default_value = "semantic")]
+    pub output_dir: PathBuf,
+
+    /// The image width in pixels.
+    #[clap(long, default_value = "800")]
+    pub width: u32,
+
+    /// The image height in pixels.
+    #[clap(long, default_value = "600")]
+    pub height: u32,
+
+    /// The horizontal field of view in degrees.
+    #[clap(long, default_value = "90.0")]
+    pub fov: f32,
+
+    /// Also write the raw label images, whose pixel values are the
+    /// semantic tags.
+    #[clap(long)]
+    pub raw: bool,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let config = CameraConfig {
+        name: "front".to_string(),
+        blueprint: "sensor.camera.semantic_segmentation".to_string(),
+        location: [1.5, 0.0, 2.4],
+        rotation: [0.0; 3],
+        width: opts.width,
+        height: opts.height,
+        fov: opts.fov,
+        postprocess: PostProcess::default(),
+    };
+    let ego = sim.ego.clone();
+    let camera = config.spawn(&mut sim.world, &ego)?;
+    save_calibration(opts.output_dir.join("calibration.json"), &[config])?;
+
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let camera_data = sync.register("camera", &camera, |data| Image::try_from(data).unwrap());
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let image = synced.take(&camera_data);
+
+        // The tag is stored in the red channel.
+        let (width, height) = (image.width() as u32, image.height() as u32);
+        let tags: Vec<u8> = image.as_slice().iter().map(|color| color.r).collect();
+
+        let output_dir = opts.output_dir.clone();
+        let raw = opts.raw;
+        writer.submit(move || {
+            let pixels = tags.iter().flat_map(|&tag| semantic::color(tag)).collect();
+            let path = output_dir.join(format!("{frame:06}.png"));
+            RgbImage::from_raw(width, height, pixels)
+                .unwrap()
+                .save(&path)
+                .with_context(|| format!("unable to write {}", path.display()))?;
+
+            if raw {
+                let path = output_dir.join(format!("{frame:06}_labels.png"));
+                GrayImage::from_raw(width, height, tags)
+                    .unwrap()
+                    .save(&path)
+                    .with_context(|| format!("unable to write {}", path.display()))?;
+            }
+
+            println!("saved frame {frame}");
+            Ok(())
+        })?;
+    }
+
+    camera.stop();
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use carla::{client::Sensor, prelude::*, sensor::data::SemanticLidarMeasurement};
+use clap::Parser;
+use nalgebra::{Isometry3, Point3};
+use show::{
+    lidar::LidarConfig,
+    pointcloud::{PointCloud, PointCloudFormat},
+    pose, semantic,
+    sim::{Sim, SimOpts, SENSOR_TIMEOUT},
+    sync::SensorSync,
+    writer::{DiskWriter, WriterOpts},
+};
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+#[derive(Parser)]
+struct Opts {
+    #[clap(flatten)]
+    pub sim: SimOpts,
+
+    #[clap(flatten)]
+    pub writer: WriterOpts,
+
+    #[clap(flatten)]
+    pub lidar: LidarConfig,
+
+    /// The directory to write the point clouds.
+    #[clap(short = 'o', long, default_value = "semantic_lidar")]
+    pub output_dir: PathBuf,
+
+    /// The file format of the point clouds.
+    #[clap(long, value_enum, default_value = "pcd")]
+    pub format: PointCloudFormat,
+
+    /// Save only the points of these classes, e.g. "car,pedestrian".
+    /// All points are saved if not set.
+    #[clap(long, value_delimiter = ',')]
+    pub classes: Vec<String>,
+
+    /// Keep the points in the sensor frame instead of the world
+    /// frame.
+    #[clap(long)]
+    pub sensor_frame: bool,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let lidar_config = opts.lidar.load()?;
+    lidar_config.validate(opts.sim.delta_seconds)?;
+    let tags: Vec<u32> = opts
+        .classes
+        .iter()
+        .map(|name| {
+            semantic::tag(name)
+                .map(u32::from)
+                .with_context(|| format!("unknown semantic class '{name}'"))
+        })
+        .collect::<Result<_>>()?;
+    fs::create_dir_all(&opts.output_dir)
+        .with_context(|| format!("unable to create {}", opts.output_dir.display()))?;
+
+    let mut sim = Sim::new(&opts.sim)?;
+
+    let pose = lidar_config.pose();
+    let ego = sim.ego.clone();
+    let lidar: Sensor = lidar_config
+        .builder(
+            &mut sim.world,
+            "sensor.lidar.ray_cast_semantic",
+            opts.sim.delta_seconds,
+        )?
+        .spawn_sensor_opt(&pose, Some(&ego), None)?;
+
+    let sensor_frame = opts.sensor_frame;
+    let mut sync = SensorSync::new(SENSOR_TIMEOUT);
+    let lidar_data = sync.register("lidar", &lidar, move |data| {
+        let transform = if sensor_frame {
+            Isometry3::identity()
+        } else {
+            pose::from_carla(&data.sensor_transform())
+        };
+        let measure: SemanticLidarMeasurement = data.try_into().unwrap();
+
+        // The object index is the id of the hit actor, or zero for
+        // the static scene.
+        let mut cloud =
+            PointCloud::new(&["x", "y", "z", "cos_inc_angle", "object_idx", "object_tag"]);
+        for detection in measure.as_slice() {
+            if !tags.is_empty() && !tags.contains(&detection.object_tag) {
+                continue;
+            }
+            let point = &detection.point;
+            let point = transform * Point3::new(point.x, point.y, point.z);
+            cloud.push(&[
+                point.x,
+                point.y,
+                point.z,
+                detection.cos_inc_angle,
+                detection.object_idx as f32,
+                detection.object_tag as f32,
+            ]);
+        }
+        cloud
+    });
+
+    let writer = DiskWriter::new(&opts.writer);
+    while sim.is_running() {
+        let frame = sim.tick();
+        let mut synced = match sync.wait(frame) {
+            Ok(synced) => synced,
+            Err(err) => {
+                eprintln!("Skip the frame since {err}");
+                continue;
+            }
+        };
+        let cloud = synced.take(&lidar_data);
+
+        let path = opts
+            .output_dir
+            .join(format!("{frame:06}.{}", opts.format.extension()));
+
+        // Count the points per class
+        let mut counts = BTreeMap::new();
+        for point in cloud.points() {
+            *counts.entry(point[5] as usize).or_insert(0) += 1;
+        }
+        let counts: Vec<String> = counts
+            .into_iter()
+            .map(|(tag, count)| {
+                let name = semantic::CLASSES
+                    .get(tag)
+                    .map_or("unknown", |(name, _)| name);
+                format!("{name}={count}")
+            })
+            .collect();
+
+        let format = opts.format;
+        writer.submit(move || {
+            cloud.save(&path, format)?;
+            println!("frame {frame}: {}", counts.join(" "));
+            Ok(())
+        })?;
+    }
+
+    lidar.stop();
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,333 @@
+//! The camera mounting configuration, calibration and image
+//! conversion.
+//!
+//! The camera frame follows CARLA with x forward, y right and z up.
+//! The intrinsic matrix applies to the optical frame with x right, y
+//! down and z forward, that is `(y, -z, x)` in the camera frame.
+//!
+//! The extrinsics are the geometric pose of the camera in the
+//! left-handed vehicle frame, built from the CARLA angles as described
+//! in [crate::pose], so a camera pitched down has its optical axis
+//! pointing below the horizon.
+
+use crate::{noise::PostProcess, pose};
+use anyhow::{bail, Context, Result};
+use carla::{
+    client::{Sensor, Vehicle, World},
+    sensor::data::Image,
+};
+use image::RgbImage;
+use nalgebra::{
+    Isometry3, Matrix3, Matrix4, Point2, Point3, Rotation3, Translation3, UnitQuaternion, Vector3,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// The far plane of the depth camera in meters.
+pub const DEPTH_FAR_PLANE: f32 = 1000.0;
+
+/// A camera mounted on the ego vehicle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraConfig {
+    /// The name of the camera, also used as the output directory.
+    pub name: String,
+    /// The blueprint of the camera.
+    #[serde(default = "default_blueprint")]
+    pub blueprint: String,
+    /// The location in meters relative to the vehicle.
+    pub location: [f32; 3],
+    /// The roll, pitch and yaw in degrees relative to the vehicle.
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    /// The image width in pixels.
+    #[serde(default = "default_width")]
+    pub width: u32,
+    /// The image height in pixels.
+    #[serde(default = "default_height")]
+    pub height: u32,
+    /// The horizontal field of view in degrees.
+    #[serde(default = "default_fov")]
+    pub fov: f32,
+    /// The post-processing effects of the RGB camera.
+    #[serde(default)]
+    pub postprocess: PostProcess,
+}
+
+impl CameraConfig {
+    /// The pose of the camera relative to the vehicle as carla-rust
+    /// encodes it, which is the pose to spawn the camera at.
+    pub fn pose(&self) -> Isometry3<f32> {
+        let [x, y, z] = self.location;
+        let [roll, pitch, yaw] = self.rotation;
+        Isometry3::from_parts(
+            Translation3::new(x, y, z),
+            UnitQuaternion::from_euler_angles(
+                roll.to_radians(),
+                pitch.to_radians(),
+                yaw.to_radians(),
+            ),
+        )
+    }
+
+    /// The geometric pose of the camera relative to the vehicle.
+    pub fn extrinsics(&self) -> Isometry3<f32> {
+        let [x, y, z] = self.location;
+        let [roll, pitch, yaw] = self.rotation;
+        Isometry3::from_parts(Translation3::new(x, y, z), pose::rotation(roll, pitch, yaw))
+    }
+
+    /// The focal length in pixels.
+    pub fn focal_length(&self) -> f32 {
+        self.width as f32 / (2.0 * (self.fov.to_radians() / 2.0).tan())
+    }
+
+    /// The pinhole intrinsic matrix with the principal point at the
+    /// image center.
+    pub fn intrinsics(&self) -> Matrix3<f32> {
+        let focal = self.focal_length();
+        let cx = self.width as f32 / 2.0;
+        let cy = self.height as f32 / 2.0;
+        Matrix3::new(focal, 0.0, cx, 0.0, focal, cy, 0.0, 0.0, 1.0)
+    }
+
+    /// Project a point in the camera frame onto the image. It returns
+    /// `None` for points behind the camera. The pixel may fall
+    /// outside the image.
+    pub fn project(&self, point: &Point3<f32>) -> Option<Point2<f32>> {
+        if point.x <= f32::EPSILON {
+            return None;
+        }
+        let focal = self.focal_length();
+        let u = self.width as f32 / 2.0 + focal * point.y / point.x;
+        let v = self.height as f32 / 2.0 - focal * point.z / point.x;
+        Some(Point2::new(u, v))
+    }
+
+    /// Whether the pixel lies within the image.
+    pub fn contains(&self, pixel: &Point2<f32>) -> bool {
+        (0.0..self.width as f32).contains(&pixel.x) && (0.0..self.height as f32).contains(&pixel.y)
+    }
+
+    /// Spawn the camera attached to the vehicle.
+    pub fn spawn(&self, world: &mut World, vehicle: &Vehicle) -> Result<Sensor> {
+        let builder = world
+            .actor_builder(&self.blueprint)?
+            .set_attribute("image_size_x", &self.width.to_string())?
+            .set_attribute("image_size_y", &self.height.to_string())?
+            .set_attribute("fov", &self.fov.to_string())?;
+        let camera: Sensor = self
+            .postprocess
+            .apply(builder)?
+            .spawn_sensor_opt(&self.pose(), Some(vehicle), None)
+            .with_context(|| format!("unable to spawn the camera '{}'", self.name))?;
+        Ok(camera)
+    }
+}
+
+/// A set of cameras loaded from a TOML file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rig {
+    #[serde(rename = "camera")]
+    pub cameras: Vec<CameraConfig>,
+}
+
+impl Rig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        let rig: Self =
+            toml::from_str(&text).with_context(|| format!("unable to parse {}", path.display()))?;
+        Ok(rig)
+    }
+}
+
+/// The calibration of a camera for the downstream perception code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Calibration {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// The horizontal field of view in degrees.
+    pub fov: f32,
+    /// The row-major intrinsic matrix in the optical frame.
+    pub intrinsics: [[f32; 3]; 3],
+    /// The row-major homogeneous transform from the camera frame to
+    /// the vehicle frame. Both frames have the left-handed CARLA axes,
+    /// and the rotation turns the points as CARLA does, i.e. the roll
+    /// and the pitch are negated from `from_euler_angles` of the CARLA
+    /// angles.
+    pub extrinsics: [[f32; 4]; 4],
+}
+
+impl Calibration {
+    /// The transform from the camera frame to the vehicle frame.
+    pub fn pose(&self) -> Isometry3<f32> {
+        let matrix = Matrix4::from_fn(|row, col| self.extrinsics[row][col]);
+        let rotation = Rotation3::from_matrix(&matrix.fixed_view::<3, 3>(0, 0).into_owned());
+        let translation = Vector3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+        Isometry3::from_parts(translation.into(), rotation.into())
+    }
+
+    /// Project a point in the vehicle frame onto the image and return
+    /// the pixel with the depth in meters. It returns `None` for
+    /// points behind the camera.
+    pub fn project(&self, point: &Point3<f32>) -> Option<(Point2<f32>, f32)> {
+        let camera = self.pose().inverse() * point;
+        let depth = camera.x;
+        if depth <= f32::EPSILON {
+            return None;
+        }
+
+        let intrinsics = Matrix3::from_fn(|row, col| self.intrinsics[row][col]);
+        let pixel = intrinsics * Vector3::new(camera.y, -camera.z, camera.x) / depth;
+        Some((Point2::new(pixel.x, pixel.y), depth))
+    }
+
+    /// The camera configuration that reproduces the calibration.
+    pub fn to_config(&self, blueprint: &str) -> CameraConfig {
+        let pose = self.pose();
+        let location = pose.translation.vector;
+        CameraConfig {
+            name: self.name.clone(),
+            blueprint: blueprint.to_string(),
+            location: [location.x, location.y, location.z],
+            rotation: pose::carla_angles(&pose.rotation),
+            width: self.width,
+            height: self.height,
+            fov: self.fov,
+            postprocess: PostProcess::default(),
+        }
+    }
+}
+
+impl From<&CameraConfig> for Calibration {
+    fn from(config: &CameraConfig) -> Self {
+        let intrinsics = config.intrinsics();
+        let extrinsics = config.extrinsics().to_homogeneous();
+        Self {
+            name: config.name.clone(),
+            width: config.width,
+            height: config.height,
+            fov: config.fov,
+            intrinsics: std::array::from_fn(|row| {
+                std::array::from_fn(|col| intrinsics[(row, col)])
+            }),
+            extrinsics: std::array::from_fn(|row| {
+                std::array::from_fn(|col| extrinsics[(row, col)])
+            }),
+        }
+    }
+}
+
+/// Write the calibrations of the cameras to a JSON or YAML file
+/// depending on the file extension.
+pub fn save_calibration(path: impl AsRef<Path>, cameras: &[CameraConfig]) -> Result<()> {
+    let path = path.as_ref();
+    let calibrations: Vec<Calibration> = cameras.iter().map(Calibration::from).collect();
+
+    let text = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::to_string_pretty(&calibrations)?,
+        Some("yaml" | "yml") => serde_yaml::to_string(&calibrations)?,
+        _ => bail!("unsupported calibration format {}", path.display()),
+    };
+    fs::write(path, text).with_context(|| format!("unable to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Read the calibrations from a JSON or YAML file written by
+/// [save_calibration].
+pub fn load_calibration(path: impl AsRef<Path>) -> Result<Vec<Calibration>> {
+    let path = path.as_ref();
+    let text =
+        fs::read_to_string(path).with_context(|| format!("unable to read {}", path.display()))?;
+    let calibrations = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&text)?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&text)?,
+        _ => bail!("unsupported calibration format {}", path.display()),
+    };
+    Ok(calibrations)
+}
+
+/// Convert the BGRA image into an RGB image.
+pub fn to_rgb_image(image: &Image) -> RgbImage {
+    let pixels = image
+        .as_slice()
+        .iter()
+        .flat_map(|color| [color.r, color.g, color.b])
+        .collect();
+    RgbImage::from_raw(image.width() as u32, image.height() as u32, pixels).unwrap()
+}
+
+/// Decode the depth in meters from the 24-bit value encoded in the R,
+/// G and B channels of the depth camera.
+pub fn decode_depth(image: &Image) -> Vec<f32> {
+    image
+        .as_slice()
+        .iter()
+        .map(|color| {
+            let value = color.r as f32 + color.g as f32 * 256.0 + color.b as f32 * 65536.0;
+            value / 16_777_215.0 * DEPTH_FAR_PLANE
+        })
+        .collect()
+}
+
+fn default_blueprint() -> String {
+    "sensor.camera.rgb".to_string()
+}
+
+fn default_width() -> u32 {
+    800
+}
+
+fn default_height() -> u32 {
+    600
+}
+
+fn default_fov() -> f32 {
+    90.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pitched_camera() -> CameraConfig {
+        CameraConfig {
+            name: "front".to_string(),
+            blueprint: default_blueprint(),
+            location: [1.5, 0.0, 2.4],
+            rotation: [0.0, -15.0, 10.0],
+            width: 800,
+            height: 600,
+            fov: 90.0,
+            postprocess: PostProcess::default(),
+        }
+    }
+
+    #[test]
+    fn optical_axis_projects_to_principal_point() {
+        let config = pitched_camera();
+        let calibration = Calibration::from(&config);
+
+        // The camera pitched down by 15° looks below the horizon.
+        let axis = config.extrinsics().rotation * Vector3::x();
+        assert!(axis.z < 0.0);
+        assert!((axis.z + 15_f32.to_radians().sin()).abs() < 1e-5);
+
+        let point = Point3::from(Vector3::from(config.location) + axis * 10.0);
+        let (pixel, depth) = calibration.project(&point).unwrap();
+        assert!((pixel.x - 400.0).abs() < 1e-3);
+        assert!((pixel.y - 300.0).abs() < 1e-3);
+        assert!((depth - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn calibration_round_trips_to_config() {
+        let config = pitched_camera();
+        let restored = Calibration::from(&config).to_config(&config.blueprint);
+        for (lhs, rhs) in config.rotation.iter().zip(&restored.rotation) {
+            assert!((lhs - rhs).abs() < 1e-3);
+        }
+    }
+}
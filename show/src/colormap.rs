@@ -0,0 +1,16 @@
+//! Color scales of scalar values.
+
+/// Map a value within [0, 1] to a hue from red to blue. The values
+/// out of the range are clamped.
+pub fn rainbow(value: f32) -> [u8; 3] {
+    let hue = value.clamp(0.0, 1.0) * 4.0;
+    let fract = hue.fract();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, fract, 0.0),
+        1 => (1.0 - fract, 1.0, 0.0),
+        2 => (0.0, 1.0, fract),
+        3 => (0.0, 1.0 - fract, 1.0),
+        _ => (0.0, 0.0, 1.0),
+    };
+    [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8]
+}
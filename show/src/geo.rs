@@ -0,0 +1,52 @@
+//! The geographic coordinates of the map locations.
+//!
+//! The conversion follows `carla::geom::GeoLocation::Transform`, which
+//! the GNSS sensor uses, so that the exported map lines up with the
+//! recorded tracks.
+
+const EARTH_RADIUS: f64 = 6378137.0;
+
+/// The geographic location of the map origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoReference {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl GeoReference {
+    /// Read the origin from the `+lat_0` and `+lon_0` parameters of the
+    /// geoReference in an OpenDRIVE file. A missing parameter is zero
+    /// as in CARLA.
+    pub fn from_open_drive(xodr: &str) -> Self {
+        let parameter = |name: &str| -> f64 {
+            xodr.split_once(name)
+                .and_then(|(_, rest)| {
+                    let end = rest
+                        .find(|c: char| c.is_whitespace() || c == ']' || c == '<')
+                        .unwrap_or(rest.len());
+                    rest[..end].parse().ok()
+                })
+                .unwrap_or(0.0)
+        };
+        Self {
+            latitude: parameter("+lat_0="),
+            longitude: parameter("+lon_0="),
+        }
+    }
+
+    /// Convert a map location in meters to the latitude and longitude
+    /// in degrees.
+    pub fn to_geolocation(&self, x: f64, y: f64) -> (f64, f64) {
+        let scale = self.latitude.to_radians().cos();
+        let radius = EARTH_RADIUS * scale;
+
+        // Offset the map origin on the Mercator projection. The map y
+        // axis points to the south.
+        let mx = radius * self.longitude.to_radians() + x;
+        let my = radius * ((90.0 + self.latitude).to_radians() / 2.0).tan().ln() - y;
+
+        let longitude = (mx / radius).to_degrees();
+        let latitude = 2.0 * (my / radius).exp().atan().to_degrees() - 90.0;
+        (latitude, longitude)
+    }
+}
@@ -0,0 +1,85 @@
+//! The file format of the saved camera images.
+
+use anyhow::{ensure, Context, Result};
+use clap::{Args, ValueEnum};
+use image::{codecs::jpeg::JpegEncoder, RgbImage};
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImageFormat {
+    /// Lossless PNG.
+    Png,
+    /// Lossy JPEG.
+    Jpeg,
+    /// Lossy WebP.
+    Webp,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Webp => "webp",
+        }
+    }
+}
+
+/// The format of the RGB camera images. The label, depth and mask
+/// images are always saved in PNG since they must be lossless.
+#[derive(Debug, Clone, Args)]
+pub struct ImageOpts {
+    /// The file format of the camera images. JPEG and WebP encode much
+    /// faster than PNG on large images.
+    #[clap(long, value_enum, default_value = "png")]
+    pub image_format: ImageFormat,
+
+    /// The quality from 1 to 100 of the JPEG and WebP images.
+    #[clap(long, default_value = "90")]
+    pub quality: u8,
+}
+
+impl ImageOpts {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            (1..=100).contains(&self.quality),
+            "the image quality must be within 1 to 100"
+        );
+        Ok(())
+    }
+
+    /// The name of the image file with the extension of the format.
+    pub fn file_name(&self, stem: &str) -> String {
+        format!("{stem}.{}", self.image_format.extension())
+    }
+
+    /// The path of the image file in `dir`.
+    pub fn path(&self, dir: impl AsRef<Path>, stem: &str) -> PathBuf {
+        dir.as_ref().join(self.file_name(stem))
+    }
+
+    pub fn save(&self, image: &RgbImage, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        match self.image_format {
+            ImageFormat::Png => image.save(path)?,
+            ImageFormat::Jpeg => {
+                let file = File::create(path)
+                    .with_context(|| format!("unable to create {}", path.display()))?;
+                image.write_with_encoder(JpegEncoder::new_with_quality(
+                    BufWriter::new(file),
+                    self.quality,
+                ))?;
+            }
+            ImageFormat::Webp => {
+                let encoder =
+                    webp::Encoder::from_rgb(image.as_raw(), image.width(), image.height());
+                fs::write(path, &*encoder.encode(self.quality as f32))?;
+            }
+        }
+        Ok(())
+    }
+}
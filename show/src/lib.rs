@@ -0,0 +1,15 @@
+pub mod camera;
+pub mod colormap;
+pub mod geo;
+pub mod image_format;
+pub mod lidar;
+pub mod noise;
+pub mod npy;
+pub mod occupancy;
+pub mod pointcloud;
+pub mod pose;
+pub mod semantic;
+pub mod sim;
+pub mod sync;
+pub mod topdown;
+pub mod writer;
@@ -0,0 +1,157 @@
+//! The mounting and scan pattern of the lidar.
+
+use anyhow::{ensure, Context, Result};
+use carla::client::{ActorBuilder, World};
+use clap::Args;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+/// The lidar attributes given by the flags or a TOML file.
+#[derive(Debug, Clone, Args, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LidarConfig {
+    /// A TOML file of the lidar configuration, which replaces the
+    /// lidar flags.
+    #[clap(long)]
+    #[serde(skip)]
+    pub lidar_config: Option<PathBuf>,
+
+    /// The location in meters relative to the vehicle. It can only be
+    /// changed in the configuration file.
+    #[clap(skip = [0.0, 0.0, 2.4])]
+    pub location: [f32; 3],
+
+    /// The number of laser channels.
+    #[clap(long, default_value = "32")]
+    pub channels: u32,
+
+    /// The maximum distance in meters.
+    #[clap(long, default_value = "50.0")]
+    pub range: f32,
+
+    /// The number of points per second over all channels.
+    #[clap(long, default_value = "100000")]
+    pub points_per_second: u32,
+
+    /// The rotation frequency in Hz. It defaults to one revolution
+    /// per tick.
+    #[clap(long)]
+    pub rotation_frequency: Option<f64>,
+
+    /// The angle in degrees of the highest laser.
+    #[clap(long, default_value = "10.0")]
+    pub upper_fov: f32,
+
+    /// The angle in degrees of the lowest laser.
+    #[clap(long, default_value = "-30.0")]
+    pub lower_fov: f32,
+
+    /// The horizontal field of view in degrees.
+    #[clap(long, default_value = "360.0")]
+    pub horizontal_fov: f32,
+}
+
+impl Default for LidarConfig {
+    fn default() -> Self {
+        Self {
+            lidar_config: None,
+            location: [0.0, 0.0, 2.4],
+            channels: 32,
+            range: 50.0,
+            points_per_second: 100000,
+            rotation_frequency: None,
+            upper_fov: 10.0,
+            lower_fov: -30.0,
+            horizontal_fov: 360.0,
+        }
+    }
+}
+
+impl LidarConfig {
+    /// Load the configuration file if given, or keep the flags
+    /// otherwise.
+    pub fn load(&self) -> Result<Self> {
+        let Some(path) = &self.lidar_config else {
+            return Ok(self.clone());
+        };
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        let config: Self =
+            toml::from_str(&text).with_context(|| format!("unable to parse {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// The pose of the lidar relative to the vehicle.
+    pub fn pose(&self) -> Isometry3<f32> {
+        let [x, y, z] = self.location;
+        Isometry3::from_parts(Translation3::new(x, y, z), UnitQuaternion::identity())
+    }
+
+    /// The rotation frequency in Hz with the given time step.
+    pub fn rotation_frequency(&self, delta_seconds: f64) -> f64 {
+        self.rotation_frequency.unwrap_or(1.0 / delta_seconds)
+    }
+
+    /// Check the attributes against the time step. The examples treat
+    /// each measurement as a full sweep, so the lidar must cover the
+    /// horizontal field of view within a tick.
+    pub fn validate(&self, delta_seconds: f64) -> Result<()> {
+        ensure!(
+            self.channels > 0,
+            "the lidar must have at least one channel"
+        );
+        ensure!(self.range > 0.0, "the lidar range must be positive");
+        ensure!(
+            -90.0 <= self.lower_fov && self.lower_fov < self.upper_fov && self.upper_fov <= 90.0,
+            "the lidar vertical field of view [{}, {}] is invalid",
+            self.lower_fov,
+            self.upper_fov
+        );
+        ensure!(
+            self.horizontal_fov > 0.0 && self.horizontal_fov <= 360.0,
+            "the lidar horizontal field of view must be within (0, 360]"
+        );
+
+        let rotation_frequency = self.rotation_frequency(delta_seconds);
+        let revolutions = rotation_frequency * delta_seconds;
+        ensure!(
+            revolutions >= 1.0 - 1e-6,
+            "the lidar rotates {:.0}% of a revolution in a tick of {delta_seconds} s, \
+             which needs a rotation frequency of at least {} Hz",
+            revolutions * 100.0,
+            1.0 / delta_seconds
+        );
+
+        let points_per_channel =
+            self.points_per_second as f64 * delta_seconds / self.channels as f64;
+        ensure!(
+            points_per_channel >= 1.0,
+            "the lidar emits {points_per_channel:.2} points per channel in a tick, \
+             increase the points per second"
+        );
+
+        Ok(())
+    }
+
+    /// Validate the configuration and set the attributes of the lidar
+    /// blueprint.
+    pub fn builder<'a>(
+        &self,
+        world: &'a mut World,
+        blueprint: &str,
+        delta_seconds: f64,
+    ) -> Result<ActorBuilder<'a>> {
+        self.validate(delta_seconds)?;
+        let rotation_frequency = self.rotation_frequency(delta_seconds);
+        world
+            .actor_builder(blueprint)?
+            .set_attribute("channels", &self.channels.to_string())?
+            .set_attribute("range", &self.range.to_string())?
+            .set_attribute("points_per_second", &self.points_per_second.to_string())?
+            .set_attribute("rotation_frequency", &rotation_frequency.to_string())?
+            .set_attribute("upper_fov", &self.upper_fov.to_string())?
+            .set_attribute("lower_fov", &self.lower_fov.to_string())?
+            .set_attribute("horizontal_fov", &self.horizontal_fov.to_string())
+    }
+}
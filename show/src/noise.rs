@@ -0,0 +1,223 @@
+//! The noise and post-processing attributes of the sensor blueprints.
+//!
+//! The attributes left unset keep the defaults of the blueprints. The
+//! GNSS and IMU are noiseless by default, while the lidar drops 45% of
+//! the points and the RGB camera applies the post-processing effects.
+
+use anyhow::Result;
+use carla::client::ActorBuilder;
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct GnssNoise {
+    /// The seed of the GNSS noise generator.
+    #[clap(long = "gnss-seed")]
+    pub seed: Option<i32>,
+
+    /// The latitude bias in degrees.
+    #[clap(long = "gnss-lat-bias")]
+    pub lat_bias: Option<f32>,
+
+    /// The standard deviation of the latitude in degrees.
+    #[clap(long = "gnss-lat-stddev")]
+    pub lat_stddev: Option<f32>,
+
+    /// The longitude bias in degrees.
+    #[clap(long = "gnss-lon-bias")]
+    pub lon_bias: Option<f32>,
+
+    /// The standard deviation of the longitude in degrees.
+    #[clap(long = "gnss-lon-stddev")]
+    pub lon_stddev: Option<f32>,
+
+    /// The altitude bias in meters.
+    #[clap(long = "gnss-alt-bias")]
+    pub alt_bias: Option<f32>,
+
+    /// The standard deviation of the altitude in meters.
+    #[clap(long = "gnss-alt-stddev")]
+    pub alt_stddev: Option<f32>,
+}
+
+impl GnssNoise {
+    pub fn apply<'a>(&self, builder: ActorBuilder<'a>) -> Result<ActorBuilder<'a>> {
+        let builder = set_attribute(builder, "noise_seed", self.seed)?;
+        set_attributes(
+            builder,
+            &[
+                ("noise_lat_bias", self.lat_bias),
+                ("noise_lat_stddev", self.lat_stddev),
+                ("noise_lon_bias", self.lon_bias),
+                ("noise_lon_stddev", self.lon_stddev),
+                ("noise_alt_bias", self.alt_bias),
+                ("noise_alt_stddev", self.alt_stddev),
+            ],
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct ImuNoise {
+    /// The seed of the IMU noise generator.
+    #[clap(long = "imu-seed")]
+    pub seed: Option<i32>,
+
+    /// The standard deviation in m/s² of the accelerometer on every
+    /// axis.
+    #[clap(long = "imu-accel-stddev")]
+    pub accel_stddev: Option<f32>,
+
+    /// The standard deviation in rad/s of the gyroscope on every axis.
+    #[clap(long = "imu-gyro-stddev")]
+    pub gyro_stddev: Option<f32>,
+
+    /// The bias in rad/s of the gyroscope on every axis.
+    #[clap(long = "imu-gyro-bias")]
+    pub gyro_bias: Option<f32>,
+}
+
+impl ImuNoise {
+    pub fn apply<'a>(&self, builder: ActorBuilder<'a>) -> Result<ActorBuilder<'a>> {
+        let mut builder = set_attribute(builder, "noise_seed", self.seed)?;
+        for axis in ["x", "y", "z"] {
+            builder = set_attributes(
+                builder,
+                &[
+                    (&format!("noise_accel_stddev_{axis}"), self.accel_stddev),
+                    (&format!("noise_gyro_stddev_{axis}"), self.gyro_stddev),
+                    (&format!("noise_gyro_bias_{axis}"), self.gyro_bias),
+                ],
+            )?;
+        }
+        Ok(builder)
+    }
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct LidarNoise {
+    /// The fraction of the points dropped at random. The blueprint
+    /// drops 0.45 by default.
+    #[clap(long = "lidar-dropoff-general-rate")]
+    pub dropoff_general_rate: Option<f32>,
+
+    /// The intensity above which no point is dropped.
+    #[clap(long = "lidar-dropoff-intensity-limit")]
+    pub dropoff_intensity_limit: Option<f32>,
+
+    /// The probability to drop the points with zero intensity.
+    #[clap(long = "lidar-dropoff-zero-intensity")]
+    pub dropoff_zero_intensity: Option<f32>,
+
+    /// The standard deviation in meters of the noise along the rays.
+    #[clap(long = "lidar-noise-stddev")]
+    pub noise_stddev: Option<f32>,
+
+    /// The attenuation rate of the intensity per meter.
+    #[clap(long = "lidar-atmosphere-attenuation-rate")]
+    pub atmosphere_attenuation_rate: Option<f32>,
+
+    /// Disable the dropoff, leaving the other attributes as given.
+    #[clap(
+        long = "lidar-no-dropoff",
+        conflicts_with_all = ["dropoff_general_rate", "dropoff_zero_intensity"]
+    )]
+    pub no_dropoff: bool,
+}
+
+impl LidarNoise {
+    pub fn apply<'a>(&self, builder: ActorBuilder<'a>) -> Result<ActorBuilder<'a>> {
+        let (general_rate, zero_intensity) = if self.no_dropoff {
+            (Some(0.0), Some(0.0))
+        } else {
+            (self.dropoff_general_rate, self.dropoff_zero_intensity)
+        };
+        set_attributes(
+            builder,
+            &[
+                ("dropoff_general_rate", general_rate),
+                ("dropoff_intensity_limit", self.dropoff_intensity_limit),
+                ("dropoff_zero_intensity", zero_intensity),
+                ("noise_stddev", self.noise_stddev),
+                (
+                    "atmosphere_attenuation_rate",
+                    self.atmosphere_attenuation_rate,
+                ),
+            ],
+        )
+    }
+}
+
+/// The post-processing effects of the RGB camera, which can also be
+/// given per camera in the rig file.
+#[derive(Debug, Clone, Default, PartialEq, Args, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcess {
+    /// Render the raw images without the post-processing effects.
+    #[clap(long)]
+    pub no_postprocess: bool,
+
+    /// The gamma of the target.
+    #[clap(long)]
+    pub gamma: Option<f32>,
+
+    /// The intensity of the motion blur from 0 to 1.
+    #[clap(long)]
+    pub motion_blur_intensity: Option<f32>,
+
+    /// The intensity of the bloom effect.
+    #[clap(long)]
+    pub bloom_intensity: Option<f32>,
+
+    /// The intensity of the lens flares.
+    #[clap(long)]
+    pub lens_flare_intensity: Option<f32>,
+
+    /// The intensity of the chromatic aberration.
+    #[clap(long)]
+    pub chromatic_aberration_intensity: Option<f32>,
+}
+
+impl PostProcess {
+    pub fn apply<'a>(&self, builder: ActorBuilder<'a>) -> Result<ActorBuilder<'a>> {
+        let builder = if self.no_postprocess {
+            builder.set_attribute("enable_postprocess_effects", "false")?
+        } else {
+            builder
+        };
+        set_attributes(
+            builder,
+            &[
+                ("gamma", self.gamma),
+                ("motion_blur_intensity", self.motion_blur_intensity),
+                ("bloom_intensity", self.bloom_intensity),
+                ("lens_flare_intensity", self.lens_flare_intensity),
+                (
+                    "chromatic_aberration_intensity",
+                    self.chromatic_aberration_intensity,
+                ),
+            ],
+        )
+    }
+}
+
+fn set_attributes<'a>(
+    mut builder: ActorBuilder<'a>,
+    attributes: &[(&str, Option<f32>)],
+) -> Result<ActorBuilder<'a>> {
+    for &(id, value) in attributes {
+        builder = set_attribute(builder, id, value)?;
+    }
+    Ok(builder)
+}
+
+fn set_attribute<'a, T: ToString>(
+    builder: ActorBuilder<'a>,
+    id: &str,
+    value: Option<T>,
+) -> Result<ActorBuilder<'a>> {
+    match value {
+        Some(value) => builder.set_attribute(id, &value.to_string()),
+        None => Ok(builder),
+    }
+}
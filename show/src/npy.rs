@@ -0,0 +1,39 @@
+//! A minimal writer of NumPy `.npy` arrays.
+
+use anyhow::{Context, Result};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Write a little-endian `f32` array of the shape in C order.
+pub fn write_f32(path: impl AsRef<Path>, shape: &[usize], data: &[f32]) -> Result<()> {
+    let path = path.as_ref();
+    let mut writer = BufWriter::new(
+        File::create(path).with_context(|| format!("unable to create {}", path.display()))?,
+    );
+
+    let shape: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+    let shape = match shape.as_slice() {
+        [dim] => format!("({dim},)"),
+        dims => format!("({})", dims.join(", ")),
+    };
+    let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {shape}, }}");
+
+    // Pad the header with spaces so that the data is 64-byte aligned.
+    let prefix_len = 10;
+    let padding = 64 - (prefix_len + header.len() + 1) % 64;
+    header.extend(std::iter::repeat_n(' ', padding % 64));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in data {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
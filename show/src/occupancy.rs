@@ -0,0 +1,157 @@
+//! Ego-centric 2D occupancy grids rasterized from lidar sweeps.
+
+use image::GrayImage;
+use nalgebra::{Isometry3, Point3};
+
+/// The log-odds added to a cell hit by a point.
+const HIT_LOG_ODDS: f32 = 0.85;
+
+/// The log-odds added to a cell crossed by a ray.
+const MISS_LOG_ODDS: f32 = -0.4;
+
+/// The bound of the log-odds of a cell.
+const MAX_LOG_ODDS: f32 = 5.0;
+
+/// A lidar sweep in the world frame.
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    /// The location of the sensor.
+    pub origin: Point3<f32>,
+    pub points: Vec<Point3<f32>>,
+}
+
+/// The parameters of the occupancy grid.
+#[derive(Debug, Clone)]
+pub struct GridParams {
+    /// The cell size in meters.
+    pub resolution: f32,
+    /// The distance in meters from the ego vehicle to the grid
+    /// borders.
+    pub extent: f32,
+    /// The points below this height in meters above the vehicle origin
+    /// are ground and only clear the cells.
+    pub min_height: f32,
+    /// The points above this height in meters are ignored.
+    pub max_height: f32,
+}
+
+/// A square grid centered at the ego vehicle with the x axis pointing
+/// up in the image and the y axis to the right.
+#[derive(Debug, Clone)]
+pub struct OccupancyGrid {
+    pub params: GridParams,
+    /// The number of cells per side.
+    pub size: usize,
+    log_odds: Vec<f32>,
+}
+
+impl OccupancyGrid {
+    pub fn new(params: GridParams) -> Self {
+        let size = (2.0 * params.extent / params.resolution).ceil() as usize;
+        Self {
+            params,
+            size,
+            log_odds: vec![0.0; size * size],
+        }
+    }
+
+    /// Rasterize the sweeps into a grid around the ego pose. The rays
+    /// from the sensor to the points clear the cells they cross.
+    pub fn from_sweeps<'a>(
+        params: GridParams,
+        ego_pose: &Isometry3<f32>,
+        sweeps: impl IntoIterator<Item = &'a Sweep>,
+    ) -> Self {
+        let mut grid = Self::new(params);
+        let to_ego = ego_pose.inverse();
+
+        for sweep in sweeps {
+            let origin = to_ego * sweep.origin;
+            let Some(origin_cell) = grid.cell(&origin) else {
+                continue;
+            };
+
+            for point in &sweep.points {
+                let point = to_ego * point;
+                if point.z > grid.params.max_height {
+                    continue;
+                }
+                let occupied = point.z >= grid.params.min_height;
+
+                // Clip the ray at the border of the grid.
+                let end = grid.cell_clamped(&point);
+                let inside = grid.cell(&point).is_some();
+                grid.trace(origin_cell, end, occupied && inside);
+            }
+        }
+
+        grid
+    }
+
+    /// The occupancy probability of a cell.
+    pub fn probability(&self, row: usize, col: usize) -> f32 {
+        let odds = self.log_odds[row * self.size + col];
+        1.0 - 1.0 / (1.0 + odds.exp())
+    }
+
+    /// Render the grid with occupied cells in black, free cells in
+    /// white and unknown cells in gray.
+    pub fn to_image(&self) -> GrayImage {
+        let size = self.size as u32;
+        GrayImage::from_fn(size, size, |col, row| {
+            let probability = self.probability(row as usize, col as usize);
+            image::Luma([((1.0 - probability) * 255.0) as u8])
+        })
+    }
+
+    /// The (row, col) of the cell containing the point in the ego
+    /// frame.
+    fn cell(&self, point: &Point3<f32>) -> Option<(usize, usize)> {
+        let extent = self.params.extent;
+        let row = (extent - point.x) / self.params.resolution;
+        let col = (point.y + extent) / self.params.resolution;
+        let range = 0.0..self.size as f32;
+        (range.contains(&row) && range.contains(&col)).then_some((row as usize, col as usize))
+    }
+
+    fn cell_clamped(&self, point: &Point3<f32>) -> (usize, usize) {
+        let extent = self.params.extent;
+        let max = (self.size - 1) as f32;
+        let row = ((extent - point.x) / self.params.resolution).clamp(0.0, max);
+        let col = ((point.y + extent) / self.params.resolution).clamp(0.0, max);
+        (row as usize, col as usize)
+    }
+
+    /// Walk the cells from `start` to `end` with Bresenham's algorithm,
+    /// clearing the crossed cells and updating the end cell.
+    fn trace(&mut self, start: (usize, usize), end: (usize, usize), hit: bool) {
+        let (mut row, mut col) = (start.0 as isize, start.1 as isize);
+        let (end_row, end_col) = (end.0 as isize, end.1 as isize);
+        let d_row = (end_row - row).abs();
+        let d_col = -(end_col - col).abs();
+        let step_row = if row < end_row { 1 } else { -1 };
+        let step_col = if col < end_col { 1 } else { -1 };
+        let mut error = d_row + d_col;
+
+        while (row, col) != (end_row, end_col) {
+            self.update(row as usize, col as usize, MISS_LOG_ODDS);
+            let double = 2 * error;
+            if double >= d_col {
+                error += d_col;
+                row += step_row;
+            }
+            if double <= d_row {
+                error += d_row;
+                col += step_col;
+            }
+        }
+
+        let update = if hit { HIT_LOG_ODDS } else { MISS_LOG_ODDS };
+        self.update(end_row as usize, end_col as usize, update);
+    }
+
+    fn update(&mut self, row: usize, col: usize, delta: f32) {
+        let odds = &mut self.log_odds[row * self.size + col];
+        *odds = (*odds + delta).clamp(-MAX_LOG_ODDS, MAX_LOG_ODDS);
+    }
+}
@@ -0,0 +1,175 @@
+//! Minimal ASCII writers of PCD and PLY point clouds.
+
+use anyhow::{ensure, Context, Result};
+use clap::ValueEnum;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PointCloudFormat {
+    /// The Point Cloud Library format.
+    Pcd,
+    /// The Stanford polygon format.
+    Ply,
+}
+
+impl PointCloudFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Pcd => "pcd",
+            Self::Ply => "ply",
+        }
+    }
+}
+
+/// A point cloud with named `f32` fields stored row by row.
+#[derive(Debug, Clone)]
+pub struct PointCloud {
+    pub fields: Vec<&'static str>,
+    pub data: Vec<f32>,
+}
+
+impl PointCloud {
+    pub fn new(fields: &[&'static str]) -> Self {
+        Self {
+            fields: fields.to_vec(),
+            data: vec![],
+        }
+    }
+
+    /// Append a point with one value per field.
+    pub fn push(&mut self, point: &[f32]) {
+        assert_eq!(point.len(), self.fields.len());
+        self.data.extend_from_slice(point);
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &[f32]> {
+        self.data.chunks_exact(self.fields.len())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: PointCloudFormat) -> Result<()> {
+        let path = path.as_ref();
+        ensure!(!self.fields.is_empty(), "the point cloud has no fields");
+
+        let mut writer = BufWriter::new(
+            File::create(path).with_context(|| format!("unable to create {}", path.display()))?,
+        );
+
+        match format {
+            PointCloudFormat::Pcd => self.write_pcd_header(&mut writer)?,
+            PointCloudFormat::Ply => self.write_ply_header(&mut writer)?,
+        }
+
+        for point in self.points() {
+            let line: Vec<String> = point.iter().map(|value| value.to_string()).collect();
+            writeln!(writer, "{}", line.join(" "))?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn write_pcd_header(&self, writer: &mut impl Write) -> Result<()> {
+        let count = self.fields.len();
+        let repeat = |value: &str| vec![value; count].join(" ");
+
+        writeln!(writer, "# .PCD v0.7 - Point Cloud Data file format")?;
+        writeln!(writer, "VERSION 0.7")?;
+        writeln!(writer, "FIELDS {}", self.fields.join(" "))?;
+        writeln!(writer, "SIZE {}", repeat("4"))?;
+        writeln!(writer, "TYPE {}", repeat("F"))?;
+        writeln!(writer, "COUNT {}", repeat("1"))?;
+        writeln!(writer, "WIDTH {}", self.len())?;
+        writeln!(writer, "HEIGHT 1")?;
+        writeln!(writer, "VIEWPOINT 0 0 0 1 0 0 0")?;
+        writeln!(writer, "POINTS {}", self.len())?;
+        writeln!(writer, "DATA ascii")?;
+        Ok(())
+    }
+
+    fn write_ply_header(&self, writer: &mut impl Write) -> Result<()> {
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format ascii 1.0")?;
+        writeln!(writer, "element vertex {}", self.len())?;
+        for field in &self.fields {
+            writeln!(writer, "property float {field}")?;
+        }
+        writeln!(writer, "end_header")?;
+        Ok(())
+    }
+}
+
+/// A voxel grid that merges the points falling into the same voxel
+/// into their centroid. The points are accumulated incrementally so
+/// that the memory is bounded by the number of occupied voxels.
+#[derive(Debug, Clone)]
+pub struct VoxelGrid {
+    pub fields: Vec<&'static str>,
+    /// The voxel size in meters.
+    pub voxel_size: f32,
+    voxels: HashMap<[i32; 3], (Vec<f32>, usize)>,
+}
+
+impl VoxelGrid {
+    /// Create a grid of points whose first three fields are x, y and
+    /// z.
+    pub fn new(fields: &[&'static str], voxel_size: f32) -> Self {
+        assert!(fields.len() >= 3);
+        assert!(voxel_size > 0.0);
+        Self {
+            fields: fields.to_vec(),
+            voxel_size,
+            voxels: HashMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, point: &[f32]) {
+        assert_eq!(point.len(), self.fields.len());
+        let key = [0, 1, 2].map(|axis| (point[axis] / self.voxel_size).floor() as i32);
+        let (sum, count) = self
+            .voxels
+            .entry(key)
+            .or_insert_with(|| (vec![0.0; point.len()], 0));
+        sum.iter_mut()
+            .zip(point)
+            .for_each(|(sum, value)| *sum += value);
+        *count += 1;
+    }
+
+    pub fn extend(&mut self, cloud: &PointCloud) {
+        for point in cloud.points() {
+            self.push(point);
+        }
+    }
+
+    /// The number of occupied voxels.
+    pub fn len(&self) -> usize {
+        self.voxels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    /// Build a point cloud with the centroid of each voxel.
+    pub fn to_point_cloud(&self) -> PointCloud {
+        let mut cloud = PointCloud::new(&self.fields);
+        for (sum, count) in self.voxels.values() {
+            let centroid: Vec<f32> = sum.iter().map(|value| value / *count as f32).collect();
+            cloud.push(&centroid);
+        }
+        cloud
+    }
+}
@@ -0,0 +1,33 @@
+//! The geometric poses of the CARLA transforms.
+//!
+//! carla-rust converts a CARLA transform by building the rotation with
+//! `UnitQuaternion::from_euler_angles(roll, pitch, yaw)` straight from
+//! the angles of the left-handed CARLA frame. The encoding round-trips
+//! to CARLA, but the quaternion turns the points the other way around
+//! the pitch and the roll axes, e.g. a camera pitched down would look
+//! up. The geometric rotation negates the roll and the pitch, and maps
+//! the coordinates of the sensor frame to the parent frame as CARLA
+//! does.
+
+use nalgebra::{Isometry3, UnitQuaternion};
+
+/// The geometric rotation of the CARLA roll, pitch and yaw in degrees.
+pub fn rotation(roll: f32, pitch: f32, yaw: f32) -> UnitQuaternion<f32> {
+    UnitQuaternion::from_euler_angles(-roll.to_radians(), -pitch.to_radians(), yaw.to_radians())
+}
+
+/// Convert a pose encoded by carla-rust, such as the sensor transform
+/// of a measurement, to the geometric pose.
+pub fn from_carla(pose: &Isometry3<f32>) -> Isometry3<f32> {
+    let (roll, pitch, yaw) = pose.rotation.euler_angles();
+    Isometry3::from_parts(
+        pose.translation,
+        UnitQuaternion::from_euler_angles(-roll, -pitch, yaw),
+    )
+}
+
+/// The CARLA roll, pitch and yaw in degrees of a geometric rotation.
+pub fn carla_angles(rotation: &UnitQuaternion<f32>) -> [f32; 3] {
+    let (roll, pitch, yaw) = rotation.euler_angles();
+    [-roll.to_degrees(), -pitch.to_degrees(), yaw.to_degrees()]
+}
@@ -0,0 +1,51 @@
+//! The semantic tags of CARLA and the CityScapes palette.
+
+/// The name and the CityScapes color of each semantic tag of CARLA
+/// 0.9.14, indexed by the tag.
+pub const CLASSES: &[(&str, [u8; 3])] = &[
+    ("unlabeled", [0, 0, 0]),
+    ("road", [128, 64, 128]),
+    ("sidewalk", [244, 35, 232]),
+    ("building", [70, 70, 70]),
+    ("wall", [102, 102, 156]),
+    ("fence", [190, 153, 153]),
+    ("pole", [153, 153, 153]),
+    ("traffic_light", [250, 170, 30]),
+    ("traffic_sign", [220, 220, 0]),
+    ("vegetation", [107, 142, 35]),
+    ("terrain", [152, 251, 152]),
+    ("sky", [70, 130, 180]),
+    ("pedestrian", [220, 20, 60]),
+    ("rider", [255, 0, 0]),
+    ("car", [0, 0, 142]),
+    ("truck", [0, 0, 70]),
+    ("bus", [0, 60, 100]),
+    ("train", [0, 80, 100]),
+    ("motorcycle", [0, 0, 230]),
+    ("bicycle", [119, 11, 32]),
+    ("static", [110, 190, 160]),
+    ("dynamic", [170, 120, 50]),
+    ("other", [55, 90, 80]),
+    ("water", [45, 60, 150]),
+    ("road_line", [157, 234, 50]),
+    ("ground", [81, 0, 81]),
+    ("bridge", [150, 100, 100]),
+    ("rail_track", [230, 150, 140]),
+    ("guard_rail", [180, 165, 180]),
+];
+
+/// The CityScapes color of a tag. Unknown tags are black.
+pub fn color(tag: u8) -> [u8; 3] {
+    CLASSES
+        .get(tag as usize)
+        .map(|(_, color)| *color)
+        .unwrap_or_default()
+}
+
+/// The tag of a class name.
+pub fn tag(name: &str) -> Option<u8> {
+    CLASSES
+        .iter()
+        .position(|(class, _)| *class == name)
+        .map(|tag| tag as u8)
+}
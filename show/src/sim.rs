@@ -0,0 +1,141 @@
+//! The common setup of the sensor examples.
+
+use anyhow::{Context, Result};
+use carla::{
+    client::{Client, Vehicle, World},
+    rpc::EpisodeSettings,
+    traffic_manager::TrafficManager,
+};
+use clap::Args;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The time to wait for the sensor data of a frame.
+pub const SENSOR_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Args)]
+pub struct SimOpts {
+    #[clap(long, default_value = "localhost")]
+    pub addr: String,
+
+    #[clap(long, default_value = "2000")]
+    pub port: u16,
+
+    #[clap(long)]
+    pub world: Option<String>,
+
+    /// The blueprint of the ego vehicle.
+    #[clap(long, default_value = "vehicle.tesla.model3")]
+    pub ego_blueprint: String,
+
+    /// The index of the recommended spawn point of the ego vehicle.
+    #[clap(long, default_value = "0")]
+    pub spawn_point: usize,
+
+    /// The simulation time step in seconds.
+    #[clap(long, default_value = "0.05")]
+    pub delta_seconds: f64,
+
+    /// The port of the traffic manager.
+    #[clap(long, default_value = "8000")]
+    pub tm_port: u16,
+
+    /// The number of ticks to run. Run until Ctrl-C if not set.
+    #[clap(long)]
+    pub frames: Option<usize>,
+}
+
+/// A synchronous simulation with an ego vehicle driven by the
+/// autopilot. The original settings are restored on drop.
+pub struct Sim {
+    pub client: Client,
+    pub world: World,
+    pub traffic_manager: TrafficManager,
+    pub ego: Vehicle,
+    stop: Arc<AtomicBool>,
+    frames: Option<usize>,
+    ticks: usize,
+    orig_settings: EpisodeSettings,
+}
+
+impl Sim {
+    pub fn new(opts: &SimOpts) -> Result<Self> {
+        let client = Client::connect(&opts.addr, opts.port, None);
+        let mut world = match &opts.world {
+            Some(world) => client.load_world(world),
+            None => client.world(),
+        };
+
+        // Use synchronous mode
+        let orig_settings = world.settings();
+        world.apply_settings(
+            &EpisodeSettings {
+                synchronous_mode: true,
+                fixed_delta_seconds: Some(opts.delta_seconds),
+                ..orig_settings.clone()
+            },
+            Duration::ZERO,
+        );
+        let mut traffic_manager = client.instance_tm(opts.tm_port);
+        traffic_manager.set_synchronous_mode(true);
+
+        // Spawn the ego vehicle
+        let spawn_point = world
+            .map()
+            .recommended_spawn_points()
+            .get(opts.spawn_point)
+            .with_context(|| format!("spawn point {} does not exist", opts.spawn_point))?;
+        let ego: Vehicle = world
+            .actor_builder(&opts.ego_blueprint)?
+            .spawn_vehicle(&spawn_point)?;
+        ego.set_autopilot_opt(true, traffic_manager.port());
+
+        // Register a Ctrl-C handler
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let stop = stop.clone();
+            ctrlc::set_handler(move || {
+                stop.store(true, Ordering::SeqCst);
+            })
+            .with_context(|| "Error setting Ctrl-C handler")?;
+        }
+
+        Ok(Self {
+            client,
+            world,
+            traffic_manager,
+            ego,
+            stop,
+            frames: opts.frames,
+            ticks: 0,
+            orig_settings,
+        })
+    }
+
+    /// Whether the run is neither interrupted nor finished.
+    pub fn is_running(&self) -> bool {
+        !self.stop.load(Ordering::SeqCst) && self.frames.is_none_or(|frames| self.ticks < frames)
+    }
+
+    /// Advance the simulation by one step and return the new frame
+    /// number.
+    pub fn tick(&mut self) -> usize {
+        self.ticks += 1;
+        self.world.tick() as usize
+    }
+}
+
+impl Drop for Sim {
+    fn drop(&mut self) {
+        self.ego
+            .set_autopilot_opt(false, self.traffic_manager.port());
+        self.traffic_manager.set_synchronous_mode(false);
+        self.world
+            .apply_settings(&self.orig_settings, Duration::ZERO);
+    }
+}
@@ -0,0 +1,143 @@
+//! Gather the data of several sensors for the same frame.
+
+use anyhow::{bail, Result};
+use carla::{
+    client::Sensor,
+    sensor::{SensorData, SensorDataBase},
+};
+use std::{
+    any::Any,
+    marker::PhantomData,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Collects the data of the registered sensors and blocks the tick
+/// loop until every sensor has delivered the current frame, which
+/// works like the `sensor_synchronization.py` example of CARLA.
+pub struct SensorSync {
+    shared: Arc<Shared>,
+    names: Vec<String>,
+    timeout: Duration,
+}
+
+/// A typed key to take the data of a registered sensor from a
+/// [SyncedFrame].
+pub struct SensorHandle<T> {
+    index: usize,
+    _phantom: PhantomData<T>,
+}
+
+/// The data of all registered sensors for a frame.
+pub struct SyncedFrame {
+    pub frame: usize,
+    data: Vec<Option<Box<dyn Any + Send>>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    slots: Mutex<Vec<Slot>>,
+    arrived: Condvar,
+}
+
+#[derive(Default)]
+struct Slot {
+    frame: Option<usize>,
+    data: Option<Box<dyn Any + Send>>,
+}
+
+impl SensorSync {
+    /// Create an empty synchronizer which fails a frame if some
+    /// sensors do not deliver within `timeout`.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            shared: Arc::default(),
+            names: vec![],
+            timeout,
+        }
+    }
+
+    /// Listen to the sensor and convert its data with `convert` in
+    /// the sensor thread. Only the latest data of each sensor is
+    /// kept.
+    pub fn register<T, F>(
+        &mut self,
+        name: impl Into<String>,
+        sensor: &Sensor,
+        convert: F,
+    ) -> SensorHandle<T>
+    where
+        T: Send + 'static,
+        F: Fn(SensorData) -> T + Send + 'static,
+    {
+        let index = {
+            let mut slots = self.shared.slots.lock().unwrap();
+            slots.push(Slot::default());
+            slots.len() - 1
+        };
+        self.names.push(name.into());
+
+        let shared = self.shared.clone();
+        sensor.listen(move |data| {
+            let frame = data.frame();
+            let data = convert(data);
+            let mut slots = shared.slots.lock().unwrap();
+            slots[index] = Slot {
+                frame: Some(frame),
+                data: Some(Box::new(data)),
+            };
+            shared.arrived.notify_all();
+        });
+
+        SensorHandle {
+            index,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Block until all sensors deliver the frame or a later one. It
+    /// fails with the names of the missing sensors on timeout.
+    pub fn wait(&self, frame: usize) -> Result<SyncedFrame> {
+        let deadline = Instant::now() + self.timeout;
+        let is_ready = |slot: &Slot| {
+            slot.data.is_some() && slot.frame.is_some_and(|data_frame| data_frame >= frame)
+        };
+
+        let mut slots = self.shared.slots.lock().unwrap();
+        while !slots.iter().all(is_ready) {
+            let now = Instant::now();
+            if now >= deadline {
+                let missing: Vec<&str> = slots
+                    .iter()
+                    .zip(&self.names)
+                    .filter(|(slot, _)| !is_ready(slot))
+                    .map(|(_, name)| name.as_str())
+                    .collect();
+                bail!(
+                    "no data from {} is received for frame {frame}",
+                    missing.join(", ")
+                );
+            }
+            slots = self
+                .shared
+                .arrived
+                .wait_timeout(slots, deadline - now)
+                .unwrap()
+                .0;
+        }
+
+        let data = slots.iter_mut().map(|slot| slot.data.take()).collect();
+        Ok(SyncedFrame { frame, data })
+    }
+}
+
+impl SyncedFrame {
+    /// Take the data of the sensor. It panics if the data is taken
+    /// twice or the handle belongs to another synchronizer.
+    pub fn take<T: 'static>(&mut self, handle: &SensorHandle<T>) -> T {
+        let data = self.data[handle.index]
+            .take()
+            .expect("the sensor data is already taken");
+        *data.downcast().expect("the sensor handle has a wrong type")
+    }
+}
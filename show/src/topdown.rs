@@ -0,0 +1,304 @@
+//! Top-down drawing of the road network.
+//!
+//! The drawings follow the CARLA frame viewed from above, with x to
+//! the right and y downwards.
+
+use anyhow::{bail, ensure, Result};
+use carla::{
+    client::{Map, Waypoint},
+    road::{LaneId, RoadId, SectionId},
+};
+use image::{Rgb, RgbImage};
+use nalgebra::{Point2, Vector2};
+use std::collections::HashMap;
+
+/// The largest width or height in pixels of a canvas.
+pub const MAX_SIZE: f32 = 16384.0;
+
+pub const BACKGROUND_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+pub const ROAD_COLOR: Rgb<u8> = Rgb([200, 200, 200]);
+pub const LANE_COLOR: Rgb<u8> = Rgb([150, 150, 150]);
+
+/// A sampled driving lane.
+#[derive(Debug, Clone)]
+pub struct Lane {
+    pub road_id: RoadId,
+    pub section_id: SectionId,
+    pub lane_id: LaneId,
+    pub is_junction: bool,
+    /// The lane width in meters.
+    pub width: f32,
+    /// The center line ordered along the road.
+    pub points: Vec<Point2<f32>>,
+    /// The unit heading of the waypoint at each point.
+    pub directions: Vec<Vector2<f32>>,
+}
+
+impl Lane {
+    /// The lane borders on the left and the right of the heading.
+    pub fn boundaries(&self) -> (Vec<Point2<f32>>, Vec<Point2<f32>>) {
+        self.points
+            .iter()
+            .zip(&self.directions)
+            .map(|(point, direction)| {
+                // The y axis points to the right of the x axis.
+                let right = Vector2::new(-direction.y, direction.x) * (self.width / 2.0);
+                (point - right, point + right)
+            })
+            .unzip()
+    }
+}
+
+/// The driving lanes of a map sampled at a fixed distance.
+#[derive(Debug, Clone)]
+pub struct RoadMap {
+    pub lanes: Vec<Lane>,
+    waypoint_distance: f32,
+}
+
+impl RoadMap {
+    pub fn new(map: &Map, waypoint_distance: f64) -> Result<Self> {
+        ensure!(
+            waypoint_distance > 0.0,
+            "the waypoint distance must be positive"
+        );
+
+        // Group the waypoints by lane and order them along the road.
+        let mut lanes: HashMap<_, Vec<Waypoint>> = HashMap::new();
+        for waypoint in map.generate_waypoints(waypoint_distance).iter() {
+            let key = (
+                waypoint.road_id(),
+                waypoint.section_id(),
+                waypoint.lane_id(),
+            );
+            lanes.entry(key).or_default().push(waypoint);
+        }
+        let mut lanes: Vec<Lane> = lanes
+            .into_iter()
+            .map(|((road_id, section_id, lane_id), mut waypoints)| {
+                waypoints.sort_by(|lhs, rhs| lhs.distance().total_cmp(&rhs.distance()));
+                let (points, directions) = waypoints
+                    .iter()
+                    .map(|waypoint| {
+                        let transform = waypoint.transform();
+                        let translation = transform.translation;
+                        let heading = transform.rotation * nalgebra::Vector3::x();
+                        (
+                            Point2::new(translation.x, translation.y),
+                            Vector2::new(heading.x, heading.y).normalize(),
+                        )
+                    })
+                    .unzip();
+                Lane {
+                    road_id,
+                    section_id,
+                    lane_id,
+                    is_junction: waypoints[0].is_junction(),
+                    width: waypoints[0].lane_width() as f32,
+                    points,
+                    directions,
+                }
+            })
+            .collect();
+        lanes.sort_by_key(|lane| (lane.road_id, lane.section_id, lane.lane_id));
+        ensure!(
+            !lanes.is_empty(),
+            "the map {} has no driving lanes",
+            map.name()
+        );
+
+        Ok(Self {
+            lanes,
+            waypoint_distance: waypoint_distance as f32,
+        })
+    }
+
+    /// The longest distance in meters between the neighboring points
+    /// of a lane. The longer ones are the gaps between the sections.
+    pub fn max_gap(&self) -> f32 {
+        3.0 * self.waypoint_distance
+    }
+
+    /// The corners of the box enclosing the lane center lines.
+    pub fn bounds(&self) -> (Point2<f32>, Point2<f32>) {
+        bounds(self.lanes.iter().flat_map(|lane| &lane.points))
+    }
+
+    /// Draw the lanes at their widths with the center lines.
+    pub fn draw(&self, canvas: &mut Canvas) {
+        let max_gap = self.max_gap();
+        for lane in &self.lanes {
+            canvas.polyline(&lane.points, lane.width, max_gap, ROAD_COLOR);
+        }
+        let line_width = 1.0 / canvas.scale;
+        for lane in &self.lanes {
+            canvas.polyline(&lane.points, line_width, max_gap, LANE_COLOR);
+        }
+    }
+}
+
+/// Split the points into the runs without gaps longer than
+/// `max_gap` meters.
+pub fn pieces(points: &[Point2<f32>], max_gap: f32) -> Vec<&[Point2<f32>]> {
+    let mut pieces = vec![];
+    let mut start = 0;
+    for index in 1..=points.len() {
+        let is_gap = index == points.len() || (points[index] - points[index - 1]).norm() > max_gap;
+        if is_gap {
+            pieces.push(&points[start..index]);
+            start = index;
+        }
+    }
+    pieces
+}
+
+/// The corners of the box enclosing the points.
+pub fn bounds<'a>(points: impl IntoIterator<Item = &'a Point2<f32>>) -> (Point2<f32>, Point2<f32>) {
+    let mut min = Point2::new(f32::INFINITY, f32::INFINITY);
+    let mut max = Point2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for point in points {
+        min = min.inf(point);
+        max = max.sup(point);
+    }
+    (min, max)
+}
+
+/// An image over a region of the map.
+pub struct Canvas {
+    pub image: RgbImage,
+    /// The map location in meters at the top-left corner.
+    pub origin: Point2<f32>,
+    /// The pixels per meter.
+    pub scale: f32,
+}
+
+impl Canvas {
+    /// Create a blank canvas of the size in pixels centered at a map
+    /// location.
+    pub fn centered(center: Point2<f32>, width: u32, height: u32, scale: f32) -> Self {
+        let half_size = Vector2::new(width as f32, height as f32) / (2.0 * scale);
+        Self {
+            image: RgbImage::from_pixel(width, height, BACKGROUND_COLOR),
+            origin: center - half_size,
+            scale,
+        }
+    }
+
+    /// Create a blank canvas covering the box with a margin in meters.
+    pub fn fit(min: Point2<f32>, max: Point2<f32>, margin: f32, scale: f32) -> Result<Self> {
+        let size = (max - min).add_scalar(2.0 * margin) * scale;
+        if size.x > MAX_SIZE || size.y > MAX_SIZE {
+            bail!(
+                "the image of {:.0}x{:.0} pixels is too large, decrease the scale",
+                size.x,
+                size.y
+            );
+        }
+        Ok(Self::centered(
+            nalgebra::center(&min, &max),
+            size.x as u32,
+            size.y as u32,
+            scale,
+        ))
+    }
+
+    pub fn to_pixel(&self, point: &Point2<f32>) -> Point2<f32> {
+        Point2::from((point - self.origin) * self.scale)
+    }
+
+    /// Copy another canvas of the same scale onto this one.
+    pub fn overlay(&mut self, other: &Canvas) {
+        let offset = (other.origin - self.origin) * self.scale;
+        image::imageops::overlay(
+            &mut self.image,
+            &other.image,
+            offset.x.round() as i64,
+            offset.y.round() as i64,
+        );
+    }
+
+    /// Draw the line segments of `width` meters, skipping the ones
+    /// longer than `max_gap` meters.
+    pub fn polyline(&mut self, points: &[Point2<f32>], width: f32, max_gap: f32, color: Rgb<u8>) {
+        let radius = (width * self.scale / 2.0).max(0.5);
+        for pair in points.windows(2) {
+            let length = (pair[1] - pair[0]).norm();
+            if length > max_gap {
+                continue;
+            }
+            let from = self.to_pixel(&pair[0]);
+            let to = self.to_pixel(&pair[1]);
+
+            // Stamp discs along the segment at every pixel.
+            let steps = ((to - from).norm().ceil() as usize).max(1);
+            for step in 0..=steps {
+                let center = from + (to - from) * (step as f32 / steps as f32);
+                self.stamp(&center, radius, color);
+            }
+        }
+    }
+
+    /// Draw a disc with the radius in pixels at a map location.
+    pub fn disc(&mut self, point: &Point2<f32>, radius: f32, color: Rgb<u8>) {
+        let center = self.to_pixel(point);
+        self.stamp(&center, radius, color);
+    }
+
+    /// Fill a convex polygon given in map locations.
+    pub fn polygon(&mut self, corners: &[Point2<f32>], color: Rgb<u8>) {
+        let corners: Vec<_> = corners.iter().map(|point| self.to_pixel(point)).collect();
+        let (min, max) = bounds(&corners);
+        let Some((min_x, max_x, min_y, max_y)) = self.clip(min, max) else {
+            return;
+        };
+
+        // A pixel is inside if it is on the same side of every edge.
+        let side = |x: f32, y: f32| {
+            let mut signs = corners
+                .iter()
+                .zip(corners.iter().cycle().skip(1))
+                .map(|(from, to)| {
+                    let edge = to - from;
+                    edge.x * (y - from.y) - edge.y * (x - from.x) >= 0.0
+                });
+            let first = signs.next();
+            signs.all(|sign| Some(sign) == first)
+        };
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if side(x as f32 + 0.5, y as f32 + 0.5) {
+                    self.image.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    fn stamp(&mut self, center: &Point2<f32>, radius: f32, color: Rgb<u8>) {
+        let extent = Vector2::repeat(radius);
+        let Some((min_x, max_x, min_y, max_y)) = self.clip(center - extent, center + extent) else {
+            return;
+        };
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let (dx, dy) = (x as f32 + 0.5 - center.x, y as f32 + 0.5 - center.y);
+                if dx * dx + dy * dy <= radius * radius {
+                    self.image.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// The pixel ranges of a box in pixels within the image.
+    fn clip(&self, min: Point2<f32>, max: Point2<f32>) -> Option<(u32, u32, u32, u32)> {
+        let (width, height) = (self.image.width() as f32, self.image.height() as f32);
+        if max.x < 0.0 || max.y < 0.0 || min.x >= width || min.y >= height {
+            return None;
+        }
+        Some((
+            min.x.max(0.0) as u32,
+            max.x.min(width - 1.0) as u32,
+            min.y.max(0.0) as u32,
+            max.y.min(height - 1.0) as u32,
+        ))
+    }
+}
@@ -0,0 +1,202 @@
+//! Write the output files in worker threads.
+//!
+//! Encoding and writing the sensor data can take longer than a tick,
+//! so the examples queue the writes and return to ticking. When the
+//! queue is full, the tick thread waits for the workers, and the wait
+//! is reported as the backpressure.
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+type Job = Box<dyn FnOnce() -> Result<()> + Send>;
+
+#[derive(Debug, Clone, Args)]
+pub struct WriterOpts {
+    /// The maximum number of pending writes before the simulation
+    /// waits for the disk.
+    #[clap(long, default_value = "64")]
+    pub max_queue: usize,
+
+    /// The number of writer threads.
+    #[clap(long, default_value = "2")]
+    pub writer_threads: usize,
+}
+
+/// A pool of threads that run the queued writes.
+pub struct DiskWriter {
+    sender: SyncSender<Job>,
+    workers: Vec<JoinHandle<()>>,
+    max_queue: usize,
+    pending: Arc<AtomicUsize>,
+    error: Arc<Mutex<Option<anyhow::Error>>>,
+    stats: Mutex<Stats>,
+}
+
+/// The backpressure on the tick thread.
+#[derive(Debug, Default)]
+struct Stats {
+    jobs: usize,
+    peak_queue: usize,
+    waits: usize,
+    waited: Duration,
+}
+
+impl DiskWriter {
+    pub fn new(opts: &WriterOpts) -> Self {
+        let max_queue = opts.max_queue.max(1);
+        let (sender, receiver) = mpsc::sync_channel::<Job>(max_queue);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let pending = Arc::new(AtomicUsize::new(0));
+        let error = Arc::new(Mutex::new(None));
+
+        let workers = (0..opts.writer_threads.max(1))
+            .map(|_| {
+                let receiver = receiver.clone();
+                let pending = pending.clone();
+                let error = error.clone();
+                thread::spawn(move || work(&receiver, &pending, &error))
+            })
+            .collect();
+
+        Self {
+            sender,
+            workers,
+            max_queue,
+            pending,
+            error,
+            stats: Mutex::new(Stats::default()),
+        }
+    }
+
+    /// Queue a write. It waits while the queue is full, and returns
+    /// the error of a failed write so that the example stops. The
+    /// error is kept for the workers and [`finish`](Self::finish).
+    pub fn submit(&self, job: impl FnOnce() -> Result<()> + Send + 'static) -> Result<()> {
+        if let Some(err) = &*self.error.lock().unwrap() {
+            return Err(anyhow!("{err:#}"));
+        }
+
+        let mut stats = self.stats.lock().unwrap();
+        let pending = self.pending.fetch_add(1, Ordering::SeqCst) + 1;
+        stats.jobs += 1;
+        stats.peak_queue = stats.peak_queue.max(pending);
+
+        let job: Job = Box::new(job);
+        match self.sender.try_send(job) {
+            Ok(()) => {}
+            Err(TrySendError::Full(job)) => {
+                if stats.waits == 0 {
+                    eprintln!(
+                        "The queue of {} writes is full, the simulation waits for the disk",
+                        self.max_queue
+                    );
+                }
+                let since = Instant::now();
+                self.sender
+                    .send(job)
+                    .map_err(|_| anyhow!("the writer threads are gone"))?;
+                stats.waits += 1;
+                stats.waited += since.elapsed();
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                return Err(anyhow!("the writer threads are gone"))
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for the queued writes and print the backpressure.
+    pub fn finish(self) -> Result<()> {
+        let Self {
+            sender,
+            workers,
+            max_queue,
+            error,
+            stats,
+            ..
+        } = self;
+        drop(sender);
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let stats = stats.into_inner().unwrap();
+        eprintln!(
+            "Wrote {} times, the queue peaked at {} of {max_queue}, \
+             and the simulation waited {} times for {:.2} s",
+            stats.jobs,
+            stats.peak_queue,
+            stats.waits,
+            stats.waited.as_secs_f64()
+        );
+
+        let error = error.lock().unwrap().take();
+        match error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Run the jobs until the queue is closed. The first error is kept
+/// and the later jobs are dropped.
+fn work(
+    receiver: &Mutex<Receiver<Job>>,
+    pending: &AtomicUsize,
+    error: &Mutex<Option<anyhow::Error>>,
+) {
+    loop {
+        let job = receiver.lock().unwrap().recv();
+        let Ok(job) = job else {
+            break;
+        };
+        let failed = error.lock().unwrap().is_some();
+        if !failed {
+            if let Err(err) = job() {
+                error.lock().unwrap().get_or_insert(err);
+            }
+        }
+        pending.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+
+    #[test]
+    fn failed_write_stops_the_later_writes() {
+        let writer = DiskWriter::new(&WriterOpts {
+            max_queue: 4,
+            writer_threads: 1,
+        });
+        writer.submit(|| bail!("disk full")).unwrap();
+        while writer.pending.load(Ordering::SeqCst) > 0 {
+            thread::yield_now();
+        }
+
+        assert!(writer.submit(|| Ok(())).is_err());
+        // The error stays for the workers and the final report.
+        let ran = Arc::new(AtomicUsize::new(0));
+        for _ in 0..2 {
+            let ran = ran.clone();
+            let _ = writer.submit(move || {
+                ran.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        }
+        let err = writer.finish().unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+        assert_eq!(ran.load(Ordering::SeqCst), 0);
+    }
+}